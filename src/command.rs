@@ -1,18 +1,35 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand, ValueHint};
+use anyhow::Result;
+use clap::{
+    parser::ValueSource, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand,
+    ValueHint,
+};
 use time::Date;
 
 use crate::{
-    models::{OrderByEnum, TaskStatusEnum},
-    utils::{category_parser, created_at_parser, date_parser, info_parser, title_parser},
+    models::{
+        ActionKindEnum, DeadlineEnum, DeleteModeEnum, OrderByEnum, OutputFormatEnum,
+        RecurrenceEnum, TaskColumnEnum, TaskCursor, TaskFilter, TaskPriorityEnum,
+        TaskSortColumnEnum, TaskSortKey, TaskStatusEnum, ViewQuery,
+    },
+    utils::{
+        age_parser, category_parser, created_at_parser, cursor_parser, date_parser,
+        deadline_parser, filter_parser, info_parser, period_end_parser, recurrence_parser,
+        sort_key_parser, text_parser, title_parser, view_name_parser,
+    },
 };
 
 /**
  * The CLI parser of arguments
  */
 #[derive(Parser, Debug)]
-#[command(version, about = "A CLI application to manage TODO tasks", long_about = None)]
+#[command(
+    version,
+    about = "A CLI application to manage TODO tasks",
+    long_about = None,
+    after_help = "Environment:\n  TODO_CLI_UTC_OFFSET  UTC offset that defines the current day, e.g. +02:00 [default: system offset]"
+)]
 pub struct RootCommand {
     #[arg(
         short,
@@ -24,10 +41,21 @@ pub struct RootCommand {
     )]
     pub file: PathBuf,
 
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "text",
+        global = true,
+        help = "Output format, see docs/json-output.md for the JSON schema"
+    )]
+    pub output: OutputFormatEnum,
+
     #[command(subcommand)]
     pub command: RootCommandsEnum,
 }
 
+// parsed once per run, boxing the task commands would not save anything
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum RootCommandsEnum {
     #[command(about = "All operations for tasks")]
@@ -39,19 +67,82 @@ pub enum RootCommandsEnum {
     Undo {
         #[arg(short, long, help = "Force operation without confirmation")]
         force: bool,
+        #[arg(long, value_name = "STEPS", help = "Number of operations to undo", default_value = "1", conflicts_with = "to", value_parser = clap::value_parser!(u64).range(1..))]
+        steps: u64,
+        #[arg(long, value_name = "ACTION ID", help = "Undo every operation down to and including this action", value_parser = clap::value_parser!(i64).range(1..))]
+        to: Option<i64>,
     },
     #[command(about = "Redo last undo operation")]
     Redo {
         #[arg(short, long, help = "Force operation without confirmation")]
         force: bool,
+        #[arg(long, value_name = "STEPS", help = "Number of undone operations to redo", default_value = "1", conflicts_with = "to", value_parser = clap::value_parser!(u64).range(1..))]
+        steps: u64,
+        #[arg(long, value_name = "ACTION ID", help = "Redo every undone operation up to and including this action", value_parser = clap::value_parser!(i64).range(1..))]
+        to: Option<i64>,
     },
-    #[command(about = "List last actions")]
+    #[command(about = "List last actions", args_conflicts_with_subcommands = true)]
     Actions {
         #[arg(short, long, value_name = "LIMIT", help = "Number of items to show", default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
         limit: u64,
+        #[arg(long, value_name = "TASK ID", help = "Filter by actions that changed a task", value_parser = clap::value_parser!(i64).range(1..))]
+        task: Option<i64>,
+        #[arg(long, value_name = "KIND", help = "Filter by kind of action")]
+        kind: Option<ActionKindEnum>,
+        #[arg(long, value_name = "DATE", help = "Filter by actions logged on or after a day", value_parser = date_parser)]
+        since: Option<Date>,
+        #[arg(long, value_name = "DATE", help = "Filter by actions logged on or before a day", value_parser = date_parser)]
+        until: Option<Date>,
+        #[arg(
+            long,
+            value_name = "RESTORED",
+            help = "Filter by undone (true) or applied (false) actions"
+        )]
+        restored: Option<bool>,
+        #[command(subcommand)]
+        command: Option<ActionsCommandsEnum>,
+    },
+    #[command(
+        about = "1) Delete archived, 2) Archive completed tasks, 3) Delete actions log, or only the selected steps"
+    )]
+    Housekeeping {
+        #[arg(
+            long,
+            value_name = "AGE",
+            help = "Delete the archived tasks last modified at least AGE ago (e.g. 90d, 12w, 3m, 1y)",
+            value_parser = age_parser
+        )]
+        purge_archived_older_than: Option<Date>,
+        #[arg(
+            long,
+            value_name = "AGE",
+            help = "Archive the done tasks last modified at least AGE ago (e.g. 14d, 2w, 1m, 1y)",
+            value_parser = age_parser
+        )]
+        archive_done_older_than: Option<Date>,
+        #[arg(
+            long,
+            value_name = "COUNT",
+            help = "Delete the actions log but its last COUNT actions"
+        )]
+        keep_actions: Option<u64>,
+        #[arg(long, help = "Report what would change without changing anything")]
+        dry_run: bool,
+        #[arg(short, long, help = "Force operation without confirmation")]
+        force: bool,
+    },
+    #[command(about = "Schema version of the data file and its migrations")]
+    Db {
+        #[command(subcommand)]
+        command: DbCommandsEnum,
+    },
+    #[command(about = "Rebuild the full text search index from the tasks and their categories")]
+    Reindex,
+    #[command(about = "Saved task list queries, shared through the data file")]
+    View {
+        #[command(subcommand)]
+        command: ViewCommandsEnum,
     },
-    #[command(about = "1) Delete archived, 2) Delete actions log, 3) Archive all completed tasks")]
-    Housekeeping,
     #[command(about = "All operations for task categories")]
     Category {
         #[command(subcommand)]
@@ -112,35 +203,53 @@ pub enum TaskCommandsEnum {
             short,
             long,
             value_name = "DEADLINE",
-            help = "Deadline date of the task",
-            value_parser = date_parser
+            help = "Deadline of the task, e.g. 2024-01-31, tomorrow 14:00, +3d, next monday at 9:30, end of month",
+            value_parser = deadline_parser
         )]
-        deadline: Option<Date>,
+        deadline: Option<DeadlineEnum>,
         #[arg(short, long, value_name = "CATEGORY", help = "Categories of the task", value_parser = category_parser)]
         categories: Option<Vec<String>>,
         #[arg(long, short, value_name = "STATUS", help = "Status of the task", default_value=TaskStatusEnum::Undone)]
         status: TaskStatusEnum,
+        #[arg(long, short, value_name = "PRIORITY", help = "Priority of the task", default_value=TaskPriorityEnum::Normal)]
+        priority: TaskPriorityEnum,
+        #[arg(long, value_name = "PARENT ID", help = "Parent task of the new subtask", value_parser = clap::value_parser!(i64).range(1..))]
+        parent: Option<i64>,
+        #[arg(
+            long,
+            value_name = "RULE",
+            help = "Recurrence of the task: daily, weekly:mon,thu, monthly:15 or every:3d",
+            value_parser = recurrence_parser
+        )]
+        repeat: Option<RecurrenceEnum>,
         #[arg(
             long,
             short = 'a',
             value_name = "DATE",
-            help = "Creation date of the task",
+            help = "Creation date of the task, e.g. 2024-01-31, yesterday, -2w",
             default_value = "NOW",
             value_parser = created_at_parser
         )]
         date: Date,
     },
-    #[command(about = "Delete an existing task")]
+    #[command(about = "Delete existing tasks, undone as a single operation")]
     Delete {
-        #[arg(index = 1, value_name = "ID", help = "The target task id", value_parser = clap::value_parser!(i64).range(1..))]
-        id: i64,
+        #[arg(index = 1, value_name = "ID", help = "The target task ids", required = true, num_args = 1.., value_parser = clap::value_parser!(i64).range(1..))]
+        ids: Vec<i64>,
+        #[arg(
+            long,
+            value_name = "MODE",
+            help = "What to do with the subtasks of the task",
+            default_value = "refuse"
+        )]
+        children: DeleteModeEnum,
         #[arg(short, long, help = "Force operation without confirmation")]
         force: bool,
     },
-    #[command(about = "Update an existing task")]
+    #[command(about = "Update existing tasks, undone as a single operation")]
     Update {
-        #[arg(index = 1, value_name = "ID", help = "The target task id", value_parser = clap::value_parser!(i64).range(1..))]
-        id: i64,
+        #[arg(index = 1, value_name = "ID", help = "The target task ids", required = true, num_args = 1.., value_parser = clap::value_parser!(i64).range(1..))]
+        ids: Vec<i64>,
         #[arg(long, short, value_name = "TITLE", help = "Title of the task", value_parser = title_parser)]
         title: Option<String>,
         #[arg(short, long, value_name = "INFO", help = "Info of the task", value_parser = info_parser)]
@@ -149,16 +258,24 @@ pub enum TaskCommandsEnum {
             short,
             long,
             value_name = "DEADLINE",
-            help = "Deadline date of the task"
+            help = "Deadline of the task, e.g. 2024-01-31, tomorrow 14:00, +3d, next monday, empty to clear"
         )]
         deadline: Option<String>,
         #[arg(long, short, value_name = "STATUS", help = "Status of the task")]
         status: Option<TaskStatusEnum>,
+        #[arg(long, short, value_name = "PRIORITY", help = "Priority of the task")]
+        priority: Option<TaskPriorityEnum>,
+        #[arg(
+            long,
+            value_name = "RULE",
+            help = "Recurrence of the task, empty to stop repeating"
+        )]
+        repeat: Option<String>,
         #[arg(
             long,
             short = 'a',
             value_name = "DATE",
-            help = "Creation date of the task",
+            help = "Creation date of the task, e.g. 2024-01-31, yesterday, -2w",
             value_parser = created_at_parser
         )]
         date: Option<Date>,
@@ -166,54 +283,608 @@ pub enum TaskCommandsEnum {
         force: bool,
     },
     #[command(about = "List all the tasks based on query filters")]
-    List {
-        #[arg(long, short, value_name = "STATUS", help = "Filter by status")]
-        status: Option<TaskStatusEnum>,
-
-        #[arg(short, long, value_name = "CATEGORY", help = "Filter by categories", value_parser = category_parser)]
-        categories: Option<Vec<String>>,
-
+    List(ListArgs),
+    #[command(
+        about = "Search tasks by title, info and categories, the best matches first",
+        long_about = "Search tasks by title, info and categories, the best matches first.\n\
+            Words and \"phrases\" must all match, OR between terms matches either side, \
+            a trailing * matches words by prefix (deplo*), title:, info: and cat: search a single column, \
+            a leading - or NOT excludes a term."
+    )]
+    Search {
         #[arg(
-            long,
-            short,
-            value_name = "TEXT",
-            help = "Filter by searching title, info"
+            index = 1,
+            value_name = "QUERY",
+            help = "The search, e.g. 'deplo* \"login bug\" title:api -draft'",
+            required = true
         )]
-        text: Option<String>,
+        query: Vec<String>,
+
+        #[arg(long, short, value_name = "STATUS", help = "Filter by status")]
+        status: Option<TaskStatusEnum>,
 
         #[arg(short, long, value_name = "LIMIT", help = "Number of items to show", default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
         limit: u64,
+    },
+    #[command(about = "Mark that a task cannot start until another task is done")]
+    Depend {
+        #[arg(index = 1, value_name = "ID", help = "The target task id", value_parser = clap::value_parser!(i64).range(1..))]
+        id: i64,
+        #[arg(index = 2, value_name = "DEPENDS ON ID", help = "The task it depends on", value_parser = clap::value_parser!(i64).range(1..))]
+        depends_on_id: i64,
+    },
+    #[command(about = "Remove a dependency between two tasks")]
+    Undepend {
+        #[arg(index = 1, value_name = "ID", help = "The target task id", value_parser = clap::value_parser!(i64).range(1..))]
+        id: i64,
+        #[arg(index = 2, value_name = "DEPENDS ON ID", help = "The task it depends on", value_parser = clap::value_parser!(i64).range(1..))]
+        depends_on_id: i64,
+    },
+    #[command(about = "Read an existing task")]
+    Read {
+        #[arg(index = 1, value_name = "ID", help = "The target task id", value_parser = clap::value_parser!(i64).range(1..))]
+        id: i64,
+    },
+    #[command(about = "Show the timeline of the changes made to a task, deleted tasks included")]
+    History {
+        #[arg(index = 1, value_name = "ID", help = "The target task id", value_parser = clap::value_parser!(i64).range(1..))]
+        id: i64,
+    },
+}
 
-        #[arg(
-            long,
-            short = 'a',
-            value_name = "SORT CREATED AT",
-            help = "Sort by created at"
-        )]
-        sort_created_at: Option<OrderByEnum>,
+/**
+ * Arguments of `task list`, also given to saved views
+ */
+#[derive(Args, Debug, Clone)]
+pub struct ListArgs {
+    #[arg(long, short, value_name = "STATUS", help = "Filter by status")]
+    pub status: Option<TaskStatusEnum>,
+
+    #[arg(long, short, value_name = "PRIORITY", help = "Filter by priority")]
+    pub priority: Option<TaskPriorityEnum>,
+
+    #[arg(short, long, visible_alias = "any-categories", value_name = "CATEGORY", help = "Filter by tasks having any of the categories", value_parser = category_parser)]
+    pub categories: Option<Vec<String>>,
+
+    #[arg(long, value_name = "CATEGORY", help = "Filter by tasks having all of the categories", value_parser = category_parser)]
+    pub all_categories: Option<Vec<String>>,
+
+    #[arg(long, value_name = "CATEGORY", help = "Filter by tasks having none of the categories", value_parser = category_parser)]
+    pub exclude_categories: Option<Vec<String>>,
+
+    #[arg(
+        long,
+        short,
+        value_name = "TEXT",
+        help = "Filter by searching title, info, categories, with the syntax of `task search`",
+        value_parser = text_parser
+    )]
+    pub text: Option<String>,
+
+    #[arg(
+        long = "where",
+        value_name = "EXPR",
+        help = "Filter with an expression, e.g. 'status:undone and (cat:backend or cat:ops) and due<+7d and text:\"login bug\"'",
+        long_help = "Filter with an expression, e.g. 'status:undone and (cat:backend or cat:ops) and due<+7d and text:\"login bug\"'.\n\
+            Terms are field:value, or field<value with <, <=, > and >= for priority and dates.\n\
+            Fields: status, priority, cat, text, due (a date or none), created, updated, is (overdue, blocked or ready).\n\
+            Terms combine with and, or, not and parentheses, bare words search title, info and categories.",
+        value_parser = filter_parser
+    )]
+    pub filter: Option<TaskFilter>,
+
+    #[arg(long, value_name = "DATE", help = "Filter by tasks due before a day", value_parser = date_parser, conflicts_with_all = ["due_today", "due_within", "no_deadline"])]
+    pub due_before: Option<Date>,
+
+    #[arg(long, value_name = "DATE", help = "Filter by tasks due after a day", value_parser = date_parser, conflicts_with_all = ["due_today", "due_within", "no_deadline"])]
+    pub due_after: Option<Date>,
+
+    #[arg(
+        long,
+        help = "Filter by undone tasks whose deadline has passed",
+        conflicts_with = "no_deadline"
+    )]
+    pub overdue: bool,
+
+    #[arg(
+        long,
+        help = "Filter by tasks due today",
+        conflicts_with_all = ["due_within", "no_deadline"]
+    )]
+    pub due_today: bool,
+
+    #[arg(long, value_name = "PERIOD", help = "Filter by tasks due from today until the end of a period, e.g. 7d, 2w, 1m or a date", value_parser = period_end_parser, conflicts_with = "no_deadline")]
+    pub due_within: Option<Date>,
+
+    #[arg(long, help = "Filter by tasks without a deadline")]
+    pub no_deadline: bool,
+
+    #[arg(long, value_name = "DATE", help = "Filter by tasks created on or after a day", value_parser = date_parser)]
+    pub created_since: Option<Date>,
+
+    #[arg(long, value_name = "DATE", help = "Filter by tasks updated on or after a day", value_parser = date_parser)]
+    pub updated_since: Option<Date>,
+
+    #[arg(
+        long,
+        help = "Show only tasks waiting on a task that is not done",
+        conflicts_with = "ready"
+    )]
+    pub blocked: bool,
+
+    #[arg(long, help = "Show only undone tasks whose dependencies are all done")]
+    pub ready: bool,
+
+    #[arg(short, long, value_name = "LIMIT", help = "Number of items to show", default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
+    pub limit: u64,
+
+    #[arg(
+        long,
+        value_name = "OFFSET",
+        help = "Number of matching tasks to skip",
+        conflicts_with_all = ["page", "after"]
+    )]
+    pub offset: Option<u64>,
+
+    #[arg(long, value_name = "PAGE", help = "Page to show, pages hold LIMIT tasks", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "after")]
+    pub page: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "CURSOR",
+        help = "Show the tasks after a cursor printed by a previous page, stable when tasks are added",
+        value_parser = cursor_parser
+    )]
+    pub after: Option<TaskCursor>,
+
+    #[arg(
+        long,
+        value_name = "KEYS",
+        help = "Sort keys in order, e.g. deadline:asc,priority:desc,title. Missing values go last unless `:nulls-first` is added, ties are ordered by id. Sorted subtasks are not grouped under their parent",
+        value_delimiter = ',',
+        value_parser = sort_key_parser
+    )]
+    pub sort: Vec<TaskSortKey>,
+
+    #[arg(
+        long,
+        short = 'a',
+        value_name = "SORT CREATED AT",
+        help = "Sort by created at, same as --sort created:<ORDER>",
+        conflicts_with = "sort"
+    )]
+    pub sort_created_at: Option<OrderByEnum>,
+
+    #[arg(
+        long,
+        short = 'u',
+        value_name = "SORT UPDATE AT",
+        help = "Sort by updated at, same as --sort updated:<ORDER>",
+        conflicts_with = "sort"
+    )]
+    pub sort_updated_at: Option<OrderByEnum>,
+
+    #[arg(
+        long,
+        short = 'd',
+        value_name = "SORT DEADLINE",
+        help = "Sort by deadline, same as --sort deadline:<ORDER>",
+        conflicts_with = "sort"
+    )]
+    pub sort_deadline: Option<OrderByEnum>,
+
+    #[arg(
+        long,
+        short = 'o',
+        value_name = "SORT TITLE",
+        help = "Sort by title, same as --sort title:<ORDER>",
+        conflicts_with = "sort"
+    )]
+    pub sort_title: Option<OrderByEnum>,
+
+    #[arg(
+        long,
+        short = 'r',
+        value_name = "SORT PRIORITY",
+        help = "Sort by priority, same as --sort priority:<ORDER>",
+        conflicts_with = "sort"
+    )]
+    pub sort_priority: Option<OrderByEnum>,
+
+    #[arg(
+        long,
+        value_name = "COLUMNS",
+        help = "Columns of the table",
+        value_delimiter = ',',
+        default_value = "id,title,status,priority,deadline,categories"
+    )]
+    pub columns: Vec<TaskColumnEnum>,
+}
+
+/**
+ * Parser of the `task list` arguments given to a view, later arguments override earlier ones
+ */
+#[derive(Parser, Debug)]
+#[command(name = "view", args_override_self = true)]
+struct SavedListArgs {
+    #[command(flatten)]
+    list: ListArgs,
+}
+
+impl ListArgs {
+    /**
+     * Used to get the sort keys, the legacy sort options included
+     */
+    pub fn sort_keys(&self) -> Vec<TaskSortKey> {
+        let legacy_sort = [
+            (TaskSortColumnEnum::Created, self.sort_created_at),
+            (TaskSortColumnEnum::Updated, self.sort_updated_at),
+            (TaskSortColumnEnum::Deadline, self.sort_deadline),
+            (TaskSortColumnEnum::Title, self.sort_title),
+            (TaskSortColumnEnum::Priority, self.sort_priority),
+        ]
+        .into_iter()
+        .filter_map(|(column, order)| order.map(|order| TaskSortKey::create(column, order)));
+
+        self.sort.iter().copied().chain(legacy_sort).collect()
+    }
+
+    /**
+     * Used to parse the arguments given to `view save` into the query stored by the view.
+     * Dates and the `--where` expression are kept as written so they are resolved when the view runs.
+     */
+    pub fn parse_view_query(arguments: &[String]) -> Result<ViewQuery> {
+        let matches = SavedListArgs::command().try_get_matches_from(
+            std::iter::once("view").chain(arguments.iter().map(String::as_str)),
+        )?;
+
+        for (id, argument) in [
+            ("offset", "--offset"),
+            ("page", "--page"),
+            ("after", "--after"),
+            ("columns", "--columns"),
+        ] {
+            if matches.value_source(id) == Some(ValueSource::CommandLine) {
+                anyhow::bail!(
+                    "[View] - [argument: {}] - only filters, sort keys and limit are saved, give it to `view run` instead",
+                    argument
+                );
+            }
+        }
+
+        Self::view_query(&matches)
+    }
+
+    /**
+     * Used to convert the arguments stored by the views before they stored a query,
+     * pagination and columns are left out
+     */
+    pub fn parse_legacy_view_query(arguments: &[String]) -> Result<ViewQuery> {
+        let matches = SavedListArgs::command().try_get_matches_from(
+            std::iter::once("view").chain(arguments.iter().map(String::as_str)),
+        )?;
 
+        Self::view_query(&matches)
+    }
+
+    /**
+     * Used to build the query of a view from parsed `task list` arguments
+     */
+    fn view_query(matches: &ArgMatches) -> Result<ViewQuery> {
+        let args = SavedListArgs::from_arg_matches(matches)?.list;
+        // the last value wins, as `args_override_self` does for the parsed ones
+        let raw = |id: &str| {
+            matches
+                .get_raw(id)
+                .and_then(|mut values| values.next_back())
+                .map(|value| value.to_string_lossy().into_owned())
+        };
+
+        Ok(ViewQuery {
+            status: args.status,
+            priority: args.priority,
+            any_categories: args.categories.clone(),
+            all_categories: args.all_categories.clone(),
+            exclude_categories: args.exclude_categories.clone(),
+            text: args.text.clone(),
+            due_before: raw("due_before"),
+            due_after: raw("due_after"),
+            due_within: match args.due_today {
+                true => Some("today".to_string()),
+                false => raw("due_within"),
+            },
+            overdue: args.overdue,
+            no_deadline: args.no_deadline,
+            created_since: raw("created_since"),
+            updated_since: raw("updated_since"),
+            blocked: args.blocked,
+            ready: args.ready,
+            filter: raw("filter"),
+            limit: args.limit,
+            sort: args.sort_keys().iter().map(ToString::to_string).collect(),
+        })
+    }
+
+    /**
+     * Used to parse the extra arguments given to `view run` on top of the query of the view.
+     * Options given as extra arguments replace the saved values, the deadline, dependency
+     * and sort options are replaced as a group since they conflict with each other.
+     */
+    pub fn parse_view(query: &ViewQuery, extra: &[String]) -> Result<Self> {
+        let matches = SavedListArgs::command().try_get_matches_from(
+            std::iter::once("view").chain(extra.iter().map(String::as_str)),
+        )?;
+        let mut args = SavedListArgs::from_arg_matches(&matches)?.list;
+        let given = |ids: &[&str]| {
+            ids.iter()
+                .any(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+        };
+
+        if !given(&["status"]) {
+            args.status = query.status;
+        }
+        if !given(&["priority"]) {
+            args.priority = query.priority;
+        }
+        if !given(&["categories"]) {
+            args.categories = query.any_categories.clone();
+        }
+        if !given(&["all_categories"]) {
+            args.all_categories = query.all_categories.clone();
+        }
+        if !given(&["exclude_categories"]) {
+            args.exclude_categories = query.exclude_categories.clone();
+        }
+        if !given(&["text"]) {
+            args.text = query.text.clone();
+        }
+        if !given(&["filter"]) {
+            args.filter = query.filter.as_deref().map(filter_parser).transpose()?;
+        }
+        if !given(&[
+            "due_before",
+            "due_after",
+            "overdue",
+            "due_today",
+            "due_within",
+            "no_deadline",
+        ]) {
+            args.due_before = query.due_before.as_deref().map(date_parser).transpose()?;
+            args.due_after = query.due_after.as_deref().map(date_parser).transpose()?;
+            args.overdue = query.overdue;
+            args.due_within = query
+                .due_within
+                .as_deref()
+                .map(period_end_parser)
+                .transpose()?;
+            args.no_deadline = query.no_deadline;
+        }
+        if !given(&["created_since"]) {
+            args.created_since = query
+                .created_since
+                .as_deref()
+                .map(date_parser)
+                .transpose()?;
+        }
+        if !given(&["updated_since"]) {
+            args.updated_since = query
+                .updated_since
+                .as_deref()
+                .map(date_parser)
+                .transpose()?;
+        }
+        if !given(&["blocked", "ready"]) {
+            args.blocked = query.blocked;
+            args.ready = query.ready;
+        }
+        if !given(&["limit"]) {
+            args.limit = query.limit;
+        }
+        if !given(&[
+            "sort",
+            "sort_created_at",
+            "sort_updated_at",
+            "sort_deadline",
+            "sort_title",
+            "sort_priority",
+        ]) {
+            args.sort = query
+                .sort
+                .iter()
+                .map(|key| sort_key_parser(key))
+                .collect::<Result<_>>()?;
+        }
+
+        Ok(args)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ActionsCommandsEnum {
+    #[command(about = "Show a logged action, comparing the tasks it stored with the tasks now")]
+    Show {
+        #[arg(value_name = "ACTION ID", help = "Id of the action to show", value_parser = clap::value_parser!(i64).range(1..))]
+        id: i64,
+    },
+    #[command(
+        about = "Apply the inverse of a past action on top of the current state, logged as a new action"
+    )]
+    Revert {
+        #[arg(value_name = "ACTION ID", help = "Id of the action to revert", value_parser = clap::value_parser!(i64).range(1..))]
+        id: i64,
+        #[arg(short, long, help = "Force operation without confirmation")]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommandsEnum {
+    #[command(about = "Show the schema version and the migrations applied or pending")]
+    Status,
+    #[command(
+        about = "Apply the pending migrations, other commands also apply them when opening the data file"
+    )]
+    Migrate,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ViewCommandsEnum {
+    #[command(
+        about = "Save the filters, sort keys and limit of `task list` under a name, replacing any view with that name"
+    )]
+    Save {
+        #[arg(index = 1, value_name = "NAME", help = "The view name", value_parser = view_name_parser)]
+        name: String,
         #[arg(
-            long,
-            short = 'u',
-            value_name = "SORT UPDATE AT",
-            help = "Sort by updated at"
+            index = 2,
+            value_name = "LIST ARGS",
+            help = "Arguments of `task list`, e.g. --where 'due<+7d' --sort deadline",
+            trailing_var_arg = true,
+            allow_hyphen_values = true
         )]
-        sort_updated_at: Option<OrderByEnum>,
-
+        arguments: Vec<String>,
+    },
+    #[command(about = "List the tasks of a view")]
+    Run {
+        #[arg(index = 1, value_name = "NAME", help = "The view name")]
+        name: String,
         #[arg(
-            long,
-            short = 'd',
-            value_name = "SORT DEADLINE",
-            help = "Sort by deadline"
+            index = 2,
+            value_name = "LIST ARGS",
+            help = "More arguments of `task list`, overriding the saved ones, e.g. --page 2",
+            trailing_var_arg = true,
+            allow_hyphen_values = true
         )]
-        sort_deadline: Option<OrderByEnum>,
-
-        #[arg(long, short = 'o', value_name = "SORT TITLE", help = "Sort by title")]
-        sort_title: Option<OrderByEnum>,
+        arguments: Vec<String>,
     },
-    #[command(about = "Read an existing task")]
-    Read {
-        #[arg(index = 1, value_name = "ID", help = "The target task id", value_parser = clap::value_parser!(i64).range(1..))]
-        id: i64,
+    #[command(about = "List all views")]
+    List,
+    #[command(about = "Delete a view")]
+    Delete {
+        #[arg(index = 1, value_name = "NAME", help = "The view name")]
+        name: String,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use clap::CommandFactory;
+
+    use time::Duration;
+
+    use crate::{models::TaskStatusEnum, utils::get_today};
+
+    use super::{ListArgs, RootCommand, SavedListArgs};
+
+    #[test]
+    fn test_command_definition() {
+        RootCommand::command().debug_assert();
+        SavedListArgs::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_view_query() -> Result<()> {
+        let arguments = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        // test dates and the filter are stored as written
+        let query = ListArgs::parse_view_query(&arguments(&[
+            "--status",
+            "undone",
+            "--limit",
+            "5",
+            "--where",
+            "cat:backend and due<+7d",
+            "--due-before",
+            "+3d",
+            "-d",
+            "desc",
+        ]))?;
+        assert_eq!(Some(TaskStatusEnum::Undone), query.status);
+        assert_eq!(5, query.limit);
+        assert_eq!(Some("cat:backend and due<+7d".to_string()), query.filter);
+        assert_eq!(Some("+3d".to_string()), query.due_before);
+        assert_eq!(vec!["deadline:desc:nulls-last"], query.sort);
+
+        // test later arguments override earlier ones
+        let query = ListArgs::parse_view_query(&arguments(&[
+            "--limit",
+            "5",
+            "--limit",
+            "20",
+            "--due-within",
+            "1w",
+            "--due-within",
+            "2w",
+        ]))?;
+        assert_eq!(20, query.limit);
+        assert_eq!(Some("2w".to_string()), query.due_within);
+
+        let query = ListArgs::parse_view_query(&arguments(&["--due-today"]))?;
+        assert_eq!(Some("today".to_string()), query.due_within);
+
+        assert!(ListArgs::parse_view_query(&arguments(&["--unknown"])).is_err());
+        assert!(ListArgs::parse_view_query(&arguments(&["--where", "stauts:done"])).is_err());
+        assert!(ListArgs::parse_view_query(&arguments(&["--page", "2"])).is_err());
+        assert_eq!(
+            10,
+            ListArgs::parse_legacy_view_query(&arguments(&["--page", "2"]))?.limit
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_view() -> Result<()> {
+        let arguments = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let today = get_today();
+
+        let query = ListArgs::parse_view_query(&arguments(&[
+            "-c",
+            "backend",
+            "-c",
+            "ops",
+            "--sort",
+            "deadline,title",
+            "--due-within",
+            "7d",
+            "--limit",
+            "5",
+        ]))?;
+
+        // test relative dates are resolved when the view runs
+        let args = ListArgs::parse_view(&query, &[])?;
+        assert_eq!(Some(arguments(&["backend", "ops"])), args.categories);
+        assert_eq!(
+            Some(today.saturating_add(Duration::days(7))),
+            args.due_within
+        );
+        assert_eq!(5, args.limit);
+        assert_eq!(
+            vec!["deadline:asc:nulls-last", "title:asc:nulls-last"],
+            args.sort_keys()
+                .iter()
+                .map(|key| key.to_string())
+                .collect::<Vec<_>>()
+        );
+
+        // test extra arguments replace the saved values and their group
+        let args = ListArgs::parse_view(
+            &query,
+            &arguments(&["-r", "desc", "--due-before", "today", "--page", "2"]),
+        )?;
+        assert_eq!(Some(arguments(&["backend", "ops"])), args.categories);
+        assert_eq!(None, args.due_within);
+        assert_eq!(Some(today), args.due_before);
+        assert_eq!(Some(2), args.page);
+        assert_eq!(
+            vec!["priority:desc:nulls-last"],
+            args.sort_keys()
+                .iter()
+                .map(|key| key.to_string())
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,537 @@
+use std::fmt::Display;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use time::Date;
+
+use crate::{
+    models::{CompareEnum, TaskFilter, TaskFilterTerm, TaskPriorityEnum, TaskStatusEnum},
+    utils::relative_date_parser,
+};
+
+/// fields accepted by `field:value` terms, listed in error messages
+const FIELDS: &str = "status, priority, cat, text, due, created, updated or is";
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenEnum {
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+    Not,
+    /// `field op value`, `value_start` is the column of the value
+    Term {
+        field: String,
+        compare: CompareEnum,
+        value: String,
+        value_start: usize,
+    },
+    /// bare word or quoted text
+    Text(String),
+}
+
+/**
+ * A token and the columns it spans, counted in characters
+ */
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenEnum,
+    start: usize,
+    end: usize,
+}
+
+/**
+ * Used to parse a `--where` expression, e.g.
+ * `status:undone and (cat:backend or cat:ops) and due<+7d and text:"login bug"`.
+ *
+ * Terms are `field:value` or `field<value` with `<`, `<=`, `>`, `>=`, bare words search the text.
+ * Terms combine with `and`, `or`, `not` and parentheses, adjacent terms are joined with `and`.
+ * Dates accept the same expressions as `--deadline` and are resolved relative to `today`.
+ */
+pub fn parse_filter(input: &str, today: Date) -> Result<TaskFilter> {
+    let tokens = tokenize(input)?;
+
+    let mut parser = Parser {
+        input,
+        tokens,
+        index: 0,
+        today,
+    };
+
+    if parser.tokens.is_empty() {
+        return Err(filter_error(input, 0, 0, "empty expression"));
+    }
+
+    let filter = parser.parse_or()?;
+
+    match parser.tokens.get(parser.index) {
+        Some(token) => Err(filter_error(
+            input,
+            token.start,
+            token.end,
+            format!("unexpected `{}`", parser.text(token)),
+        )),
+        None => Ok(filter),
+    }
+}
+
+/**
+ * Used to build an error showing the expression with the bad columns underlined
+ */
+fn filter_error(input: &str, start: usize, end: usize, message: impl Display) -> anyhow::Error {
+    anyhow::anyhow!(
+        "[Invalid filter] - {} at column {}\n  {}\n  {}{}",
+        message,
+        start + 1,
+        input,
+        " ".repeat(start),
+        "^".repeat(end.saturating_sub(start).max(1))
+    )
+}
+
+fn is_separator(char: char) -> bool {
+    char.is_whitespace() || matches!(char, '(' | ')' | '"')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut index = 0;
+
+    while index < chars.len() {
+        let start = index;
+
+        let kind = match chars[index] {
+            char if char.is_whitespace() => {
+                index += 1;
+                continue;
+            }
+            '(' => {
+                index += 1;
+                TokenEnum::LeftParen
+            }
+            ')' => {
+                index += 1;
+                TokenEnum::RightParen
+            }
+            '"' => {
+                let (text, end) = read_quoted(input, &chars, index)?;
+                index = end;
+                TokenEnum::Text(text)
+            }
+            _ => {
+                while index < chars.len()
+                    && !is_separator(chars[index])
+                    && !matches!(chars[index], ':' | '=' | '<' | '>')
+                {
+                    index += 1;
+                }
+                let word: String = chars[start..index].iter().collect();
+
+                let compare = match (chars.get(index), chars.get(index + 1)) {
+                    (Some('<'), Some('=')) => Some((CompareEnum::Le, 2)),
+                    (Some('>'), Some('=')) => Some((CompareEnum::Ge, 2)),
+                    (Some('<'), _) => Some((CompareEnum::Lt, 1)),
+                    (Some('>'), _) => Some((CompareEnum::Gt, 1)),
+                    (Some(':' | '='), _) => Some((CompareEnum::Eq, 1)),
+                    _ => None,
+                };
+
+                match compare {
+                    Some((compare, length)) => {
+                        if word.is_empty() {
+                            return Err(filter_error(
+                                input,
+                                index,
+                                index + length,
+                                "expected a field name before the comparison",
+                            ));
+                        }
+                        index += length;
+
+                        let value_start = index;
+                        let value = match chars.get(index) {
+                            Some('"') => {
+                                let (text, end) = read_quoted(input, &chars, index)?;
+                                index = end;
+                                text
+                            }
+                            _ => {
+                                while index < chars.len() && !is_separator(chars[index]) {
+                                    index += 1;
+                                }
+                                chars[value_start..index].iter().collect()
+                            }
+                        };
+
+                        if value.is_empty() {
+                            return Err(filter_error(
+                                input,
+                                start,
+                                index,
+                                format!("expected a value after `{}`", word),
+                            ));
+                        }
+
+                        TokenEnum::Term {
+                            field: word.to_lowercase(),
+                            compare,
+                            value,
+                            value_start,
+                        }
+                    }
+                    None => match word.to_lowercase().as_str() {
+                        "and" => TokenEnum::And,
+                        "or" => TokenEnum::Or,
+                        "not" => TokenEnum::Not,
+                        _ => TokenEnum::Text(word),
+                    },
+                }
+            }
+        };
+
+        tokens.push(Token {
+            kind,
+            start,
+            end: index,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/**
+ * Used to read a double quoted string starting at `start`, `\"` and `\\` are escapes
+ */
+fn read_quoted(input: &str, chars: &[char], start: usize) -> Result<(String, usize)> {
+    let mut text = String::new();
+    let mut index = start + 1;
+
+    while index < chars.len() {
+        match chars[index] {
+            '"' => return Ok((text, index + 1)),
+            '\\' if index + 1 < chars.len() => {
+                text.push(chars[index + 1]);
+                index += 2;
+            }
+            char => {
+                text.push(char);
+                index += 1;
+            }
+        }
+    }
+
+    Err(filter_error(
+        input,
+        start,
+        chars.len(),
+        "unterminated quote",
+    ))
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Token>,
+    index: usize,
+    today: Date,
+}
+
+impl Parser<'_> {
+    fn text(&self, token: &Token) -> String {
+        self.input
+            .chars()
+            .skip(token.start)
+            .take(token.end - token.start)
+            .collect()
+    }
+
+    fn peek(&self) -> Option<&TokenEnum> {
+        self.tokens.get(self.index).map(|token| &token.kind)
+    }
+
+    fn parse_or(&mut self) -> Result<TaskFilter> {
+        let mut filter = self.parse_and()?;
+
+        while self.peek() == Some(&TokenEnum::Or) {
+            self.index += 1;
+            let right = self.parse_and()?;
+            filter = TaskFilter::Or(Box::new(filter), Box::new(right));
+        }
+
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<TaskFilter> {
+        let mut filter = self.parse_unary()?;
+
+        loop {
+            match self.peek() {
+                Some(TokenEnum::And) => self.index += 1,
+                // adjacent terms are joined with `and`
+                Some(
+                    TokenEnum::Not
+                    | TokenEnum::LeftParen
+                    | TokenEnum::Term { .. }
+                    | TokenEnum::Text(_),
+                ) => {}
+                _ => break,
+            }
+            let right = self.parse_unary()?;
+            filter = TaskFilter::And(Box::new(filter), Box::new(right));
+        }
+
+        Ok(filter)
+    }
+
+    fn parse_unary(&mut self) -> Result<TaskFilter> {
+        match self.peek() {
+            Some(TokenEnum::Not) => {
+                self.index += 1;
+                Ok(TaskFilter::Not(Box::new(self.parse_unary()?)))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<TaskFilter> {
+        let Some(token) = self.tokens.get(self.index).cloned() else {
+            let end = self.input.chars().count();
+            return Err(filter_error(
+                self.input,
+                end,
+                end,
+                "unexpected end, expected a filter",
+            ));
+        };
+        self.index += 1;
+
+        match token.kind {
+            TokenEnum::LeftParen => {
+                let filter = self.parse_or()?;
+                match self.peek() {
+                    Some(TokenEnum::RightParen) => {
+                        self.index += 1;
+                        Ok(filter)
+                    }
+                    _ => Err(filter_error(
+                        self.input,
+                        token.start,
+                        token.end,
+                        "unclosed `(`",
+                    )),
+                }
+            }
+            TokenEnum::Text(text) => Ok(TaskFilter::Term(TaskFilterTerm::Text(text))),
+            TokenEnum::Term {
+                ref field,
+                compare,
+                ref value,
+                value_start,
+            } => self
+                .parse_term(&token, field, compare, value, value_start)
+                .map(TaskFilter::Term),
+            TokenEnum::RightParen | TokenEnum::And | TokenEnum::Or | TokenEnum::Not => {
+                Err(filter_error(
+                    self.input,
+                    token.start,
+                    token.end,
+                    format!("unexpected `{}`, expected a filter", self.text(&token)),
+                ))
+            }
+        }
+    }
+
+    fn parse_term(
+        &self,
+        token: &Token,
+        field: &str,
+        compare: CompareEnum,
+        value: &str,
+        value_start: usize,
+    ) -> Result<TaskFilterTerm> {
+        let field_end = token.start + field.chars().count();
+        let value_error =
+            |message: String| filter_error(self.input, value_start, token.end, message);
+        let only_equal = || match compare {
+            CompareEnum::Eq => Ok(()),
+            _ => Err(filter_error(
+                self.input,
+                field_end,
+                value_start,
+                format!("`{}` can only be compared with `:`", field),
+            )),
+        };
+        let date = |value: &str| {
+            relative_date_parser(value, self.today)
+                .map_err(|_| value_error(format!("invalid date `{}`", value)))
+        };
+
+        match field {
+            "status" => {
+                only_equal()?;
+                TaskStatusEnum::from_str(value, true)
+                    .map(TaskFilterTerm::Status)
+                    .map_err(|_| {
+                        value_error(format!(
+                            "invalid status `{}`, expected {}",
+                            value,
+                            possible_values::<TaskStatusEnum>()
+                        ))
+                    })
+            }
+            "priority" => TaskPriorityEnum::from_str(value, true)
+                .map(|priority| TaskFilterTerm::Priority(compare, priority))
+                .map_err(|_| {
+                    value_error(format!(
+                        "invalid priority `{}`, expected {}",
+                        value,
+                        possible_values::<TaskPriorityEnum>()
+                    ))
+                }),
+            "cat" | "category" => {
+                only_equal()?;
+                Ok(TaskFilterTerm::Category(value.to_string()))
+            }
+            "text" => {
+                only_equal()?;
+                Ok(TaskFilterTerm::Text(value.to_string()))
+            }
+            "due" | "deadline" => match value.to_lowercase().as_str() {
+                "none" => {
+                    only_equal()?;
+                    Ok(TaskFilterTerm::Deadline(compare, None))
+                }
+                _ => Ok(TaskFilterTerm::Deadline(compare, Some(date(value)?))),
+            },
+            "created" => Ok(TaskFilterTerm::CreatedAt(compare, date(value)?)),
+            "updated" => Ok(TaskFilterTerm::UpdatedAt(compare, date(value)?)),
+            "is" => {
+                only_equal()?;
+                match value.to_lowercase().as_str() {
+                    "overdue" => Ok(TaskFilterTerm::Overdue),
+                    "blocked" => Ok(TaskFilterTerm::Blocked),
+                    "ready" => Ok(TaskFilterTerm::Ready),
+                    _ => Err(value_error(format!(
+                        "invalid state `{}`, expected overdue, blocked or ready",
+                        value
+                    ))),
+                }
+            }
+            _ => Err(filter_error(
+                self.input,
+                token.start,
+                field_end,
+                format!("unknown field `{}`, expected {}", field, FIELDS),
+            )),
+        }
+    }
+}
+
+fn possible_values<T: ValueEnum>() -> String {
+    T::value_variants()
+        .iter()
+        .filter_map(|value| value.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use time::macros::date;
+
+    use crate::models::{
+        CompareEnum, TaskFilter, TaskFilterTerm, TaskPriorityEnum, TaskStatusEnum,
+    };
+
+    use super::parse_filter;
+
+    fn term(term: TaskFilterTerm) -> Box<TaskFilter> {
+        Box::new(TaskFilter::Term(term))
+    }
+
+    #[test]
+    fn test_parse_filter() -> Result<()> {
+        let today = date!(2024 - 01 - 31);
+
+        // test precedence: `and` binds tighter than `or`, parentheses group
+        assert_eq!(
+            TaskFilter::And(
+                Box::new(TaskFilter::And(
+                    Box::new(TaskFilter::And(
+                        term(TaskFilterTerm::Status(TaskStatusEnum::Undone)),
+                        Box::new(TaskFilter::Or(
+                            term(TaskFilterTerm::Category("backend".into())),
+                            term(TaskFilterTerm::Category("ops".into())),
+                        )),
+                    )),
+                    term(TaskFilterTerm::Deadline(
+                        CompareEnum::Lt,
+                        Some(date!(2024 - 02 - 07))
+                    )),
+                )),
+                term(TaskFilterTerm::Text("login bug".into())),
+            ),
+            parse_filter(
+                r#"status:undone and (cat:backend or cat:ops) and due<+7d and text:"login bug""#,
+                today
+            )?
+        );
+
+        assert_eq!(
+            TaskFilter::Or(
+                Box::new(TaskFilter::And(
+                    term(TaskFilterTerm::Priority(
+                        CompareEnum::Ge,
+                        TaskPriorityEnum::High
+                    )),
+                    Box::new(TaskFilter::Not(term(TaskFilterTerm::Blocked))),
+                )),
+                term(TaskFilterTerm::Deadline(CompareEnum::Eq, None)),
+            ),
+            parse_filter("PRIORITY>=high NOT is:blocked or due:none", today)?
+        );
+
+        assert_eq!(
+            TaskFilter::And(
+                term(TaskFilterTerm::Text("report".into())),
+                term(TaskFilterTerm::CreatedAt(
+                    CompareEnum::Ge,
+                    date!(2024 - 01 - 01)
+                )),
+            ),
+            parse_filter("report created>=2024-01-01", today)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_filter_errors() {
+        let today = date!(2024 - 01 - 31);
+        let error = |input: &str| parse_filter(input, today).unwrap_err().to_string();
+
+        assert_eq!(
+            "[Invalid filter] - unknown field `stauts`, expected status, priority, cat, text, due, created, updated or is at column 15\n  \
+             due<today and stauts:done\n                \
+             ^^^^^^",
+            error("due<today and stauts:done")
+        );
+        assert_eq!(
+            "[Invalid filter] - invalid status `todo`, expected done, undone, archived at column 8\n  \
+             status:todo\n         \
+             ^^^^",
+            error("status:todo")
+        );
+        assert!(error("(cat:a or cat:b").contains("unclosed `(` at column 1"));
+        assert!(error("cat:a)").contains("unexpected `)` at column 6"));
+        assert!(error("cat:a and").contains("unexpected end, expected a filter at column 10"));
+        assert!(error("or cat:a").contains("unexpected `or`, expected a filter at column 1"));
+        assert!(error("text:\"login").contains("unterminated quote at column 6"));
+        assert!(error("due<soon").contains("invalid date `soon` at column 5"));
+        assert!(error("cat>a").contains("`cat` can only be compared with `:` at column 4"));
+        assert!(error("status:").contains("expected a value after `status` at column 1"));
+        assert!(error("  ").contains("empty expression"));
+    }
+}
@@ -1,26 +1,53 @@
 use anyhow::Result;
 use clap::Parser;
-use command::RootCommand;
-use models::{setup_database, AddTask, QueryTaskPayload, UpdateTask};
+use command::{ListArgs, RootCommand};
+use migrations::{database_version, latest_version, migrate, MIGRATIONS};
+use models::{
+    setup_database, Action, ActionFilter, AddTask, HousekeepingPayload, OutputFormatEnum,
+    QueryTaskPayload, SearchTaskPayload, Task, TaskEventEnum, UpdateTask,
+};
+use output::{
+    print_item, print_items, ActionDetailOutput, ActionOutput, CanceledOutput,
+    CategoryChangeOutput, CategoryOutput, DatabaseOutput, DependencyOutput, HousekeepingOutput,
+    MigrationOutput, ReindexOutput, SearchResultOutput, TaskChangeOutput, TaskEventOutput,
+    TaskOutput, UndoRedoOutput, ViewOutput,
+};
 use repositories::{
-    action_repository::ActionRepository, category_repository::CategoryRepository, query_tasks,
-    task_repository::TaskRepository,
+    action_repository::ActionRepository, category_repository::CategoryRepository,
+    dependency_repository::DependencyRepository, query_tasks, task_repository::TaskRepository,
+    view_repository::ViewRepository,
 };
 use rusqlite::Connection;
-use utils::{ask_permission, optional_date_parser};
+use search::fts_query;
+use table::{task_table, terminal_width, use_colors};
+use time::macros::format_description;
+use utils::{
+    ask_permission, format_ids, get_local_now, get_today, optional_deadline_parser,
+    optional_recurrence_parser, task_tree, tree_prefix,
+};
 
 mod command;
+mod filter;
+mod migrations;
 mod models;
+mod output;
 mod repositories;
+mod search;
+mod table;
 mod utils;
 
 fn main() -> Result<()> {
     let matches = RootCommand::parse();
 
     let mut conn = Connection::open(&matches.file)?;
-    setup_database(&conn)?;
+    // `db` commands report and apply the migrations themselves
+    if !matches!(matches.command, command::RootCommandsEnum::Db { .. }) {
+        setup_database(&conn)?;
+    }
+
+    let conn = conn.transaction()?;
 
-    let mut conn = conn.transaction()?;
+    let output = matches.output;
 
     match matches.command {
         command::RootCommandsEnum::Task { command } => match command {
@@ -29,6 +56,9 @@ fn main() -> Result<()> {
                 info,
                 deadline,
                 status,
+                priority,
+                parent,
+                repeat,
                 date,
                 categories,
             } => {
@@ -37,259 +67,786 @@ fn main() -> Result<()> {
                     info,
                     deadline,
                     status,
+                    priority,
+                    parent_id: parent,
+                    recurrence: repeat,
                     created_at: date,
                     categories,
                 };
 
-                let task = repositories::add_task(&mut conn, task)?;
+                let task = repositories::add_task(&conn, task)?;
 
-                println!("[Task][Create] - (#{}) - [{}]", task.id, task.title);
+                match output {
+                    OutputFormatEnum::Text => {
+                        println!("[Task][Create] - (#{}) - [{}]", task.id, task.title)
+                    }
+                    _ => print_item(
+                        output,
+                        &TaskChangeOutput {
+                            task: TaskOutput::create(&conn, task)?,
+                            next_task: None,
+                        },
+                    )?,
+                }
             }
-            command::TaskCommandsEnum::Delete { id, force } => {
-                let repository = TaskRepository::create(&conn);
-                let task = match repository.get_task(id)? {
-                    Some(task) => task,
-                    None => return Err(anyhow::anyhow!("Task with id (#{}) not found!", id).into()),
-                };
+            command::TaskCommandsEnum::Delete {
+                ids,
+                children,
+                force,
+            } => {
+                let tasks = get_tasks(&conn, &ids)?;
 
-                let proceed = ask_permission(
-                    &format!(
-                        "Do you want to delete task (#{}) - [{}]? (y/N)",
-                        id, task.title
-                    ),
-                    force,
-                )?;
+                let proceed = ask_permission(&task_question("delete", &tasks), force)?;
 
                 if proceed {
-                    repositories::delete_task(&mut conn, &task)?;
-                    println!("[Task][Delete] - (#{}) - [{}]", id, task.title);
+                    let deleted = repositories::compound_operation(&conn, |conn| {
+                        let repository = TaskRepository::create(conn);
+                        let mut deleted = vec![];
+                        for task in &tasks {
+                            // a cascade may have deleted it along with an earlier task
+                            let Some(task) = repository.get_task(task.id)? else {
+                                continue;
+                            };
+                            let output = TaskOutput::create(conn, task.clone())?;
+                            repositories::delete_task(conn, &task, children)?;
+                            deleted.push((task, output));
+                        }
+                        Ok(deleted)
+                    })?;
+                    match output {
+                        OutputFormatEnum::Text => {
+                            for (task, _) in deleted {
+                                println!("[Task][Delete] - (#{}) - [{}]", task.id, task.title)
+                            }
+                        }
+                        _ => {
+                            let changes: Vec<TaskChangeOutput> = deleted
+                                .into_iter()
+                                .map(|(_, task)| TaskChangeOutput {
+                                    task,
+                                    next_task: None,
+                                })
+                                .collect();
+                            print_task_changes(output, ids.len(), &changes)?;
+                        }
+                    }
                 } else {
-                    println!("Operation Canceled")
+                    print_canceled(output)?;
                 }
             }
             command::TaskCommandsEnum::Update {
-                id,
+                ids,
                 title,
                 info,
                 deadline,
                 // categories,
                 status,
+                priority,
+                repeat,
                 date,
                 force,
             } => {
-                let repository = TaskRepository::create(&conn);
-
-                let old_task = match repository.get_task(id)? {
-                    Some(existing_task) => existing_task,
-                    None => return Err(anyhow::anyhow!("Task with id (#{}) not found!", id).into()),
-                };
+                let tasks = get_tasks(&conn, &ids)?;
 
-                let info = info.map(|info| if info == "" { None } else { Some(info) });
-                let deadline = deadline.map(|v| optional_date_parser(&v)).transpose()?;
+                let info = info.map(|info| if info.is_empty() { None } else { Some(info) });
+                let deadline = deadline.map(|v| optional_deadline_parser(&v)).transpose()?;
+                let recurrence = repeat.map(|v| optional_recurrence_parser(&v)).transpose()?;
 
                 let new_task = UpdateTask {
                     title,
                     info,
                     deadline,
                     status,
+                    priority,
+                    recurrence,
                     created_at: date,
                 };
 
-                let proceed = ask_permission(
-                    &format!(
-                        "Do you want to update task (#{}) - [{}]? (y/N)",
-                        id, old_task.title
-                    ),
-                    force,
-                )?;
+                let proceed = ask_permission(&task_question("update", &tasks), force)?;
 
                 if proceed {
-                    let task = repositories::edit_task(&mut conn, id, old_task, new_task)?;
-                    println!("[Task][Updated] (#{}) - [{}]", id, task.title);
+                    let updated = repositories::compound_operation(&conn, |conn| {
+                        tasks
+                            .into_iter()
+                            .map(|old_task| {
+                                repositories::edit_task(
+                                    conn,
+                                    old_task.id,
+                                    old_task,
+                                    new_task.clone(),
+                                )
+                            })
+                            .collect::<Result<Vec<_>>>()
+                    })?;
+                    match output {
+                        OutputFormatEnum::Text => {
+                            for (task, next_task) in updated {
+                                println!("[Task][Updated] (#{}) - [{}]", task.id, task.title);
+                                if let Some(next_task) = next_task {
+                                    println!(
+                                        "[Task][Create] - (#{}) - [{}] - [Deadline: {}]",
+                                        next_task.id,
+                                        next_task.title,
+                                        next_task
+                                            .deadline
+                                            .map(|v| v.to_string())
+                                            .unwrap_or_default()
+                                    );
+                                }
+                            }
+                        }
+                        _ => {
+                            let changes = updated
+                                .into_iter()
+                                .map(|(task, next_task)| {
+                                    Ok(TaskChangeOutput {
+                                        task: TaskOutput::create(&conn, task)?,
+                                        next_task: next_task
+                                            .map(|next_task| TaskOutput::create(&conn, next_task))
+                                            .transpose()?,
+                                    })
+                                })
+                                .collect::<Result<Vec<_>>>()?;
+                            print_task_changes(output, ids.len(), &changes)?;
+                        }
+                    }
                 } else {
-                    println!("Operation Canceled")
+                    print_canceled(output)?;
                 }
             }
-            command::TaskCommandsEnum::List {
+            command::TaskCommandsEnum::List(args) => list_tasks(&conn, output, args)?,
+            command::TaskCommandsEnum::Search {
+                query,
                 status,
-                categories,
-                text,
                 limit,
-                sort_created_at,
-                sort_updated_at,
-                sort_deadline,
-                sort_title,
             } => {
-                let payload = QueryTaskPayload {
+                let colors = output == OutputFormatEnum::Text && use_colors();
+                let marks = match colors {
+                    true => ("\x1b[1m", "\x1b[0m"),
+                    false => ("**", "**"),
+                };
+
+                let payload = SearchTaskPayload {
+                    query: fts_query(&query.join(" "))?,
                     status,
-                    categories,
-                    text,
                     limit,
-                    sort_created_at,
-                    sort_updated_at,
-                    sort_deadline,
-                    sort_title,
+                    marks: (marks.0.to_string(), marks.1.to_string()),
                 };
-                let tasks = query_tasks(&conn, payload)?;
-                println!("========== TASKS ==========");
-                for task in tasks {
-                    println!(
-                        "(#{}) - [{}] - [Status: {}] - [{}]",
-                        task.id,
-                        task.title,
-                        task.status.to_string(),
-                        task.created_at
-                    );
+                let results = TaskRepository::create(&conn).search_tasks(payload)?;
+
+                match output {
+                    OutputFormatEnum::Text => {
+                        println!("========== Search ==========");
+                        for result in results {
+                            println!(
+                                "(#{}) - [{}] - [Score: {:.2}]",
+                                result.task.id, result.title, result.score
+                            );
+                            if let Some(snippet) = result.snippet {
+                                println!("    {}", snippet.replace('\n', " "));
+                            }
+                        }
+                    }
+                    _ => {
+                        let results = results
+                            .into_iter()
+                            .map(|result| {
+                                Ok(SearchResultOutput {
+                                    task: TaskOutput::create(&conn, result.task)?,
+                                    score: result.score,
+                                    title: result.title,
+                                    snippet: result.snippet,
+                                })
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        print_items(output, &results)?;
+                    }
+                }
+            }
+            command::TaskCommandsEnum::Depend { id, depends_on_id } => {
+                repositories::add_task_dependency(&conn, id, depends_on_id)?;
+                match output {
+                    OutputFormatEnum::Text => println!(
+                        "[Dependency][Created] - (#{}) - [Depends on: {}]",
+                        id, depends_on_id
+                    ),
+                    _ => print_item(
+                        output,
+                        &DependencyOutput {
+                            task_id: id,
+                            depends_on_id,
+                        },
+                    )?,
+                }
+            }
+            command::TaskCommandsEnum::Undepend { id, depends_on_id } => {
+                repositories::remove_task_dependency(&conn, id, depends_on_id)?;
+                match output {
+                    OutputFormatEnum::Text => println!(
+                        "[Dependency][Removed] - (#{}) - [Depends on: {}]",
+                        id, depends_on_id
+                    ),
+                    _ => print_item(
+                        output,
+                        &DependencyOutput {
+                            task_id: id,
+                            depends_on_id,
+                        },
+                    )?,
+                }
+            }
+            command::TaskCommandsEnum::History { id } => {
+                let history = repositories::fetch_task_history(&conn, id)?;
+
+                match output {
+                    OutputFormatEnum::Text => {
+                        println!("========== Task (#{}) - History ==========", id);
+                        for event in history {
+                            let time = match event.logged_at {
+                                Some(logged_at) => logged_at.format(format_description!(
+                                    "[year]-[month]-[day] [hour]:[minute]:[second]"
+                                ))?,
+                                None => event.created_at.to_string(),
+                            };
+                            let value = |value: &Option<String>| {
+                                value.clone().unwrap_or_else(|| "(none)".to_string())
+                            };
+                            let changes: Vec<String> = event
+                                .changes
+                                .iter()
+                                .map(|change| {
+                                    let (before, after) = (&change.before, &change.after);
+                                    let change_text = match event.event {
+                                        TaskEventEnum::Created => value(after),
+                                        TaskEventEnum::CategoryAdded
+                                        | TaskEventEnum::DependencyAdded => {
+                                            format!("+{}", value(after))
+                                        }
+                                        TaskEventEnum::CategoryRemoved
+                                        | TaskEventEnum::DependencyRemoved => {
+                                            format!("-{}", value(before))
+                                        }
+                                        _ => format!("{} -> {}", value(before), value(after)),
+                                    };
+                                    format!("{}: {}", change.field, change_text)
+                                })
+                                .collect();
+                            let mut line =
+                                format!("[{}] - (#{}) - [{}]", time, event.action_id, event.event);
+                            if !changes.is_empty() {
+                                line.push_str(&format!(" - {}", changes.join(", ")));
+                            }
+                            println!("{}", line);
+                        }
+                    }
+                    _ => {
+                        let history: Vec<TaskEventOutput> =
+                            history.into_iter().map(TaskEventOutput::from).collect();
+                        print_items(output, &history)?;
+                    }
                 }
             }
             command::TaskCommandsEnum::Read { id } => {
                 let repository = TaskRepository::create(&conn);
                 let category_repository = CategoryRepository::create(&conn);
+                let dependency_repository = DependencyRepository::create(&conn);
 
                 match repository.get_task(id)? {
+                    Some(task) if output != OutputFormatEnum::Text => {
+                        print_item(output, &TaskOutput::create(&conn, task)?)?;
+                    }
                     Some(task) => {
                         let categories = category_repository.fetch_task_categories(id)?;
                         let header = format!("=== (#{}) [{}] ===", id, task.title);
                         println!("{}", header);
                         println!("{}", "=".repeat(header.len()));
-                        match task.info {
-                            Some(info) => {
-                                println!("Info: {}", info);
-                            }
-                            None => {}
+                        if let Some(info) = task.info {
+                            println!("Info: {}", info);
                         }
-                        match task.deadline {
-                            Some(deadline) => {
-                                println!("Deadline: {}", deadline);
-                            }
-                            None => {}
+                        if let Some(deadline) = task.deadline {
+                            println!("Deadline: {}", deadline);
+                        }
+                        println!("status: {}", task.status);
+                        println!("Priority: {}", task.priority);
+                        if let Some(recurrence) = &task.recurrence {
+                            println!("Repeat: {}", recurrence);
+                        }
+                        if let Some(parent_id) = task.parent_id {
+                            println!("Parent: (#{})", parent_id);
                         }
-                        println!("status: {}", task.status.to_string());
                         println!("Created At: {}", task.created_at);
                         println!("Updated At: {}", task.updated_at);
-                        if categories.len() > 0 {
+                        if !categories.is_empty() {
                             println!("Categories: {}", categories.join(", "));
                         }
+                        let dependencies = dependency_repository.fetch_dependencies(id)?;
+                        if !dependencies.is_empty() {
+                            println!("Depends On: {}", format_ids(&dependencies));
+                        }
+                        let dependents = dependency_repository.fetch_dependents(id)?;
+                        if !dependents.is_empty() {
+                            println!("Blocks: {}", format_ids(&dependents));
+                        }
+                        let subtasks = repository.fetch_descendants(id)?;
+                        if !subtasks.is_empty() {
+                            println!("Subtasks:");
+                            for (depth, subtask) in task_tree(subtasks) {
+                                println!(
+                                    "{}(#{}) - [{}] - [Status: {}]",
+                                    tree_prefix(depth + 1),
+                                    subtask.id,
+                                    subtask.title,
+                                    subtask.status
+                                );
+                            }
+                        }
                         println!("{}", "=".repeat(header.len()));
                     }
-                    None => return Err(anyhow::anyhow!("Task with id (#{}) not found!", id).into()),
+                    None => return Err(anyhow::anyhow!("Task with id (#{}) not found!", id)),
                 }
             }
         },
-        command::RootCommandsEnum::Undo { force } => {
-            let repository = ActionRepository::create(&conn);
-
-            let action = repository.get_last_unrestored_action()?;
+        command::RootCommandsEnum::Undo { force, steps, to } => {
+            let actions = ActionRepository::create(&conn).fetch_undo_actions(steps, to)?;
+            revert_actions(&conn, output, "undo", actions, force)?;
+        }
+        command::RootCommandsEnum::Redo { force, steps, to } => {
+            let actions = ActionRepository::create(&conn).fetch_redo_actions(steps, to)?;
+            revert_actions(&conn, output, "redo", actions, force)?;
+        }
+        command::RootCommandsEnum::Actions {
+            command: Some(command::ActionsCommandsEnum::Revert { id, force }),
+            ..
+        } => {
+            let action = ActionRepository::create(&conn)
+                .get_action(id)?
+                .ok_or_else(|| anyhow::anyhow!("Action with id (#{}) not found!", id))?;
             let proceed = ask_permission(
-                &format!("Do you want to undo: {}? (y/N)", action.action.to_string()),
+                &format!("Do you want to revert: {}? (y/N)", action.action),
                 force,
             )?;
             if proceed {
-                let result = repositories::undo_redo_operation(&mut conn, action)?;
-                println!("{}", result)
+                let reverted = repositories::revert_past_action(&conn, id)?;
+                match output {
+                    OutputFormatEnum::Text => println!("[Revert]{}", reverted.action),
+                    _ => print_item(
+                        output,
+                        &UndoRedoOutput {
+                            operation: "revert",
+                            action: ActionOutput::from(reverted.clone()),
+                            actions: vec![ActionOutput::from(reverted)],
+                        },
+                    )?,
+                }
             } else {
-                println!("Operation Canceled")
+                print_canceled(output)?;
             }
         }
-        command::RootCommandsEnum::Redo { force } => {
-            let repository = ActionRepository::create(&conn);
+        command::RootCommandsEnum::Actions {
+            command: Some(command::ActionsCommandsEnum::Show { id }),
+            ..
+        } => {
+            let action = ActionRepository::create(&conn)
+                .get_action(id)?
+                .ok_or_else(|| anyhow::anyhow!("Action with id (#{}) not found!", id))?;
+            let detail = ActionDetailOutput::create(&conn, action)?;
 
-            let action = repository.get_first_restored_action()?;
-            let proceed = ask_permission(
-                &format!("Do you want to redo: {}? (y/N)", action.action.to_string()),
-                force,
-            )?;
-            if proceed {
-                let result = repositories::undo_redo_operation(&mut conn, action)?;
-                println!("{}", result)
-            } else {
-                println!("Operation Canceled")
+            match output {
+                OutputFormatEnum::Text => {
+                    let header = format!("========== Action (#{}) ==========", id);
+                    println!("{}", header);
+                    println!(
+                        "<{}> - [Restored: {}] - [{}]",
+                        detail.action.summary, detail.action.restored, detail.action.created_at
+                    );
+                    for task in &detail.tasks {
+                        let state = if task.exists { "now" } else { "deleted" };
+                        println!(
+                            "---------- Task (#{}) - logged / {} ----------",
+                            task.task_id, state
+                        );
+                        for field in &task.fields {
+                            let value = |value: &Option<String>| {
+                                value.clone().unwrap_or_else(|| "(none)".to_string())
+                            };
+                            if !field.changed {
+                                println!("  {}: {}", field.field, value(&field.logged));
+                                continue;
+                            }
+                            println!("- {}: {}", field.field, value(&field.logged));
+                            if task.exists {
+                                println!("+ {}: {}", field.field, value(&field.current));
+                            }
+                        }
+                    }
+                    println!("{}", "=".repeat(header.len()));
+                }
+                _ => print_item(output, &detail)?,
             }
         }
-        command::RootCommandsEnum::Actions { limit } => {
+        command::RootCommandsEnum::Actions {
+            limit,
+            task,
+            kind,
+            since,
+            until,
+            restored,
+            command: None,
+        } => {
             let repository = ActionRepository::create(&conn);
 
-            let actions = repository.fetch_actions(limit)?;
+            let filter = ActionFilter {
+                task_id: task,
+                kind,
+                since,
+                until,
+                restored,
+            };
+            let actions = repository.fetch_actions(Some(limit), &filter)?;
 
-            println!("========== ACTIONS ==========");
-            for action in actions {
-                println!(
-                    "(#{}) - <{}> - [Restored: {}] - [{}]",
-                    action.id,
-                    action.action.to_string(),
-                    action.restored,
-                    action.created_at
-                );
+            match output {
+                OutputFormatEnum::Text => {
+                    println!("========== ACTIONS ==========");
+                    for action in actions {
+                        println!(
+                            "(#{}) - <{}> - [Restored: {}] - [{}]",
+                            action.id, action.action, action.restored, action.created_at
+                        );
+                    }
+                }
+                _ => {
+                    let actions: Vec<ActionOutput> =
+                        actions.into_iter().map(ActionOutput::from).collect();
+                    print_items(output, &actions)?;
+                }
             }
         }
+        command::RootCommandsEnum::View { command } => match command {
+            command::ViewCommandsEnum::Save { name, arguments } => {
+                let query = ListArgs::parse_view_query(&arguments)?;
+
+                let view = ViewRepository::create(&conn).save_view(&name, &query, get_today())?;
+
+                match output {
+                    OutputFormatEnum::Text => println!(
+                        "[View][Save] - [{}] - [{}]",
+                        view.name,
+                        serde_json::to_string(&view.query)?
+                    ),
+                    _ => print_item(output, &ViewOutput::from(view))?,
+                }
+            }
+            command::ViewCommandsEnum::Run { name, arguments } => {
+                let view = ViewRepository::create(&conn)
+                    .get_view(&name)?
+                    .ok_or_else(|| anyhow::anyhow!("View [{}] not found!", name))?;
+
+                let args = ListArgs::parse_view(&view.query, &arguments)?;
+                list_tasks(&conn, output, args)?;
+            }
+            command::ViewCommandsEnum::List => {
+                let views = ViewRepository::create(&conn).fetch_views()?;
+
+                match output {
+                    OutputFormatEnum::Text => {
+                        println!("========== Views ==========");
+                        for view in views {
+                            println!(
+                                "[{}] - [{}]",
+                                view.name,
+                                serde_json::to_string(&view.query)?
+                            );
+                        }
+                    }
+                    _ => {
+                        let views: Vec<ViewOutput> =
+                            views.into_iter().map(ViewOutput::from).collect();
+                        print_items(output, &views)?;
+                    }
+                }
+            }
+            command::ViewCommandsEnum::Delete { name } => {
+                let repository = ViewRepository::create(&conn);
+
+                let view = repository
+                    .get_view(&name)?
+                    .ok_or_else(|| anyhow::anyhow!("View [{}] not found!", name))?;
+                repository.delete_view(&name)?;
+
+                match output {
+                    OutputFormatEnum::Text => println!("[View][Delete] - [{}]", view.name),
+                    _ => print_item(output, &ViewOutput::from(view))?,
+                }
+            }
+        },
         command::RootCommandsEnum::Category { command } => match command {
             command::CategoryCommandsEnum::List => {
                 let repository = CategoryRepository::create(&conn);
 
                 let categories = repository.all_categories()?;
 
-                println!("========== Categories ==========");
-                for category in categories {
-                    println!("(#{}) - [Count: {}]", category.0, category.1);
+                match output {
+                    OutputFormatEnum::Text => {
+                        println!("========== Categories ==========");
+                        for category in categories {
+                            println!("(#{}) - [Count: {}]", category.0, category.1);
+                        }
+                    }
+                    _ => {
+                        let categories: Vec<CategoryOutput> = categories
+                            .into_iter()
+                            .map(|(name, count)| CategoryOutput { name, count })
+                            .collect();
+                        print_items(output, &categories)?;
+                    }
                 }
             }
             command::CategoryCommandsEnum::Add { task_id, category } => {
-                repositories::add_category_to_task(&mut conn, task_id, &category)?;
-                println!(
-                    "[Category][Created] - (#{}) - [Task: {}]",
-                    category, task_id
-                );
+                repositories::add_category_to_task(&conn, task_id, &category)?;
+                match output {
+                    OutputFormatEnum::Text => println!(
+                        "[Category][Created] - (#{}) - [Task: {}]",
+                        category, task_id
+                    ),
+                    _ => print_item(
+                        output,
+                        &CategoryChangeOutput {
+                            category,
+                            new_category: None,
+                            task_id: Some(task_id),
+                        },
+                    )?,
+                }
             }
             command::CategoryCommandsEnum::Rename {
                 task_id,
                 old_category,
                 new_category,
             } => {
-                repositories::rename_task_category(
-                    &mut conn,
-                    task_id,
-                    &old_category,
-                    &new_category,
-                )?;
-                println!(
-                    "[Category][Renamed] - (From: {}) - (To: {}) - [Task: {}]",
-                    old_category, new_category, task_id
-                );
+                repositories::rename_task_category(&conn, task_id, &old_category, &new_category)?;
+                match output {
+                    OutputFormatEnum::Text => println!(
+                        "[Category][Renamed] - (From: {}) - (To: {}) - [Task: {}]",
+                        old_category, new_category, task_id
+                    ),
+                    _ => print_item(
+                        output,
+                        &CategoryChangeOutput {
+                            category: old_category,
+                            new_category: Some(new_category),
+                            task_id: Some(task_id),
+                        },
+                    )?,
+                }
             }
             command::CategoryCommandsEnum::Remove { task_id, category } => {
-                repositories::remove_task_category(&mut conn, task_id, &category)?;
-                println!(
-                    "[Category][Removed] - (#{}) - [Task: {}]",
-                    category, task_id
-                );
+                repositories::remove_task_category(&conn, task_id, &category)?;
+                match output {
+                    OutputFormatEnum::Text => println!(
+                        "[Category][Removed] - (#{}) - [Task: {}]",
+                        category, task_id
+                    ),
+                    _ => print_item(
+                        output,
+                        &CategoryChangeOutput {
+                            category,
+                            new_category: None,
+                            task_id: Some(task_id),
+                        },
+                    )?,
+                }
             }
             command::CategoryCommandsEnum::BatchRename {
                 old_category,
                 new_category,
             } => {
-                repositories::batch_rename_category(&mut conn, &old_category, &new_category)?;
-                println!(
-                    "[Category][Batch][Rename] - (From: {}) - (To: {})",
-                    old_category, new_category
-                );
+                repositories::batch_rename_category(&conn, &old_category, &new_category)?;
+                match output {
+                    OutputFormatEnum::Text => println!(
+                        "[Category][Batch][Rename] - (From: {}) - (To: {})",
+                        old_category, new_category
+                    ),
+                    _ => print_item(
+                        output,
+                        &CategoryChangeOutput {
+                            category: old_category,
+                            new_category: Some(new_category),
+                            task_id: None,
+                        },
+                    )?,
+                }
             }
             command::CategoryCommandsEnum::BatchDelete { category } => {
-                repositories::batch_delete_category(&mut conn, &category)?;
-                println!("[Category][Batch][Delete] - (#{})", category);
+                repositories::batch_delete_category(&conn, &category)?;
+                match output {
+                    OutputFormatEnum::Text => {
+                        println!("[Category][Batch][Delete] - (#{})", category)
+                    }
+                    _ => print_item(
+                        output,
+                        &CategoryChangeOutput {
+                            category,
+                            new_category: None,
+                            task_id: None,
+                        },
+                    )?,
+                }
             }
         },
-        command::RootCommandsEnum::Housekeeping => {
-            let proceed = ask_permission("This operation is going to:\n 1) Delete all actions\n 2) Delete all archived tasks\n 3) Archive all completed tasks\n(y/N)", false)?;
+        command::RootCommandsEnum::Db { command } => match command {
+            command::DbCommandsEnum::Status => {
+                let version = database_version(&conn)?;
+                let migrations: Vec<MigrationOutput> = MIGRATIONS
+                    .iter()
+                    .map(|migration| MigrationOutput::create(migration, version))
+                    .collect();
+
+                match output {
+                    OutputFormatEnum::Text => {
+                        println!("========== Database ==========");
+                        println!("Version: {} (latest: {})", version, latest_version());
+                        if version > latest_version() {
+                            println!("Written by a newer todo-cli, please upgrade it");
+                        }
+                        for migration in migrations {
+                            let state = match migration.applied {
+                                true => "Applied",
+                                false => "Pending",
+                            };
+                            println!(
+                                "[{}] - [{}] - [{}]",
+                                migration.version, state, migration.description
+                            );
+                        }
+                    }
+                    _ => print_item(
+                        output,
+                        &DatabaseOutput {
+                            version,
+                            latest_version: latest_version(),
+                            migrations,
+                        },
+                    )?,
+                }
+            }
+            command::DbCommandsEnum::Migrate => {
+                let applied = migrate(&conn)?;
+                let version = database_version(&conn)?;
+
+                match output {
+                    OutputFormatEnum::Text => {
+                        for migration in &applied {
+                            println!(
+                                "[Migration][Applied] - [{}] - [{}]",
+                                migration.version, migration.description
+                            );
+                        }
+                        match applied.is_empty() {
+                            true => println!("[Database] - [Version: {}] - up to date", version),
+                            false => println!("[Database] - [Version: {}]", version),
+                        }
+                    }
+                    _ => print_item(
+                        output,
+                        &DatabaseOutput {
+                            version,
+                            latest_version: latest_version(),
+                            migrations: applied
+                                .iter()
+                                .map(|migration| MigrationOutput::create(migration, version))
+                                .collect(),
+                        },
+                    )?,
+                }
+            }
+        },
+        command::RootCommandsEnum::Reindex => {
+            let tasks_indexed = TaskRepository::create(&conn).reindex()?;
+
+            match output {
+                OutputFormatEnum::Text => println!("[Reindex] - [Tasks: {}]", tasks_indexed),
+                _ => print_item(output, &ReindexOutput { tasks_indexed })?,
+            }
+        }
+        command::RootCommandsEnum::Housekeeping {
+            purge_archived_older_than,
+            archive_done_older_than,
+            keep_actions,
+            dry_run,
+            force,
+        } => {
+            // without a selected step every step runs on everything
+            let all = purge_archived_older_than.is_none()
+                && archive_done_older_than.is_none()
+                && keep_actions.is_none();
+            let payload = HousekeepingPayload {
+                purge_archived_until: purge_archived_older_than.or(all.then(get_today)),
+                archive_done_until: archive_done_older_than.or(all.then(get_today)),
+                keep_actions: keep_actions.or(all.then_some(0)),
+                dry_run,
+            };
+
+            let mut steps = vec![];
+            if let Some(until) = payload.purge_archived_until {
+                steps.push(format!("Delete the tasks archived on or before {}", until));
+            }
+            if let Some(until) = payload.archive_done_until {
+                steps.push(format!(
+                    "Archive the tasks completed on or before {}",
+                    until
+                ));
+            }
+            match payload.keep_actions {
+                Some(0) => steps.push("Delete all actions".to_string()),
+                Some(keep) => steps.push(format!("Delete all actions but the last {}", keep)),
+                None => {}
+            }
+            let steps: Vec<String> = steps
+                .iter()
+                .enumerate()
+                .map(|(index, step)| format!(" {}) {}", index + 1, step))
+                .collect();
+
+            // a dry run changes nothing, so there is nothing to confirm
+            let proceed = dry_run
+                || ask_permission(
+                    &format!("This operation is going to:\n{}\n(y/N)", steps.join("\n")),
+                    force,
+                )?;
 
             if proceed {
-                let (actions_deleted, tasks_deleted, tasks_updated) =
-                    repositories::clean_database(&conn)?;
-                println!(
-                    "[Actions deleted: {}] - [Tasks deleted: {}] - [Tasks updated: {}]",
-                    actions_deleted, tasks_deleted, tasks_updated
-                );
+                let report = repositories::clean_database(&conn, &payload)?;
+                match output {
+                    OutputFormatEnum::Text => {
+                        let prefix = if dry_run { "[Dry run] - " } else { "" };
+                        println!(
+                            "{}[Actions deleted: {}] - [Tasks deleted: {}] - [Tasks updated: {}]",
+                            prefix,
+                            report.actions_deleted,
+                            report.purged_task_ids.len(),
+                            report.archived_task_ids.len()
+                        );
+                        if !report.purged_task_ids.is_empty() {
+                            println!("Deleted: {}", format_ids(&report.purged_task_ids));
+                        }
+                        if !report.archived_task_ids.is_empty() {
+                            println!("Archived: {}", format_ids(&report.archived_task_ids));
+                        }
+                    }
+                    _ => print_item(
+                        output,
+                        &HousekeepingOutput {
+                            actions_deleted: report.actions_deleted,
+                            tasks_deleted: report.purged_task_ids.len() as i64,
+                            tasks_updated: report.archived_task_ids.len() as i64,
+                            deleted_task_ids: report.purged_task_ids,
+                            archived_task_ids: report.archived_task_ids,
+                            dry_run,
+                        },
+                    )?,
+                }
             } else {
-                println!("Operation Canceled")
+                print_canceled(output)?;
             }
         }
     }
@@ -298,3 +855,230 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/**
+ * Used to fetch the tasks targeted by a command, in the given order and without duplicates
+ */
+fn get_tasks(conn: &Connection, ids: &[i64]) -> Result<Vec<Task>> {
+    let repository = TaskRepository::create(conn);
+
+    let mut tasks: Vec<Task> = vec![];
+    for id in ids {
+        match repository.get_task(*id)? {
+            Some(task) if !tasks.iter().any(|other| other.id == task.id) => tasks.push(task),
+            Some(_) => {}
+            None => return Err(anyhow::anyhow!("Task with id (#{}) not found!", id)),
+        }
+    }
+
+    Ok(tasks)
+}
+
+/**
+ * Used to build the confirmation question of a command, naming every task it changes
+ */
+fn task_question(operation: &str, tasks: &[Task]) -> String {
+    match tasks {
+        [task] => format!(
+            "Do you want to {} task (#{}) - [{}]? (y/N)",
+            operation, task.id, task.title
+        ),
+        tasks => {
+            let mut question = format!("Do you want to {} {} tasks:", operation, tasks.len());
+            for task in tasks {
+                question.push_str(&format!("\n (#{}) - [{}]", task.id, task.title));
+            }
+            question.push_str("\n(y/N)");
+            question
+        }
+    }
+}
+
+/**
+ * Used to print the task changes of a command, a single object when it was given a single id
+ */
+fn print_task_changes(
+    output: OutputFormatEnum,
+    ids: usize,
+    changes: &[TaskChangeOutput],
+) -> Result<()> {
+    match changes {
+        [change] if ids == 1 => print_item(output, change),
+        changes => print_items(output, changes),
+    }
+}
+
+/**
+ * Used to report an operation the user did not confirm
+ */
+fn print_canceled(output: OutputFormatEnum) -> Result<()> {
+    match output {
+        OutputFormatEnum::Text => println!("Operation Canceled"),
+        _ => print_item(output, &CanceledOutput { canceled: true })?,
+    }
+
+    Ok(())
+}
+
+/**
+ * Used to preview, confirm and revert the actions of an undo or redo, in the given order
+ */
+fn revert_actions(
+    conn: &Connection,
+    output: OutputFormatEnum,
+    operation: &'static str,
+    actions: Vec<Action>,
+    force: bool,
+) -> Result<()> {
+    let message = match actions.as_slice() {
+        [action] => format!("Do you want to {}: {}? (y/N)", operation, action.action),
+        actions => {
+            let mut message = format!("Do you want to {} {} actions:", operation, actions.len());
+            for action in actions {
+                message.push_str(&format!("\n (#{}) - <{}>", action.id, action.action));
+            }
+            message.push_str("\n(y/N)");
+            message
+        }
+    };
+    if !ask_permission(&message, force)? {
+        return print_canceled(output);
+    }
+
+    let mut reverted = vec![];
+    for action in actions {
+        reverted.push(ActionOutput::from(action.clone()));
+        let result = repositories::undo_redo_operation(conn, action)?;
+        if let OutputFormatEnum::Text = output {
+            println!("{}", result);
+        }
+    }
+
+    if !matches!(output, OutputFormatEnum::Text) {
+        print_item(
+            output,
+            &UndoRedoOutput {
+                operation,
+                action: reverted[0].clone(),
+                actions: reverted,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Used to print a page of tasks matching the `task list` arguments
+ */
+fn list_tasks(conn: &Connection, output: OutputFormatEnum, args: ListArgs) -> Result<()> {
+    let sort = args.sort_keys();
+    let ListArgs {
+        status,
+        priority,
+        categories,
+        all_categories,
+        exclude_categories,
+        text,
+        filter,
+        due_before,
+        due_after,
+        overdue,
+        due_today,
+        due_within,
+        no_deadline,
+        created_since,
+        updated_since,
+        blocked,
+        ready,
+        limit,
+        offset,
+        page,
+        after,
+        columns,
+        ..
+    } = args;
+
+    let offset = match page {
+        Some(page) => (page - 1).checked_mul(limit).ok_or_else(|| {
+            anyhow::anyhow!(
+                "[Page] - [page: {}] - [limit: {}] - the page is out of range",
+                page,
+                limit
+            )
+        })?,
+        None => offset.unwrap_or(0),
+    };
+    let today = get_today();
+    let (due_after, due_before) = match (due_today, due_within) {
+        (true, _) => (today.previous_day(), today.next_day()),
+        (false, Some(end)) => (today.previous_day(), end.next_day()),
+        (false, None) => (due_after, due_before),
+    };
+    let payload = QueryTaskPayload {
+        status,
+        priority,
+        any_categories: categories,
+        all_categories,
+        exclude_categories,
+        text,
+        due_before,
+        due_after,
+        overdue,
+        no_deadline,
+        created_since,
+        updated_since,
+        blocked,
+        ready,
+        filter,
+        limit,
+        offset,
+        after,
+        sort,
+    };
+    // subtasks are grouped under their parent unless the order is given
+    let grouped = payload.sort.is_empty();
+    let page = query_tasks(conn, payload)?;
+    let summary = match page.tasks.len() as u64 {
+        0 => format!("showing 0 of {}", page.total),
+        count => format!(
+            "showing {}–{} of {}",
+            page.start + 1,
+            page.start + count,
+            page.total
+        ),
+    };
+    let next_page = page
+        .next_cursor
+        .as_ref()
+        .map(|cursor| format!("next page: --after {}", cursor.encode()));
+    match output {
+        OutputFormatEnum::Text => {
+            let tasks = match grouped {
+                true => task_tree(page.tasks),
+                false => page.tasks.into_iter().map(|task| (0, task)).collect(),
+            };
+            let table = task_table(conn, tasks, &columns, get_local_now())?;
+            print!("{}", table.render(terminal_width(), use_colors()));
+            println!("{}", summary);
+            if let Some(next_page) = next_page {
+                println!("{}", next_page);
+            }
+        }
+        _ => {
+            let tasks = page
+                .tasks
+                .into_iter()
+                .map(|task| TaskOutput::create(conn, task))
+                .collect::<Result<Vec<_>>>()?;
+            print_items(output, &tasks)?;
+            // keep stdout a plain list, the position goes to stderr
+            eprintln!("{}", summary);
+            if let Some(next_page) = next_page {
+                eprintln!("{}", next_page);
+            }
+        }
+    }
+
+    Ok(())
+}
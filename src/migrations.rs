@@ -0,0 +1,656 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use sea_query::{ColumnDef, ForeignKey, Index, SqliteQueryBuilder, Table};
+
+use crate::{
+    command::ListArgs,
+    models::{
+        ActionIden, TaskCategoryIden, TaskDependencyIden, TaskIden, TaskPriorityEnum, ViewIden,
+    },
+};
+
+/**
+ * A change of the database schema, applied once in order of version
+ */
+pub struct Migration {
+    /// the `user_version` of a database once the migration is applied
+    pub version: i64,
+    pub description: &'static str,
+    up: fn(&Connection) -> Result<()>,
+}
+
+/// all migrations in order, a new migration takes the next version
+pub const MIGRATIONS: [Migration; 5] = [
+    Migration {
+        version: 1,
+        description: "Create the tasks, actions, categories and dependencies tables",
+        up: create_tables,
+    },
+    Migration {
+        version: 2,
+        description: "Index task categories in full text search",
+        up: index_categories,
+    },
+    Migration {
+        version: 3,
+        description: "Create the saved views table",
+        up: create_views_table,
+    },
+    Migration {
+        version: 4,
+        description: "Record the time of day actions are logged at",
+        up: add_action_logged_at,
+    },
+    Migration {
+        version: 5,
+        description: "Store saved views as queries instead of command line arguments",
+        up: store_view_queries,
+    },
+];
+
+/**
+ * Used to get the schema version this binary understands
+ */
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/**
+ * Used to read the schema version of a database, 0 for a new or unversioned database
+ */
+pub fn database_version(conn: &Connection) -> Result<i64> {
+    let version = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    Ok(version)
+}
+
+/**
+ * Used to apply the missing migrations in order, returns the applied migrations.
+ * Each migration and its version change are applied in a savepoint, so a failed
+ * migration leaves the database at the previous version, also inside a transaction.
+ */
+pub fn migrate(conn: &Connection) -> Result<Vec<&'static Migration>> {
+    run_migrations(conn, &MIGRATIONS)
+}
+
+/**
+ * Used to apply the missing migrations of a list, see `migrate`
+ */
+fn run_migrations<'a>(
+    conn: &Connection,
+    migrations: &'a [Migration],
+) -> Result<Vec<&'a Migration>> {
+    let version = database_version(conn)?;
+    let latest = migrations.last().map_or(0, |migration| migration.version);
+    if version > latest {
+        anyhow::bail!(
+            "[Database] - [version: {}] - [supported: {}] - the data file was written by a newer todo-cli, please upgrade it",
+            version,
+            latest
+        );
+    }
+
+    let pending: Vec<&Migration> = migrations
+        .iter()
+        .filter(|migration| migration.version > version)
+        .collect();
+    for migration in &pending {
+        conn.execute_batch("SAVEPOINT migration")?;
+
+        let result = (migration.up)(conn).and_then(|_| {
+            conn.pragma_update(None, "user_version", migration.version)?;
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => conn.execute_batch("RELEASE migration")?,
+            Err(err) => {
+                conn.execute_batch("ROLLBACK TO migration; RELEASE migration")?;
+                return Err(err.context(format!(
+                    "[Migration] - [version: {}] - [{}] failed",
+                    migration.version, migration.description
+                )));
+            }
+        }
+    }
+
+    Ok(pending)
+}
+
+/**
+ * Used to check if a table of the database has a column
+ */
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let exists = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        (table, column),
+        |row| row.get(0),
+    )?;
+
+    Ok(exists)
+}
+
+/**
+ * Used to create the first schema. Data files made before versioning already have
+ * these tables, so every statement keeps `IF NOT EXISTS` and the missing task columns are added.
+ */
+fn create_tables(conn: &Connection) -> Result<()> {
+    let tasks_table = Table::create()
+        .table(TaskIden::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(TaskIden::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(TaskIden::Title).text().not_null())
+        .col(ColumnDef::new(TaskIden::Info).text())
+        .col(ColumnDef::new(TaskIden::Deadline).text())
+        .col(ColumnDef::new(TaskIden::Status).text().not_null())
+        .col(
+            ColumnDef::new(TaskIden::Priority)
+                .integer()
+                .not_null()
+                .default(TaskPriorityEnum::Normal.as_i64()),
+        )
+        .col(ColumnDef::new(TaskIden::ParentId).integer())
+        .col(ColumnDef::new(TaskIden::Recurrence).text())
+        .col(ColumnDef::new(TaskIden::UpdatedAt).text().not_null())
+        .col(ColumnDef::new(TaskIden::CreatedAt).text().not_null())
+        .foreign_key(
+            ForeignKey::create()
+                .name("TASK_PARENT_FK")
+                .from(TaskIden::Table, TaskIden::ParentId)
+                .to(TaskIden::Table, TaskIden::Id),
+        )
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&tasks_table, ())?;
+
+    // unversioned data files have the tasks table without the columns added since
+    let task_columns = [
+        ColumnDef::new(TaskIden::Priority)
+            .integer()
+            .not_null()
+            .default(TaskPriorityEnum::Normal.as_i64())
+            .to_owned(),
+        ColumnDef::new(TaskIden::ParentId)
+            .integer()
+            .extra("REFERENCES \"tasks\" (\"id\")")
+            .to_owned(),
+        ColumnDef::new(TaskIden::Recurrence).text().to_owned(),
+    ];
+    for mut column in task_columns {
+        let name = column.get_column_name();
+        if has_column(conn, "tasks", &name)? {
+            continue;
+        }
+
+        let alter_tasks = Table::alter()
+            .table(TaskIden::Table)
+            .add_column(&mut column)
+            .to_string(SqliteQueryBuilder);
+        conn.execute(&alter_tasks, ())?;
+    }
+
+    let tasks_fts_table = "
+        CREATE VIRTUAL TABLE IF NOT EXISTS tasks_fts USING fts5 (id UNINDEXED, title, info);
+    ";
+    conn.execute(tasks_fts_table, ())?;
+
+    let idx = Index::create()
+        .if_not_exists()
+        .name("TASK_DEADLINE_IDX")
+        .col(TaskIden::Deadline)
+        .table(TaskIden::Table)
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&idx, ())?;
+
+    let idx = Index::create()
+        .if_not_exists()
+        .name("TASK_STATUS_IDX")
+        .col(TaskIden::Status)
+        .table(TaskIden::Table)
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&idx, ())?;
+
+    let idx = Index::create()
+        .if_not_exists()
+        .name("TASK_PRIORITY_IDX")
+        .col(TaskIden::Priority)
+        .table(TaskIden::Table)
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&idx, ())?;
+
+    let idx = Index::create()
+        .if_not_exists()
+        .name("TASK_PARENT_IDX")
+        .col(TaskIden::ParentId)
+        .table(TaskIden::Table)
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&idx, ())?;
+
+    let idx = Index::create()
+        .if_not_exists()
+        .name("TASK_CREATED_AT_IDX")
+        .col(TaskIden::CreatedAt)
+        .table(TaskIden::Table)
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&idx, ())?;
+
+    let actions_table = Table::create()
+        .table(ActionIden::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(ActionIden::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(ActionIden::Action)
+                .blob(sea_query::BlobSize::Long)
+                .not_null(),
+        )
+        .col(ColumnDef::new(ActionIden::Restored).boolean().not_null())
+        .col(ColumnDef::new(ActionIden::CreatedAt).text().not_null())
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&actions_table, ())?;
+
+    let idx = Index::create()
+        .if_not_exists()
+        .name("ACTION_RESTORED_IDX")
+        .col(ActionIden::Restored)
+        .table(ActionIden::Table)
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&idx, ())?;
+
+    let task_categories_table = Table::create()
+        .table(TaskCategoryIden::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(TaskCategoryIden::TaskId)
+                .integer()
+                .not_null(),
+        )
+        .col(ColumnDef::new(TaskCategoryIden::Category).text().not_null())
+        .foreign_key(
+            ForeignKey::create()
+                .name("TASK_CATEGORY_TASK_FK")
+                .from(TaskCategoryIden::Table, TaskCategoryIden::TaskId)
+                .to(TaskIden::Table, TaskIden::Id),
+        )
+        .primary_key(
+            Index::create()
+                .col(TaskCategoryIden::TaskId)
+                .col(TaskCategoryIden::Category),
+        )
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&task_categories_table, ())?;
+
+    let idx = Index::create()
+        .if_not_exists()
+        .name("TASK_CATEGORY_CATEGORY_IDX")
+        .col(TaskCategoryIden::Category)
+        .table(TaskCategoryIden::Table)
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&idx, ())?;
+
+    let task_dependencies_table = Table::create()
+        .table(TaskDependencyIden::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(TaskDependencyIden::TaskId)
+                .integer()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(TaskDependencyIden::DependsOnId)
+                .integer()
+                .not_null(),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("TASK_DEPENDENCY_TASK_FK")
+                .from(TaskDependencyIden::Table, TaskDependencyIden::TaskId)
+                .to(TaskIden::Table, TaskIden::Id),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("TASK_DEPENDENCY_DEPENDS_ON_FK")
+                .from(TaskDependencyIden::Table, TaskDependencyIden::DependsOnId)
+                .to(TaskIden::Table, TaskIden::Id),
+        )
+        .primary_key(
+            Index::create()
+                .col(TaskDependencyIden::TaskId)
+                .col(TaskDependencyIden::DependsOnId),
+        )
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&task_dependencies_table, ())?;
+
+    let idx = Index::create()
+        .if_not_exists()
+        .name("TASK_DEPENDENCY_DEPENDS_ON_IDX")
+        .col(TaskDependencyIden::DependsOnId)
+        .table(TaskDependencyIden::Table)
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&idx, ())?;
+
+    Ok(())
+}
+
+/**
+ * Used to add the categories column to the full text index, filled from the existing tasks
+ */
+fn index_categories(conn: &Connection) -> Result<()> {
+    // unversioned data files may already have the column
+    if has_column(conn, "tasks_fts", "categories")? {
+        return Ok(());
+    }
+
+    conn.execute("DROP TABLE tasks_fts", ())?;
+    conn.execute(
+        "CREATE VIRTUAL TABLE tasks_fts USING fts5 (id UNINDEXED, title, info, categories)",
+        (),
+    )?;
+    conn.execute(
+        "INSERT INTO tasks_fts (id, title, info, categories)
+        SELECT id, title, info, (
+            SELECT group_concat(category, ' ') FROM task_categories WHERE task_id = tasks.id
+        ) FROM tasks",
+        (),
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Used to create the table of saved views
+ */
+fn create_views_table(conn: &Connection) -> Result<()> {
+    let views_table = Table::create()
+        .table(ViewIden::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(ViewIden::Name)
+                .text()
+                .not_null()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(ViewIden::Arguments).text().not_null())
+        .col(ColumnDef::new(ViewIden::UpdatedAt).text().not_null())
+        .col(ColumnDef::new(ViewIden::CreatedAt).text().not_null())
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&views_table, ())?;
+
+    Ok(())
+}
+
+/**
+ * Used to add the moment of each action next to its day, the actions logged before stay without it
+ */
+fn add_action_logged_at(conn: &Connection) -> Result<()> {
+    let alter_actions = Table::alter()
+        .table(ActionIden::Table)
+        .add_column(ColumnDef::new(ActionIden::LoggedAt).text())
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&alter_actions, ())?;
+
+    Ok(())
+}
+
+/**
+ * Used to replace the command line arguments stored by each view with the query they describe
+ */
+fn store_view_queries(conn: &Connection) -> Result<()> {
+    let alter_views = Table::alter()
+        .table(ViewIden::Table)
+        .add_column(
+            ColumnDef::new(ViewIden::Query)
+                .text()
+                .not_null()
+                .default("{}"),
+        )
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&alter_views, ())?;
+
+    let views = conn
+        .prepare("SELECT name, arguments FROM views")?
+        .query_map((), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (name, arguments) in views {
+        let query = serde_json::from_str(&arguments)
+            .map_err(anyhow::Error::from)
+            .and_then(|arguments: Vec<String>| ListArgs::parse_legacy_view_query(&arguments))
+            .with_context(|| {
+                format!(
+                    "[View] - [name: {}] - the saved arguments are invalid",
+                    name
+                )
+            })?;
+        conn.execute(
+            "UPDATE views SET query = ?1 WHERE name = ?2",
+            (serde_json::to_string(&query)?, name),
+        )?;
+    }
+
+    let alter_views = Table::alter()
+        .table(ViewIden::Table)
+        .drop_column(ViewIden::Arguments)
+        .to_string(SqliteQueryBuilder);
+    conn.execute(&alter_views, ())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use rusqlite::Connection;
+
+    use crate::{
+        models::{
+            setup_database, ActionFilter, QueryTaskPayload, TaskPriorityEnum, TaskStatusEnum,
+        },
+        repositories::{
+            action_repository::ActionRepository, category_repository::CategoryRepository,
+            dependency_repository::DependencyRepository, query_tasks,
+            task_repository::TaskRepository, undo_redo_operation, view_repository::ViewRepository,
+        },
+    };
+
+    use super::{database_version, latest_version, migrate, run_migrations, Migration, MIGRATIONS};
+
+    const UNVERSIONED: &str = include_str!("../tests/fixtures/unversioned.sql");
+
+    fn search(conn: &Connection, text: Option<&str>) -> Result<Vec<i64>> {
+        let payload = QueryTaskPayload {
+            status: None,
+            priority: None,
+            any_categories: None,
+            all_categories: None,
+            exclude_categories: None,
+            text: text.map(String::from),
+            due_before: None,
+            due_after: None,
+            overdue: false,
+            no_deadline: false,
+            created_since: None,
+            updated_since: None,
+            blocked: false,
+            ready: false,
+            filter: None,
+            limit: 10,
+            offset: 0,
+            after: None,
+            sort: vec![],
+        };
+
+        Ok(query_tasks(conn, payload)?
+            .tasks
+            .iter()
+            .map(|task| task.id)
+            .collect())
+    }
+
+    #[test]
+    fn test_migrate_new_database() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        assert_eq!(0, database_version(&conn)?);
+
+        let applied = migrate(&conn)?;
+        assert_eq!(latest_version() as usize, applied.len());
+        assert_eq!(latest_version(), database_version(&conn)?);
+
+        // test migrating again does nothing
+        assert!(migrate(&conn)?.is_empty());
+        assert_eq!(latest_version(), database_version(&conn)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_unversioned_database() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(UNVERSIONED)?;
+        assert_eq!(0, database_version(&conn)?);
+
+        setup_database(&conn)?;
+        assert_eq!(latest_version(), database_version(&conn)?);
+
+        // test the data is kept and still readable
+        let task_repository = TaskRepository::create(&conn);
+        let task = task_repository.get_task(1)?.expect("Task should exist");
+        assert_eq!("Deploy api", task.title);
+        assert_eq!(TaskPriorityEnum::Normal, task.priority);
+        assert_eq!(None, task.parent_id);
+        assert_eq!(None, task.recurrence);
+        assert_eq!(
+            vec!["server"],
+            CategoryRepository::create(&conn).fetch_task_categories(1)?
+        );
+        assert!(DependencyRepository::create(&conn)
+            .fetch_dependencies(1)?
+            .is_empty());
+        assert!(ViewRepository::create(&conn).get_view("urgent")?.is_none());
+        assert_eq!(vec![1, 2, 3], search(&conn, None)?);
+        assert_eq!(vec![1, 3], search(&conn, Some("server"))?);
+        assert_eq!(vec![1, 2, 3], search(&conn, Some("api OR login OR docs"))?);
+
+        // test the columns added to the tasks table are usable
+        conn.execute(
+            "UPDATE tasks SET priority = 3, parent_id = 1 WHERE id = 2",
+            (),
+        )?;
+        let task = task_repository.get_task(2)?.expect("Task should exist");
+        assert_eq!(TaskPriorityEnum::Critical, task.priority);
+        assert_eq!(Some(1), task.parent_id);
+
+        // test the actions logged before versioning can be undone and redone
+        let action_repository = ActionRepository::create(&conn);
+        let actions = action_repository.fetch_actions(Some(20), &ActionFilter::default())?;
+        assert_eq!(13, actions.len());
+        assert!(actions.iter().all(|action| action.logged_at.is_none()));
+
+        for action in action_repository.fetch_undo_actions(2, None)? {
+            undo_redo_operation(&conn, action)?;
+        }
+        let task = task_repository
+            .get_task(4)?
+            .expect("Task should be restored");
+        assert_eq!("Old spike", task.title);
+        assert_eq!(TaskStatusEnum::Archived, task.status);
+        assert_eq!(
+            vec!["docs", "server"],
+            CategoryRepository::create(&conn).fetch_task_categories(3)?
+        );
+
+        for action in action_repository.fetch_redo_actions(2, None)? {
+            undo_redo_operation(&conn, action)?;
+        }
+        assert!(task_repository.get_task(4)?.is_none());
+        assert_eq!(
+            vec!["server"],
+            CategoryRepository::create(&conn).fetch_task_categories(3)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_view_arguments() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        run_migrations(&conn, &MIGRATIONS[..4])?;
+        conn.execute(
+            "INSERT INTO views (name, arguments, updated_at, created_at) VALUES (?1, ?2, ?3, ?3)",
+            (
+                "week",
+                r#"["--where", "due<+7d", "--sort", "deadline", "--page", "2"]"#,
+                "2024-01-01",
+            ),
+        )?;
+
+        setup_database(&conn)?;
+
+        // test the relative dates stay relative and pagination is left out
+        let view = ViewRepository::create(&conn)
+            .get_view("week")?
+            .expect("View should exist");
+        assert_eq!(Some("due<+7d".to_string()), view.query.filter);
+        assert_eq!(vec!["deadline:asc:nulls-last"], view.query.sort);
+        assert_eq!(10, view.query.limit);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_refuse_newer_database() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+        conn.pragma_update(None, "user_version", latest_version() + 1)?;
+
+        assert!(setup_database(&conn).is_err());
+        assert_eq!(latest_version() + 1, database_version(&conn)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "Create a table",
+                up: |conn| {
+                    conn.execute("CREATE TABLE first (id INTEGER)", ())?;
+                    Ok(())
+                },
+            },
+            Migration {
+                version: 2,
+                description: "Fail halfway",
+                up: |conn| {
+                    conn.execute("CREATE TABLE second (id INTEGER)", ())?;
+                    anyhow::bail!("Broken migration")
+                },
+            },
+        ];
+
+        assert!(run_migrations(&conn, &migrations).is_err());
+        assert_eq!(1, database_version(&conn)?);
+
+        let tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")?
+            .query_map((), |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(vec!["first"], tables);
+
+        Ok(())
+    }
+}
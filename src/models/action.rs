@@ -1,19 +1,35 @@
+use std::fmt::Display;
+
+use anyhow::{Context, Result};
 use bincode::{config, Decode, Encode};
 use rusqlite::{
-    types::{FromSql, ValueRef},
+    types::{FromSql, FromSqlError, ValueRef},
     ToSql,
 };
 use sea_query::Iden;
-use time::Date;
+use serde::Serialize;
+use time::{Date, OffsetDateTime};
+
+use super::{
+    action_v0::ActionEnumV0, action_v1::ActionEnumV1, action_v2::ActionEnumV2,
+    action_v3::ActionEnumV3, action_v4::ActionEnumV4, ActionKindEnum, RecurrenceEnum,
+    TaskPriorityEnum, TaskStatusEnum,
+};
 
-use super::TaskStatusEnum;
+/// tag written before the version of a stored action, a legacy blob starts with its variant index instead
+const ACTION_BLOB_TAG: [u8; 2] = *b"TA";
 
-#[derive(Debug, PartialEq)]
+/// version of the encoding written by `ActionEnum::to_blob`, bumped on every change of the `ActionEnum` layout
+pub const ACTION_BLOB_VERSION: u8 = 5;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Action {
     pub id: i64,
     pub action: ActionEnum,
     pub restored: bool,
     pub created_at: Date,
+    /// moment the action was logged, unknown for actions logged before it was recorded
+    pub logged_at: Option<OffsetDateTime>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Iden)]
@@ -24,26 +40,53 @@ pub enum ActionIden {
     Action,
     Restored,
     CreatedAt,
+    LoggedAt,
+}
+
+/**
+ * Filters of the actions log, `task_id` and `kind` are checked on the decoded actions
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ActionFilter {
+    pub task_id: Option<i64>,
+    pub kind: Option<ActionKindEnum>,
+    pub since: Option<Date>,
+    pub until: Option<Date>,
+    pub restored: Option<bool>,
 }
 
-#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+impl ActionFilter {
+    /**
+     * Used to check the filters that need the decoded action
+     */
+    pub fn matches(&self, action: &ActionEnum) -> bool {
+        self.kind.is_none_or(|kind| action.kind() == kind)
+            && self
+                .task_id
+                .is_none_or(|task_id| action.task_ids().contains(&task_id))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ActionTypeEnum {
     Create,
     Update,
     Delete,
 }
 
-impl ToString for ActionTypeEnum {
-    fn to_string(&self) -> String {
+impl Display for ActionTypeEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ActionTypeEnum::Create => "Create".into(),
-            ActionTypeEnum::Update => "Update".into(),
-            ActionTypeEnum::Delete => "Delete".into(),
+            ActionTypeEnum::Create => f.write_str("Create"),
+            ActionTypeEnum::Update => f.write_str("Update"),
+            ActionTypeEnum::Delete => f.write_str("Delete"),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum ActionEnum {
     Task {
         action_type: ActionTypeEnum,
@@ -52,15 +95,33 @@ pub enum ActionEnum {
         info: Option<String>,
         deadline: Option<String>,
         categories: Option<Vec<String>>,
+        dependencies: Option<Vec<(i64, i64)>>,
         status: TaskStatusEnum,
+        priority: TaskPriorityEnum,
+        parent_id: Option<i64>,
+        recurrence: Option<RecurrenceEnum>,
         updated_at: String,
         created_at: String,
     },
+    TaskTree {
+        action_type: ActionTypeEnum,
+        tasks: Vec<ActionEnum>,
+        reparented: Vec<i64>,
+    },
+    TaskRecurrence {
+        update: Box<ActionEnum>,
+        create: Box<ActionEnum>,
+    },
     Category {
         action_type: ActionTypeEnum,
         category: String,
         task_id: i64,
     },
+    Dependency {
+        action_type: ActionTypeEnum,
+        task_id: i64,
+        depends_on_id: i64,
+    },
     RenameTaskCategory {
         old_category: String,
         new_category: String,
@@ -70,48 +131,201 @@ pub enum ActionEnum {
         task_ids: Vec<i64>,
         category: String,
     },
+    /// `task_ids` are the renamed tasks, empty for renames stored before version 5
     BatchCategoryRename {
         old_category: String,
         new_category: String,
+        task_ids: Vec<i64>,
+    },
+    /// revert of the past action `action_id`, `action` is the inverse that was applied, as an undone action
+    Revert {
+        action_id: i64,
+        action: Box<ActionEnum>,
     },
+    /// actions of a single command, in the order they were applied, undone and redone as one
+    Compound { actions: Vec<ActionEnum> },
 }
 
 impl ActionEnum {
-    pub fn to_blob(&self) -> Vec<u8> {
+    /**
+     * Used to get the kind of the action, as written in its JSON
+     */
+    pub fn kind(&self) -> ActionKindEnum {
+        match self {
+            ActionEnum::Task { .. } => ActionKindEnum::Task,
+            ActionEnum::TaskTree { .. } => ActionKindEnum::TaskTree,
+            ActionEnum::TaskRecurrence { .. } => ActionKindEnum::TaskRecurrence,
+            ActionEnum::Category { .. } => ActionKindEnum::Category,
+            ActionEnum::Dependency { .. } => ActionKindEnum::Dependency,
+            ActionEnum::RenameTaskCategory { .. } => ActionKindEnum::RenameTaskCategory,
+            ActionEnum::BatchCategoryDelete { .. } => ActionKindEnum::BatchCategoryDelete,
+            ActionEnum::BatchCategoryRename { .. } => ActionKindEnum::BatchCategoryRename,
+            ActionEnum::Revert { .. } => ActionKindEnum::Revert,
+            ActionEnum::Compound { .. } => ActionKindEnum::Compound,
+        }
+    }
+
+    /**
+     * Used to list the task snapshots stored in the action, including the nested actions
+     */
+    pub fn task_snapshots(&self) -> Vec<&ActionEnum> {
+        match self {
+            ActionEnum::Task { .. } => vec![self],
+            ActionEnum::TaskTree { tasks, .. } => {
+                tasks.iter().flat_map(ActionEnum::task_snapshots).collect()
+            }
+            ActionEnum::TaskRecurrence { update, create } => {
+                [update.task_snapshots(), create.task_snapshots()].concat()
+            }
+            ActionEnum::Revert { action, .. } => action.task_snapshots(),
+            ActionEnum::Compound { actions } => actions
+                .iter()
+                .flat_map(ActionEnum::task_snapshots)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /**
+     * Used to list the tasks the action changed
+     */
+    pub fn task_ids(&self) -> Vec<i64> {
+        match self {
+            ActionEnum::Task { id, .. } => vec![*id],
+            ActionEnum::TaskTree {
+                tasks, reparented, ..
+            } => tasks
+                .iter()
+                .flat_map(ActionEnum::task_ids)
+                .chain(reparented.iter().copied())
+                .collect(),
+            ActionEnum::TaskRecurrence { update, create } => {
+                [update.task_ids(), create.task_ids()].concat()
+            }
+            ActionEnum::Category { task_id, .. }
+            | ActionEnum::RenameTaskCategory { task_id, .. } => {
+                vec![*task_id]
+            }
+            ActionEnum::Dependency {
+                task_id,
+                depends_on_id,
+                ..
+            } => vec![*task_id, *depends_on_id],
+            ActionEnum::BatchCategoryDelete { task_ids, .. }
+            | ActionEnum::BatchCategoryRename { task_ids, .. } => task_ids.clone(),
+            ActionEnum::Revert { action, .. } => action.task_ids(),
+            ActionEnum::Compound { actions } => {
+                let mut task_ids = vec![];
+                for task_id in actions.iter().flat_map(ActionEnum::task_ids) {
+                    if !task_ids.contains(&task_id) {
+                        task_ids.push(task_id);
+                    }
+                }
+                task_ids
+            }
+        }
+    }
+
+    /**
+     * Used to encode the action for the actions table, tagged with `ACTION_BLOB_VERSION`
+     */
+    pub fn to_blob(&self) -> Result<Vec<u8>> {
         let config = config::standard();
 
-        let data: Vec<u8> = bincode::encode_to_vec(self, config).unwrap();
+        let mut data: Vec<u8> = ACTION_BLOB_TAG.to_vec();
+        data.push(ACTION_BLOB_VERSION);
+        data.extend(bincode::encode_to_vec(self, config).context("[Action] - cannot encode")?);
+
+        Ok(data)
+    }
+
+    /**
+     * Used to decode an action stored by any version of todo-cli
+     */
+    pub fn from_blob(data: &[u8]) -> Result<Self> {
+        let (version, payload) = match data {
+            [a, b, version, payload @ ..] if [*a, *b] == ACTION_BLOB_TAG => (*version, payload),
+            // blobs written before the tag was introduced
+            payload => (0, payload),
+        };
+
+        let action = match version {
+            0 => decode_blob::<ActionEnumV0>(payload).map(ActionEnum::from),
+            1 => decode_blob::<ActionEnumV1>(payload).map(ActionEnum::from),
+            2 => decode_blob::<ActionEnumV2>(payload).map(ActionEnum::from),
+            3 => decode_blob::<ActionEnumV3>(payload).map(ActionEnum::from),
+            4 => decode_blob::<ActionEnumV4>(payload).map(ActionEnum::from),
+            ACTION_BLOB_VERSION => decode_blob(payload),
+            version => anyhow::bail!(
+                "[Action] - [version: {}] - [supported: {}] - the action was written by a newer todo-cli, please upgrade it",
+                version,
+                ACTION_BLOB_VERSION
+            ),
+        };
 
-        data
+        action.with_context(|| {
+            format!(
+                "[Action] - [version: {}] - cannot decode the stored action",
+                version
+            )
+        })
     }
 }
 
-impl ToString for ActionEnum {
-    fn to_string(&self) -> String {
+impl Display for ActionEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ActionEnum::Task {
+                action_type, id, ..
+            } => {
+                write!(f, "[Task][{}] - (#{})", action_type, id)
+            }
+            ActionEnum::TaskTree {
                 action_type,
-                id,
-                title: _,
-                info: _,
-                deadline: _,
-                categories: _,
-                status: _,
-                updated_at: _,
-                created_at: _,
+                tasks,
+                reparented,
             } => {
-                format!("[Task][{}] - (#{})", action_type.to_string(), id)
+                let id = match tasks.first() {
+                    Some(ActionEnum::Task { id, .. }) => *id,
+                    _ => 0,
+                };
+                write!(
+                    f,
+                    "[Task][Tree][{}] - (#{}) - [Subtasks: {}]",
+                    action_type,
+                    id,
+                    tasks.len().saturating_sub(1) + reparented.len()
+                )
+            }
+            ActionEnum::TaskRecurrence { update, create } => {
+                let (id, next_id) = match (update.as_ref(), create.as_ref()) {
+                    (ActionEnum::Task { id, .. }, ActionEnum::Task { id: next_id, .. }) => {
+                        (*id, *next_id)
+                    }
+                    _ => (0, 0),
+                };
+                write!(f, "[Task][Recur] - (#{}) - [Next: #{}]", id, next_id)
             }
             ActionEnum::Category {
                 action_type,
                 category,
                 task_id,
             } => {
-                format!(
+                write!(
+                    f,
                     "[Category][{}] - (#{}) - [Task: {}]",
-                    action_type.to_string(),
-                    category,
-                    task_id
+                    action_type, category, task_id
+                )
+            }
+            ActionEnum::Dependency {
+                action_type,
+                task_id,
+                depends_on_id,
+            } => {
+                write!(
+                    f,
+                    "[Dependency][{}] - (#{}) - [Depends on: {}]",
+                    action_type, task_id, depends_on_id
                 )
             }
             ActionEnum::RenameTaskCategory {
@@ -119,49 +333,222 @@ impl ToString for ActionEnum {
                 new_category,
                 task_id,
             } => {
-                format!(
+                write!(
+                    f,
                     "[Category][Rename] - (From: {}) - (To: {}) - [Task: {}]",
                     old_category, new_category, task_id
                 )
             }
-            ActionEnum::BatchCategoryDelete {
-                task_ids: _,
-                category,
-            } => {
-                format!("[Category][Batch][Delete] - (#{})", category)
+            ActionEnum::BatchCategoryDelete { category, .. } => {
+                write!(f, "[Category][Batch][Delete] - (#{})", category)
             }
             ActionEnum::BatchCategoryRename {
                 old_category,
                 new_category,
+                ..
             } => {
-                format!(
+                write!(
+                    f,
                     "[Category][Batch][Rename] - (From: {}) - (To: {})",
                     old_category, new_category
                 )
             }
+            ActionEnum::Revert { action_id, action } => {
+                write!(f, "[Revert] - (Action: #{}) - <{}>", action_id, action)
+            }
+            ActionEnum::Compound { actions } => {
+                let task_ids: Vec<String> = self
+                    .task_ids()
+                    .iter()
+                    .map(|id| format!("#{}", id))
+                    .collect();
+                write!(
+                    f,
+                    "[Compound] - [Actions: {}] - [Tasks: {}]",
+                    actions.len(),
+                    task_ids.join(", ")
+                )
+            }
         }
     }
 }
 
-fn action_enum_from_blob(data: &[u8]) -> ActionEnum {
+/**
+ * Used to decode the bincode payload of a stored action, refusing trailing bytes
+ */
+fn decode_blob<T: Decode>(payload: &[u8]) -> Result<T> {
     let config = config::standard();
-    bincode::decode_from_slice(data, config).unwrap().0
+    let (action, read): (T, usize) = bincode::decode_from_slice(payload, config)?;
+
+    if read != payload.len() {
+        anyhow::bail!("{} unexpected trailing bytes", payload.len() - read);
+    }
+
+    Ok(action)
 }
 
 impl ToSql for ActionEnum {
     fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
-        Ok(rusqlite::types::ToSqlOutput::from(self.to_blob()))
+        self.to_blob()
+            .map(rusqlite::types::ToSqlOutput::from)
+            .map_err(|err| rusqlite::Error::ToSqlConversionFailure(err.into()))
     }
 }
 
 impl FromSql for ActionEnum {
     fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
-        if let ValueRef::Blob(data) = value {
-            rusqlite::types::FromSqlResult::Ok(action_enum_from_blob(data))
-        } else {
-            rusqlite::types::FromSqlResult::Err(rusqlite::types::FromSqlError::Other(
-                anyhow::anyhow!("Cannot parse blog ActionEnum").into(),
-            ))
+        match value {
+            ValueRef::Blob(data) => ActionEnum::from_blob(data)
+                .map_err(|err| FromSqlError::Other(format!("{:#}", err).into())),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::models::{TaskPriorityEnum, TaskStatusEnum};
+
+    use super::{ActionEnum, ActionTypeEnum, ACTION_BLOB_TAG, ACTION_BLOB_VERSION};
+
+    #[test]
+    fn test_action_blob() -> Result<()> {
+        let action = ActionEnum::Category {
+            action_type: ActionTypeEnum::Create,
+            category: "backend".into(),
+            task_id: 3,
+        };
+
+        // test the blob is tagged with its version
+        let blob = action.to_blob()?;
+        assert_eq!(ACTION_BLOB_TAG, blob[..2]);
+        assert_eq!(ACTION_BLOB_VERSION, blob[2]);
+        assert_eq!(action, ActionEnum::from_blob(&blob)?);
+
+        // test untagged blobs written before versioning are still readable
+        let legacy = b"\x01\x00\x07backend\x06";
+        assert_eq!(action, ActionEnum::from_blob(legacy)?);
+        assert_eq!(
+            ActionEnum::Task {
+                action_type: ActionTypeEnum::Create,
+                id: 1,
+                title: "Deploy api".into(),
+                info: Some("Roll out the new release".into()),
+                deadline: Some("2026-11-02".into()),
+                categories: Some(vec!["backend".into(), "urgent".into()]),
+                dependencies: None,
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                updated_at: "2026-10-18".into(),
+                created_at: "2026-10-18".into(),
+            },
+            ActionEnum::from_blob(b"\x00\x00\x02\nDeploy api\x01\x18Roll out the new release\x01\n2026-11-02\x01\x02\x07backend\x06urgent\x01\n2026-10-18\n2026-10-18")?
+        );
+        assert_eq!(
+            ActionEnum::RenameTaskCategory {
+                old_category: "bug".into(),
+                new_category: "bugfix".into(),
+                task_id: 2,
+            },
+            ActionEnum::from_blob(b"\x02\x03bug\x06bugfix\x04")?
+        );
+        assert_eq!(
+            ActionEnum::BatchCategoryDelete {
+                task_ids: vec![3],
+                category: "docs".into(),
+            },
+            ActionEnum::from_blob(b"\x03\x01\x06\x04docs")?
+        );
+        assert_eq!(
+            ActionEnum::BatchCategoryRename {
+                old_category: "backend".into(),
+                new_category: "server".into(),
+                task_ids: vec![],
+            },
+            ActionEnum::from_blob(b"\x04\x07backend\x06server")?
+        );
+
+        // test blobs of older versions are still readable
+        assert_eq!(
+            ActionEnum::Task {
+                action_type: ActionTypeEnum::Create,
+                id: 2,
+                title: "Fix login bug".into(),
+                info: None,
+                deadline: None,
+                categories: None,
+                dependencies: None,
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::High,
+                parent_id: None,
+                recurrence: None,
+                updated_at: "2026-10-18".into(),
+                created_at: "2026-10-18".into(),
+            },
+            ActionEnum::from_blob(
+                b"TA\x01\x00\x00\x04\rFix login bug\x00\x00\x00\x01\x02\n2026-10-18\n2026-10-18"
+            )?
+        );
+        assert_eq!(
+            ActionEnum::TaskTree {
+                action_type: ActionTypeEnum::Delete,
+                tasks: vec![ActionEnum::Task {
+                    action_type: ActionTypeEnum::Delete,
+                    id: 2,
+                    title: "Fix login bug".into(),
+                    info: None,
+                    deadline: None,
+                    categories: None,
+                    dependencies: None,
+                    status: TaskStatusEnum::Undone,
+                    priority: TaskPriorityEnum::High,
+                    parent_id: Some(1),
+                    recurrence: None,
+                    updated_at: "2026-10-18".into(),
+                    created_at: "2026-10-18".into(),
+                }],
+                reparented: vec![3],
+            },
+            ActionEnum::from_blob(b"TA\x02\x01\x02\x01\x00\x02\x04\rFix login bug\x00\x00\x00\x01\x02\x01\x02\n2026-10-18\n2026-10-18\x01\x06")?
+        );
+        assert_eq!(
+            ActionEnum::Dependency {
+                action_type: ActionTypeEnum::Create,
+                task_id: 1,
+                depends_on_id: 2,
+            },
+            ActionEnum::from_blob(b"TA\x03\x03\x00\x02\x04")?
+        );
+
+        // test newer and corrupt blobs are errors
+        let mut newer = blob.clone();
+        newer[2] = ACTION_BLOB_VERSION + 1;
+        assert!(ActionEnum::from_blob(&newer)
+            .unwrap_err()
+            .to_string()
+            .contains("newer todo-cli"));
+
+        // test batch renames of version 4 are read without their tasks
+        let rename = [&ACTION_BLOB_TAG[..], &[4, 7, 3], b"one", &[3], b"two"].concat();
+        assert_eq!(
+            ActionEnum::BatchCategoryRename {
+                old_category: "one".into(),
+                new_category: "two".into(),
+                task_ids: vec![],
+            },
+            ActionEnum::from_blob(&rename)?
+        );
+
+        for corrupt in [&blob[..blob.len() - 1], &[], &[200, 1, 2], &legacy[..4]] {
+            assert!(ActionEnum::from_blob(corrupt).is_err());
         }
+        assert!(ActionEnum::from_blob(&[blob.as_slice(), &[0]].concat()).is_err());
+        assert!(ActionEnum::from_blob(&[&legacy[..], &[0]].concat()).is_err());
+
+        Ok(())
     }
 }
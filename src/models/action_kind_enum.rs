@@ -0,0 +1,29 @@
+use clap::ValueEnum;
+
+/**
+ * Kind of a logged action, named like the `kind` field of its JSON
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum ActionKindEnum {
+    /// task created, updated or deleted
+    Task,
+    /// task deleted together with its subtasks
+    TaskTree,
+    /// recurring task completed and its next instance created
+    TaskRecurrence,
+    /// category added to or removed from a task
+    Category,
+    /// dependency added or removed
+    Dependency,
+    /// category of a task renamed
+    RenameTaskCategory,
+    /// category removed from every task
+    BatchCategoryDelete,
+    /// category renamed on every task
+    BatchCategoryRename,
+    /// past action reverted
+    Revert,
+    /// actions of a command changing several tasks
+    Compound,
+}
@@ -0,0 +1,105 @@
+use bincode::Decode;
+
+use super::{ActionEnum, ActionTypeEnum, TaskPriorityEnum, TaskStatusEnum};
+
+/**
+ * Layout of the actions stored with the version 1 of the encoding, only decoded.
+ * Never change it: the variants and fields must stay in the order they were written.
+ */
+#[derive(Decode)]
+pub enum ActionEnumV1 {
+    Task {
+        action_type: ActionTypeEnum,
+        id: i64,
+        title: String,
+        info: Option<String>,
+        deadline: Option<String>,
+        categories: Option<Vec<String>>,
+        status: TaskStatusEnum,
+        priority: TaskPriorityEnum,
+        updated_at: String,
+        created_at: String,
+    },
+    Category {
+        action_type: ActionTypeEnum,
+        category: String,
+        task_id: i64,
+    },
+    RenameTaskCategory {
+        old_category: String,
+        new_category: String,
+        task_id: i64,
+    },
+    BatchCategoryDelete {
+        task_ids: Vec<i64>,
+        category: String,
+    },
+    BatchCategoryRename {
+        old_category: String,
+        new_category: String,
+    },
+}
+
+impl From<ActionEnumV1> for ActionEnum {
+    fn from(value: ActionEnumV1) -> Self {
+        match value {
+            // tasks had no parent, dependencies nor recurrence yet
+            ActionEnumV1::Task {
+                action_type,
+                id,
+                title,
+                info,
+                deadline,
+                categories,
+                status,
+                priority,
+                updated_at,
+                created_at,
+            } => ActionEnum::Task {
+                action_type,
+                id,
+                title,
+                info,
+                deadline,
+                categories,
+                dependencies: None,
+                status,
+                priority,
+                parent_id: None,
+                recurrence: None,
+                updated_at,
+                created_at,
+            },
+            ActionEnumV1::Category {
+                action_type,
+                category,
+                task_id,
+            } => ActionEnum::Category {
+                action_type,
+                category,
+                task_id,
+            },
+            ActionEnumV1::RenameTaskCategory {
+                old_category,
+                new_category,
+                task_id,
+            } => ActionEnum::RenameTaskCategory {
+                old_category,
+                new_category,
+                task_id,
+            },
+            ActionEnumV1::BatchCategoryDelete { task_ids, category } => {
+                ActionEnum::BatchCategoryDelete { task_ids, category }
+            }
+            // the renamed tasks were not recorded yet
+            ActionEnumV1::BatchCategoryRename {
+                old_category,
+                new_category,
+            } => ActionEnum::BatchCategoryRename {
+                old_category,
+                new_category,
+                task_ids: vec![],
+            },
+        }
+    }
+}
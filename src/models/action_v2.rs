@@ -0,0 +1,121 @@
+use bincode::Decode;
+
+use super::{ActionEnum, ActionTypeEnum, TaskPriorityEnum, TaskStatusEnum};
+
+/**
+ * Layout of the actions stored with the version 2 of the encoding, only decoded.
+ * Never change it: the variants and fields must stay in the order they were written.
+ */
+#[derive(Decode)]
+pub enum ActionEnumV2 {
+    Task {
+        action_type: ActionTypeEnum,
+        id: i64,
+        title: String,
+        info: Option<String>,
+        deadline: Option<String>,
+        categories: Option<Vec<String>>,
+        status: TaskStatusEnum,
+        priority: TaskPriorityEnum,
+        parent_id: Option<i64>,
+        updated_at: String,
+        created_at: String,
+    },
+    TaskTree {
+        action_type: ActionTypeEnum,
+        tasks: Vec<ActionEnumV2>,
+        reparented: Vec<i64>,
+    },
+    Category {
+        action_type: ActionTypeEnum,
+        category: String,
+        task_id: i64,
+    },
+    RenameTaskCategory {
+        old_category: String,
+        new_category: String,
+        task_id: i64,
+    },
+    BatchCategoryDelete {
+        task_ids: Vec<i64>,
+        category: String,
+    },
+    BatchCategoryRename {
+        old_category: String,
+        new_category: String,
+    },
+}
+
+impl From<ActionEnumV2> for ActionEnum {
+    fn from(value: ActionEnumV2) -> Self {
+        match value {
+            // tasks had no dependencies nor recurrence yet
+            ActionEnumV2::Task {
+                action_type,
+                id,
+                title,
+                info,
+                deadline,
+                categories,
+                status,
+                priority,
+                parent_id,
+                updated_at,
+                created_at,
+            } => ActionEnum::Task {
+                action_type,
+                id,
+                title,
+                info,
+                deadline,
+                categories,
+                dependencies: None,
+                status,
+                priority,
+                parent_id,
+                recurrence: None,
+                updated_at,
+                created_at,
+            },
+            ActionEnumV2::TaskTree {
+                action_type,
+                tasks,
+                reparented,
+            } => ActionEnum::TaskTree {
+                action_type,
+                tasks: tasks.into_iter().map(ActionEnum::from).collect(),
+                reparented,
+            },
+            ActionEnumV2::Category {
+                action_type,
+                category,
+                task_id,
+            } => ActionEnum::Category {
+                action_type,
+                category,
+                task_id,
+            },
+            ActionEnumV2::RenameTaskCategory {
+                old_category,
+                new_category,
+                task_id,
+            } => ActionEnum::RenameTaskCategory {
+                old_category,
+                new_category,
+                task_id,
+            },
+            ActionEnumV2::BatchCategoryDelete { task_ids, category } => {
+                ActionEnum::BatchCategoryDelete { task_ids, category }
+            }
+            // the renamed tasks were not recorded yet
+            ActionEnumV2::BatchCategoryRename {
+                old_category,
+                new_category,
+            } => ActionEnum::BatchCategoryRename {
+                old_category,
+                new_category,
+                task_ids: vec![],
+            },
+        }
+    }
+}
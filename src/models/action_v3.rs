@@ -0,0 +1,137 @@
+use bincode::Decode;
+
+use super::{ActionEnum, ActionTypeEnum, TaskPriorityEnum, TaskStatusEnum};
+
+/**
+ * Layout of the actions stored with the version 3 of the encoding, only decoded.
+ * Never change it: the variants and fields must stay in the order they were written.
+ */
+#[derive(Decode)]
+pub enum ActionEnumV3 {
+    Task {
+        action_type: ActionTypeEnum,
+        id: i64,
+        title: String,
+        info: Option<String>,
+        deadline: Option<String>,
+        categories: Option<Vec<String>>,
+        dependencies: Option<Vec<(i64, i64)>>,
+        status: TaskStatusEnum,
+        priority: TaskPriorityEnum,
+        parent_id: Option<i64>,
+        updated_at: String,
+        created_at: String,
+    },
+    TaskTree {
+        action_type: ActionTypeEnum,
+        tasks: Vec<ActionEnumV3>,
+        reparented: Vec<i64>,
+    },
+    Category {
+        action_type: ActionTypeEnum,
+        category: String,
+        task_id: i64,
+    },
+    Dependency {
+        action_type: ActionTypeEnum,
+        task_id: i64,
+        depends_on_id: i64,
+    },
+    RenameTaskCategory {
+        old_category: String,
+        new_category: String,
+        task_id: i64,
+    },
+    BatchCategoryDelete {
+        task_ids: Vec<i64>,
+        category: String,
+    },
+    BatchCategoryRename {
+        old_category: String,
+        new_category: String,
+    },
+}
+
+impl From<ActionEnumV3> for ActionEnum {
+    fn from(value: ActionEnumV3) -> Self {
+        match value {
+            // tasks had no recurrence yet
+            ActionEnumV3::Task {
+                action_type,
+                id,
+                title,
+                info,
+                deadline,
+                categories,
+                dependencies,
+                status,
+                priority,
+                parent_id,
+                updated_at,
+                created_at,
+            } => ActionEnum::Task {
+                action_type,
+                id,
+                title,
+                info,
+                deadline,
+                categories,
+                dependencies,
+                status,
+                priority,
+                parent_id,
+                recurrence: None,
+                updated_at,
+                created_at,
+            },
+            ActionEnumV3::TaskTree {
+                action_type,
+                tasks,
+                reparented,
+            } => ActionEnum::TaskTree {
+                action_type,
+                tasks: tasks.into_iter().map(ActionEnum::from).collect(),
+                reparented,
+            },
+            ActionEnumV3::Category {
+                action_type,
+                category,
+                task_id,
+            } => ActionEnum::Category {
+                action_type,
+                category,
+                task_id,
+            },
+            ActionEnumV3::Dependency {
+                action_type,
+                task_id,
+                depends_on_id,
+            } => ActionEnum::Dependency {
+                action_type,
+                task_id,
+                depends_on_id,
+            },
+            ActionEnumV3::RenameTaskCategory {
+                old_category,
+                new_category,
+                task_id,
+            } => ActionEnum::RenameTaskCategory {
+                old_category,
+                new_category,
+                task_id,
+            },
+            ActionEnumV3::BatchCategoryDelete { task_ids, category } => {
+                ActionEnum::BatchCategoryDelete { task_ids, category }
+            }
+            // the renamed tasks were not recorded yet
+            ActionEnumV3::BatchCategoryRename {
+                old_category,
+                new_category,
+            } => ActionEnum::BatchCategoryRename {
+                old_category,
+                new_category,
+                task_ids: vec![],
+            },
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use bincode::Decode;
+
+use super::{ActionEnum, ActionTypeEnum, RecurrenceEnum, TaskPriorityEnum, TaskStatusEnum};
+
+/**
+ * Layout of the actions stored with the version 4 of the encoding, only decoded.
+ * Never change it: the variants and fields must stay in the order they were written.
+ */
+#[derive(Decode)]
+pub enum ActionEnumV4 {
+    Task {
+        action_type: ActionTypeEnum,
+        id: i64,
+        title: String,
+        info: Option<String>,
+        deadline: Option<String>,
+        categories: Option<Vec<String>>,
+        dependencies: Option<Vec<(i64, i64)>>,
+        status: TaskStatusEnum,
+        priority: TaskPriorityEnum,
+        parent_id: Option<i64>,
+        recurrence: Option<RecurrenceEnum>,
+        updated_at: String,
+        created_at: String,
+    },
+    TaskTree {
+        action_type: ActionTypeEnum,
+        tasks: Vec<ActionEnumV4>,
+        reparented: Vec<i64>,
+    },
+    TaskRecurrence {
+        update: Box<ActionEnumV4>,
+        create: Box<ActionEnumV4>,
+    },
+    Category {
+        action_type: ActionTypeEnum,
+        category: String,
+        task_id: i64,
+    },
+    Dependency {
+        action_type: ActionTypeEnum,
+        task_id: i64,
+        depends_on_id: i64,
+    },
+    RenameTaskCategory {
+        old_category: String,
+        new_category: String,
+        task_id: i64,
+    },
+    BatchCategoryDelete {
+        task_ids: Vec<i64>,
+        category: String,
+    },
+    BatchCategoryRename {
+        old_category: String,
+        new_category: String,
+    },
+    Revert {
+        action_id: i64,
+        action: Box<ActionEnumV4>,
+    },
+    Compound {
+        actions: Vec<ActionEnumV4>,
+    },
+}
+
+impl From<ActionEnumV4> for ActionEnum {
+    fn from(value: ActionEnumV4) -> Self {
+        let boxed = |action: Box<ActionEnumV4>| Box::new(ActionEnum::from(*action));
+        let list = |actions: Vec<ActionEnumV4>| actions.into_iter().map(ActionEnum::from).collect();
+
+        match value {
+            ActionEnumV4::Task {
+                action_type,
+                id,
+                title,
+                info,
+                deadline,
+                categories,
+                dependencies,
+                status,
+                priority,
+                parent_id,
+                recurrence,
+                updated_at,
+                created_at,
+            } => ActionEnum::Task {
+                action_type,
+                id,
+                title,
+                info,
+                deadline,
+                categories,
+                dependencies,
+                status,
+                priority,
+                parent_id,
+                recurrence,
+                updated_at,
+                created_at,
+            },
+            ActionEnumV4::TaskTree {
+                action_type,
+                tasks,
+                reparented,
+            } => ActionEnum::TaskTree {
+                action_type,
+                tasks: list(tasks),
+                reparented,
+            },
+            ActionEnumV4::TaskRecurrence { update, create } => ActionEnum::TaskRecurrence {
+                update: boxed(update),
+                create: boxed(create),
+            },
+            ActionEnumV4::Category {
+                action_type,
+                category,
+                task_id,
+            } => ActionEnum::Category {
+                action_type,
+                category,
+                task_id,
+            },
+            ActionEnumV4::Dependency {
+                action_type,
+                task_id,
+                depends_on_id,
+            } => ActionEnum::Dependency {
+                action_type,
+                task_id,
+                depends_on_id,
+            },
+            ActionEnumV4::RenameTaskCategory {
+                old_category,
+                new_category,
+                task_id,
+            } => ActionEnum::RenameTaskCategory {
+                old_category,
+                new_category,
+                task_id,
+            },
+            ActionEnumV4::BatchCategoryDelete { task_ids, category } => {
+                ActionEnum::BatchCategoryDelete { task_ids, category }
+            }
+            // the renamed tasks were not recorded yet
+            ActionEnumV4::BatchCategoryRename {
+                old_category,
+                new_category,
+            } => ActionEnum::BatchCategoryRename {
+                old_category,
+                new_category,
+                task_ids: vec![],
+            },
+            ActionEnumV4::Revert { action_id, action } => ActionEnum::Revert {
+                action_id,
+                action: boxed(action),
+            },
+            ActionEnumV4::Compound { actions } => ActionEnum::Compound {
+                actions: list(actions),
+            },
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{fmt::Display, str::FromStr};
+
+use rusqlite::{
+    types::{FromSql, ToSqlOutput},
+    ToSql,
+};
+use serde::{Serialize, Serializer};
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Date, OffsetDateTime,
+};
+
+/**
+ * Deadline of a task, either a whole day or a moment with a time of day and UTC offset.
+ * Stored as text, `YYYY-MM-DD` for days and RFC 3339 for moments, so date-only rows keep working.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineEnum {
+    /// due at some point of the day
+    Date(Date),
+    /// due at an exact time
+    DateTime(OffsetDateTime),
+}
+
+impl DeadlineEnum {
+    /**
+     * Used to get the day of the deadline, in the offset it was set with
+     */
+    pub fn date(&self) -> Date {
+        match self {
+            DeadlineEnum::Date(date) => *date,
+            DeadlineEnum::DateTime(date_time) => date_time.date(),
+        }
+    }
+
+    /**
+     * Used to check if the deadline has passed, day deadlines pass once their day is over
+     */
+    pub fn is_overdue(&self, now: OffsetDateTime) -> bool {
+        match self {
+            DeadlineEnum::Date(date) => *date < now.date(),
+            DeadlineEnum::DateTime(date_time) => *date_time < now,
+        }
+    }
+
+    /**
+     * Used to move the deadline to another day, keeping its time of day
+     */
+    pub fn with_date(&self, date: Date) -> Self {
+        match self {
+            DeadlineEnum::Date(_) => DeadlineEnum::Date(date),
+            DeadlineEnum::DateTime(date_time) => {
+                DeadlineEnum::DateTime(date_time.replace_date(date))
+            }
+        }
+    }
+}
+
+impl Display for DeadlineEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            DeadlineEnum::Date(date) => date.format(format_description!("[year]-[month]-[day]")),
+            DeadlineEnum::DateTime(date_time) => date_time.format(&Rfc3339),
+        };
+
+        f.write_str(&value.map_err(|_| std::fmt::Error)?)
+    }
+}
+
+impl FromStr for DeadlineEnum {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Ok(date) = Date::parse(value, format_description!("[year]-[month]-[day]")) {
+            return Ok(DeadlineEnum::Date(date));
+        }
+
+        match OffsetDateTime::parse(value, &Rfc3339) {
+            Ok(date_time) => Ok(DeadlineEnum::DateTime(date_time)),
+            Err(_) => Err(anyhow::anyhow!(
+                "[Invalid deadline] - [input: {}] - [expected: YYYY-MM-DD or RFC 3339]",
+                value
+            )),
+        }
+    }
+}
+
+impl From<Date> for DeadlineEnum {
+    fn from(value: Date) -> Self {
+        DeadlineEnum::Date(value)
+    }
+}
+
+impl Serialize for DeadlineEnum {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl ToSql for DeadlineEnum {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for DeadlineEnum {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let value: &str = value.as_str()?;
+
+        DeadlineEnum::from_str(value)
+            .map_err(|err| rusqlite::types::FromSqlError::Other(err.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::Result;
+    use rusqlite::Connection;
+    use time::macros::{date, datetime};
+
+    use super::DeadlineEnum;
+
+    #[test]
+    fn test_deadline() -> Result<()> {
+        let day = DeadlineEnum::Date(date!(2024 - 01 - 31));
+        let moment = DeadlineEnum::DateTime(datetime!(2024 - 01 - 31 23:30 -05:00));
+
+        // test round trip
+        assert_eq!("2024-01-31", day.to_string());
+        assert_eq!("2024-01-31T23:30:00-05:00", moment.to_string());
+        assert_eq!(day, DeadlineEnum::from_str(&day.to_string())?);
+        assert_eq!(moment, DeadlineEnum::from_str(&moment.to_string())?);
+        assert!(DeadlineEnum::from_str("2024-01-31 23:30").is_err());
+
+        // test the day follows the offset of the deadline, not UTC
+        assert_eq!(date!(2024 - 01 - 31), moment.date());
+        assert_eq!(
+            DeadlineEnum::DateTime(datetime!(2024 - 02 - 07 23:30 -05:00)),
+            moment.with_date(date!(2024 - 02 - 07))
+        );
+
+        // test overdue
+        let now = datetime!(2024 - 01 - 31 23:00 -05:00);
+        assert!(!day.is_overdue(now));
+        assert!(!moment.is_overdue(now));
+        assert!(moment.is_overdue(datetime!(2024 - 02 - 01 04:31 UTC)));
+        assert!(day.is_overdue(datetime!(2024 - 02 - 01 00:00 -05:00)));
+
+        // test reading both date-only and RFC 3339 rows
+        let conn = Connection::open_in_memory()?;
+        let (old, new): (DeadlineEnum, DeadlineEnum) = conn.query_row(
+            "SELECT '2024-01-31', '2024-01-31T23:30:00-05:00'",
+            (),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert_eq!(day, old);
+        assert_eq!(moment, new);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+use clap::ValueEnum;
+
+/**
+ * What happens to the subtasks of a task that is being deleted
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum DeleteModeEnum {
+    /// refuse to delete a task that has subtasks
+    Refuse,
+    /// delete the task together with all of its subtasks
+    Cascade,
+    /// move the subtasks to the parent of the deleted task
+    Reparent,
+}
@@ -0,0 +1,9 @@
+use sea_query::Iden;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Iden)]
+pub enum TaskDependencyIden {
+    #[iden = "task_dependencies"]
+    Table,
+    TaskId,
+    DependsOnId,
+}
@@ -0,0 +1,26 @@
+use time::Date;
+
+/**
+ * Steps of housekeeping, each step runs when it is set
+ */
+#[derive(Debug, Clone, Default)]
+pub struct HousekeepingPayload {
+    /// delete the archived tasks last updated on or before this day
+    pub purge_archived_until: Option<Date>,
+    /// archive the completed tasks last updated on or before this day
+    pub archive_done_until: Option<Date>,
+    /// delete the actions log but this many of the last actions
+    pub keep_actions: Option<u64>,
+    /// report what the steps would change and roll them back
+    pub dry_run: bool,
+}
+
+/**
+ * What housekeeping changed, or would change in a dry run
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HousekeepingReport {
+    pub purged_task_ids: Vec<i64>,
+    pub archived_task_ids: Vec<i64>,
+    pub actions_deleted: i64,
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 use clap::ValueEnum;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -7,3 +9,21 @@ pub enum OrderByEnum {
     /// descending
     Desc,
 }
+
+impl Display for OrderByEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderByEnum::Asc => f.write_str("asc"),
+            OrderByEnum::Desc => f.write_str("desc"),
+        }
+    }
+}
+
+impl From<OrderByEnum> for sea_query::Order {
+    fn from(value: OrderByEnum) -> Self {
+        match value {
+            OrderByEnum::Asc => sea_query::Order::Asc,
+            OrderByEnum::Desc => sea_query::Order::Desc,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use clap::ValueEnum;
+
+/**
+ * How command results are printed
+ */
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum OutputFormatEnum {
+    /// human readable text
+    #[default]
+    Text,
+    /// a single JSON document
+    Json,
+    /// one JSON object per line
+    Ndjson,
+}
@@ -0,0 +1,268 @@
+use std::{fmt::Display, str::FromStr};
+
+use bincode::{Decode, Encode};
+use rusqlite::{
+    types::{FromSql, ToSqlOutput},
+    ToSql,
+};
+use serde::{Serialize, Serializer};
+use time::{Date, Duration, Month};
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/**
+ * Recurrence rule of a task, stored as text e.g. `daily`, `weekly:mon,thu`, `monthly:15`, `every:3d`
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum RecurrenceEnum {
+    /// every day
+    Daily,
+    /// on the given weekdays, counted from monday (0) to sunday (6)
+    Weekly { weekdays: Vec<u8> },
+    /// on the given day of the month, clamped to the length of the month
+    Monthly { day: u8 },
+    /// every N days
+    EveryDays { days: u32 },
+}
+
+impl RecurrenceEnum {
+    /**
+     * Used to compute the first date after `from` that matches the rule, an error when it is
+     * past the last supported date
+     */
+    pub fn next_date(&self, from: Date) -> anyhow::Result<Date> {
+        let next = match self {
+            RecurrenceEnum::Daily => from.next_day(),
+            RecurrenceEnum::EveryDays { days } => from.checked_add(Duration::days(*days as i64)),
+            RecurrenceEnum::Weekly { weekdays } if weekdays.is_empty() => {
+                from.checked_add(Duration::weeks(1))
+            }
+            RecurrenceEnum::Weekly { weekdays } => {
+                let mut date = from.next_day();
+                while let Some(current) = date
+                    .filter(|date| !weekdays.contains(&date.weekday().number_days_from_monday()))
+                {
+                    date = current.next_day();
+                }
+                date
+            }
+            RecurrenceEnum::Monthly { day } => {
+                let (mut year, mut month) = (from.year(), from.month());
+                let clamped = |year: i32, month: Month| (*day).min(month.length(year));
+
+                if clamped(year, month) <= from.day() {
+                    if month == Month::December {
+                        year += 1;
+                    }
+                    month = month.next();
+                }
+
+                Date::from_calendar_date(year, month, clamped(year, month)).ok()
+            }
+        };
+
+        next.ok_or_else(|| {
+            anyhow::anyhow!(
+                "[Recurrence] - [rule: {}] - [from: {}] - the next date is out of range",
+                self,
+                from
+            )
+        })
+    }
+}
+
+impl Display for RecurrenceEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecurrenceEnum::Daily => f.write_str("daily"),
+            RecurrenceEnum::Weekly { weekdays } => {
+                let weekdays: Vec<&str> = weekdays
+                    .iter()
+                    .map(|weekday| WEEKDAYS[*weekday as usize])
+                    .collect();
+                write!(f, "weekly:{}", weekdays.join(","))
+            }
+            RecurrenceEnum::Monthly { day } => write!(f, "monthly:{}", day),
+            RecurrenceEnum::EveryDays { days } => write!(f, "every:{}d", days),
+        }
+    }
+}
+
+impl FromStr for RecurrenceEnum {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            anyhow::anyhow!(
+                "[Invalid recurrence] - [input: {}] - [expected: daily, weekly:mon,thu, monthly:15 or every:3d]",
+                value
+            )
+        };
+
+        let lowercase = value.trim().to_lowercase();
+        let (kind, argument) = match lowercase.split_once(':') {
+            Some((kind, argument)) => (kind, Some(argument)),
+            None => (lowercase.as_str(), None),
+        };
+
+        match (kind, argument) {
+            ("daily", None) => Ok(RecurrenceEnum::Daily),
+            ("weekly", Some(argument)) => {
+                let mut weekdays = argument
+                    .split(',')
+                    .map(|weekday| {
+                        WEEKDAYS
+                            .iter()
+                            .position(|name| *name == weekday.trim())
+                            .map(|position| position as u8)
+                            .ok_or_else(invalid)
+                    })
+                    .collect::<Result<Vec<u8>, _>>()?;
+                weekdays.sort();
+                weekdays.dedup();
+                Ok(RecurrenceEnum::Weekly { weekdays })
+            }
+            ("monthly", Some(argument)) => match argument.parse::<u8>() {
+                Ok(day) if (1..=31).contains(&day) => Ok(RecurrenceEnum::Monthly { day }),
+                _ => Err(invalid()),
+            },
+            ("every", Some(argument)) => {
+                match argument
+                    .strip_suffix('d')
+                    .unwrap_or(argument)
+                    .parse::<u32>()
+                {
+                    Ok(days) if days > 0 => Ok(RecurrenceEnum::EveryDays { days }),
+                    _ => Err(invalid()),
+                }
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Serialize for RecurrenceEnum {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl ToSql for RecurrenceEnum {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl FromSql for RecurrenceEnum {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let value: &str = value.as_str()?;
+
+        RecurrenceEnum::from_str(value)
+            .map_err(|err| rusqlite::types::FromSqlError::Other(err.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::Result;
+    use time::{macros::date, Date};
+
+    use super::RecurrenceEnum;
+
+    #[test]
+    fn test_parse_recurrence() -> Result<()> {
+        assert_eq!(RecurrenceEnum::Daily, RecurrenceEnum::from_str("daily")?);
+        assert_eq!(
+            RecurrenceEnum::Weekly {
+                weekdays: vec![0, 3]
+            },
+            RecurrenceEnum::from_str("weekly:thu,Mon,thu")?
+        );
+        assert_eq!(
+            RecurrenceEnum::Monthly { day: 15 },
+            RecurrenceEnum::from_str("monthly:15")?
+        );
+        assert_eq!(
+            RecurrenceEnum::EveryDays { days: 3 },
+            RecurrenceEnum::from_str("every:3d")?
+        );
+        assert_eq!(
+            RecurrenceEnum::EveryDays { days: 3 },
+            RecurrenceEnum::from_str("every:3")?
+        );
+
+        for invalid in [
+            "",
+            "weekly",
+            "weekly:",
+            "weekly:xyz",
+            "monthly:0",
+            "monthly:32",
+            "every:0",
+            "daily:1",
+        ] {
+            assert!(RecurrenceEnum::from_str(invalid).is_err(), "{}", invalid);
+        }
+
+        // test round trip
+        for rule in ["daily", "weekly:mon,thu", "monthly:31", "every:10d"] {
+            assert_eq!(rule, RecurrenceEnum::from_str(rule)?.to_string());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_date() -> Result<()> {
+        // 2024-01-10 is a wednesday
+        let from = date!(2024 - 01 - 10);
+
+        let cases = [
+            ("daily", date!(2024 - 01 - 11)),
+            ("every:14d", date!(2024 - 01 - 24)),
+            ("weekly:wed", date!(2024 - 01 - 17)),
+            ("weekly:mon,thu", date!(2024 - 01 - 11)),
+            ("weekly:mon,tue", date!(2024 - 01 - 15)),
+            ("monthly:10", date!(2024 - 02 - 10)),
+            ("monthly:15", date!(2024 - 01 - 15)),
+            ("monthly:5", date!(2024 - 02 - 05)),
+        ];
+        for (rule, expected) in cases {
+            assert_eq!(
+                expected,
+                RecurrenceEnum::from_str(rule)?.next_date(from)?,
+                "{}",
+                rule
+            );
+        }
+
+        // test clamping to the month length and year rollover
+        let monthly = RecurrenceEnum::Monthly { day: 31 };
+        assert_eq!(
+            date!(2024 - 02 - 29),
+            monthly.next_date(date!(2024 - 01 - 31))?
+        );
+        assert_eq!(
+            date!(2024 - 03 - 31),
+            monthly.next_date(date!(2024 - 02 - 29))?
+        );
+        assert_eq!(
+            date!(2025 - 01 - 31),
+            monthly.next_date(date!(2024 - 12 - 31))?
+        );
+
+        // test dates past the last supported date are errors
+        assert!(RecurrenceEnum::EveryDays { days: u32::MAX }
+            .next_date(from)
+            .is_err());
+        for rule in ["daily", "weekly:mon", "monthly:1"] {
+            assert!(RecurrenceEnum::from_str(rule)?
+                .next_date(Date::MAX)
+                .is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -1,16 +1,26 @@
+use std::{fmt::Display, str::FromStr};
+
+use clap::ValueEnum;
 use sea_query::Iden;
+use serde::{Deserialize, Serialize};
 use time::Date;
 
-use super::{OrderByEnum, TaskStatusEnum};
+use super::{
+    DeadlineEnum, OrderByEnum, RecurrenceEnum, TaskFilter, TaskPriorityEnum, TaskSortColumnEnum,
+    TaskStatusEnum,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Task {
     pub id: i64,
     pub title: String,
     pub info: Option<String>,
-    pub deadline: Option<Date>,
+    pub deadline: Option<DeadlineEnum>,
     pub categories: Option<Vec<String>>,
     pub status: TaskStatusEnum,
+    pub priority: TaskPriorityEnum,
+    pub parent_id: Option<i64>,
+    pub recurrence: Option<RecurrenceEnum>,
     pub updated_at: Date,
     pub created_at: Date,
 }
@@ -24,6 +34,9 @@ pub enum TaskIden {
     Info,
     Deadline,
     Status,
+    Priority,
+    ParentId,
+    Recurrence,
     UpdatedAt,
     CreatedAt,
 }
@@ -35,35 +48,264 @@ pub enum TaskFtsIden {
     Id,
     Title,
     Info,
+    /// categories of the task separated by spaces
+    Categories,
 }
 
 #[derive(Debug)]
 pub struct AddTask {
     pub title: String,
     pub info: Option<String>,
-    pub deadline: Option<Date>,
+    pub deadline: Option<DeadlineEnum>,
     pub categories: Option<Vec<String>>,
     pub status: TaskStatusEnum,
+    pub priority: TaskPriorityEnum,
+    pub parent_id: Option<i64>,
+    pub recurrence: Option<RecurrenceEnum>,
     pub created_at: Date,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UpdateTask {
     pub title: Option<String>,
     pub info: Option<Option<String>>,
-    pub deadline: Option<Option<Date>>,
+    pub deadline: Option<Option<DeadlineEnum>>,
     pub status: Option<TaskStatusEnum>,
+    pub priority: Option<TaskPriorityEnum>,
+    pub recurrence: Option<Option<RecurrenceEnum>>,
     pub created_at: Option<Date>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct QueryTaskPayload {
     pub status: Option<TaskStatusEnum>,
-    pub categories: Option<Vec<String>>,
+    pub priority: Option<TaskPriorityEnum>,
+    /// tasks having any of the categories
+    pub any_categories: Option<Vec<String>>,
+    /// tasks having all of the categories
+    pub all_categories: Option<Vec<String>>,
+    /// tasks having none of the categories
+    pub exclude_categories: Option<Vec<String>>,
     pub text: Option<String>,
+    /// tasks due strictly before this day
+    pub due_before: Option<Date>,
+    /// tasks due strictly after this day
+    pub due_after: Option<Date>,
+    /// undone tasks whose deadline has passed
+    pub overdue: bool,
+    /// tasks without a deadline
+    pub no_deadline: bool,
+    /// tasks created on or after this day
+    pub created_since: Option<Date>,
+    /// tasks updated on or after this day
+    pub updated_since: Option<Date>,
+    pub blocked: bool,
+    pub ready: bool,
+    /// filter parsed from a `--where` expression
+    pub filter: Option<TaskFilter>,
+    pub limit: u64,
+    pub offset: u64,
+    pub after: Option<TaskCursor>,
+    /// sort keys in order of precedence, ties are always ordered by id
+    pub sort: Vec<TaskSortKey>,
+}
+
+/**
+ * Full text search over the title, info and categories of tasks
+ */
+#[derive(Debug, Clone)]
+pub struct SearchTaskPayload {
+    /// FTS5 MATCH expression, see `search::fts_query`
+    pub query: String,
+    pub status: Option<TaskStatusEnum>,
     pub limit: u64,
-    pub sort_created_at: Option<OrderByEnum>,
-    pub sort_updated_at: Option<OrderByEnum>,
-    pub sort_deadline: Option<OrderByEnum>,
-    pub sort_title: Option<OrderByEnum>,
+    /// text placed before and after each matched word of the excerpts
+    pub marks: (String, String),
+}
+
+/**
+ * A task found by a full text search
+ */
+#[derive(Debug)]
+pub struct TaskSearchResult {
+    pub task: Task,
+    /// relevance of the match, higher is better
+    pub score: f64,
+    /// title with the matched words marked
+    pub title: String,
+    /// excerpt of the info around the matched words
+    pub snippet: Option<String>,
+}
+
+/**
+ * A sort key of a task query, written as `column[:asc|desc][:nulls-first|nulls-last]`.
+ * Missing values are placed last unless `nulls-first` is given.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskSortKey {
+    pub column: TaskSortColumnEnum,
+    pub order: OrderByEnum,
+    pub nulls_first: bool,
+}
+
+impl TaskSortKey {
+    pub fn create(column: TaskSortColumnEnum, order: OrderByEnum) -> Self {
+        Self {
+            column,
+            order,
+            nulls_first: false,
+        }
+    }
+}
+
+impl Display for TaskSortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nulls = match self.nulls_first {
+            true => "nulls-first",
+            false => "nulls-last",
+        };
+
+        write!(f, "{}:{}:{}", self.column, self.order, nulls)
+    }
+}
+
+impl FromStr for TaskSortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            anyhow::anyhow!(
+                "[Invalid sort key] - [input: {}] - [expected: column[:asc|desc][:nulls-first|nulls-last]]",
+                value
+            )
+        };
+
+        let mut parts = value.trim().split(':');
+        let column = parts
+            .next()
+            .and_then(|column| TaskSortColumnEnum::from_str(column, true).ok())
+            .ok_or_else(invalid)?;
+
+        let mut key = TaskSortKey::create(column, OrderByEnum::Asc);
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "asc" => key.order = OrderByEnum::Asc,
+                "desc" => key.order = OrderByEnum::Desc,
+                "nulls-first" => key.nulls_first = true,
+                "nulls-last" => key.nulls_first = false,
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(key)
+    }
+}
+
+/**
+ * A page of tasks and its position within all the matching tasks
+ */
+#[derive(Debug)]
+pub struct TaskPage {
+    pub tasks: Vec<Task>,
+    /// number of tasks matching the filters
+    pub total: u64,
+    /// number of matching tasks before this page
+    pub start: u64,
+    /// cursor to fetch the page after this one, if any
+    pub next_cursor: Option<TaskCursor>,
+}
+
+/**
+ * Keyset position after a task, holding the sort keys it was created with and their values
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskCursor {
+    /// sort keys in order, e.g. `priority:desc:nulls-last`
+    pub sort: Vec<String>,
+    /// values of the sort keys of the last task
+    pub values: Vec<serde_json::Value>,
+    /// id of the last task
+    pub id: i64,
+}
+
+impl TaskCursor {
+    /**
+     * Used to encode the cursor to an opaque command line friendly token
+     */
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_string(self).expect("Cursor should serialize");
+
+        json.bytes().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /**
+     * Used to decode a token made by `encode`
+     */
+    pub fn decode(value: &str) -> anyhow::Result<Self> {
+        let invalid = || anyhow::anyhow!("[Invalid cursor] - [input: {}]", value);
+
+        if !value.len().is_multiple_of(2) || !value.is_ascii() {
+            return Err(invalid());
+        }
+
+        let bytes = (0..value.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&value[index..index + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::models::{OrderByEnum, TaskSortColumnEnum};
+
+    use super::TaskSortKey;
+
+    #[test]
+    fn test_sort_key() {
+        let key = |value: &str| TaskSortKey::from_str(value).ok();
+
+        assert_eq!(
+            Some(TaskSortKey::create(
+                TaskSortColumnEnum::Title,
+                OrderByEnum::Asc
+            )),
+            key("title")
+        );
+        assert_eq!(
+            Some(TaskSortKey::create(
+                TaskSortColumnEnum::Priority,
+                OrderByEnum::Desc
+            )),
+            key("priority:desc")
+        );
+        assert_eq!(
+            Some(TaskSortKey {
+                column: TaskSortColumnEnum::Deadline,
+                order: OrderByEnum::Desc,
+                nulls_first: true,
+            }),
+            key("Deadline:DESC:nulls-first")
+        );
+        assert_eq!(
+            Some(TaskSortKey::create(
+                TaskSortColumnEnum::Created,
+                OrderByEnum::Asc
+            )),
+            key("created_at:asc")
+        );
+        assert_eq!(None, key("categories"));
+        assert_eq!(None, key("title:up"));
+        assert_eq!(None, key(""));
+
+        // test the canonical form round trips
+        let deadline = key("deadline").unwrap();
+        assert_eq!("deadline:asc:nulls-last", deadline.to_string());
+        assert_eq!(Some(deadline), key(&deadline.to_string()));
+    }
 }
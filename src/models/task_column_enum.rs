@@ -0,0 +1,49 @@
+use clap::ValueEnum;
+
+/**
+ * Columns that can be shown by the task table
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum TaskColumnEnum {
+    /// task id
+    Id,
+    /// task title, indented by subtask depth
+    Title,
+    /// task status
+    Status,
+    /// task priority
+    Priority,
+    /// task deadline, colored when overdue
+    Deadline,
+    /// task categories
+    Categories,
+    /// recurrence rule
+    Repeat,
+    /// creation date
+    Created,
+    /// last update date
+    Updated,
+}
+
+impl TaskColumnEnum {
+    pub fn header(&self) -> &'static str {
+        match self {
+            TaskColumnEnum::Id => "ID",
+            TaskColumnEnum::Title => "TITLE",
+            TaskColumnEnum::Status => "STATUS",
+            TaskColumnEnum::Priority => "PRIORITY",
+            TaskColumnEnum::Deadline => "DEADLINE",
+            TaskColumnEnum::Categories => "CATEGORIES",
+            TaskColumnEnum::Repeat => "REPEAT",
+            TaskColumnEnum::Created => "CREATED",
+            TaskColumnEnum::Updated => "UPDATED",
+        }
+    }
+
+    /**
+     * Used to check if the column holds free text that can be truncated first
+     */
+    pub fn is_flexible(&self) -> bool {
+        matches!(self, TaskColumnEnum::Title | TaskColumnEnum::Categories)
+    }
+}
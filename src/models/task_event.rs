@@ -0,0 +1,61 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+use time::{Date, OffsetDateTime};
+
+/**
+ * What an action did to a single task
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskEventEnum {
+    Created,
+    Updated,
+    Deleted,
+    CategoryAdded,
+    CategoryRemoved,
+    CategoryRenamed,
+    DependencyAdded,
+    DependencyRemoved,
+    Reparented,
+}
+
+impl Display for TaskEventEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskEventEnum::Created => f.write_str("Created"),
+            TaskEventEnum::Updated => f.write_str("Updated"),
+            TaskEventEnum::Deleted => f.write_str("Deleted"),
+            TaskEventEnum::CategoryAdded => f.write_str("Category Added"),
+            TaskEventEnum::CategoryRemoved => f.write_str("Category Removed"),
+            TaskEventEnum::CategoryRenamed => f.write_str("Category Renamed"),
+            TaskEventEnum::DependencyAdded => f.write_str("Dependency Added"),
+            TaskEventEnum::DependencyRemoved => f.write_str("Dependency Removed"),
+            TaskEventEnum::Reparented => f.write_str("Reparented"),
+        }
+    }
+}
+
+/**
+ * Value of a task field before and after an action, `None` when the field had no value
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/**
+ * Change made to a task by a logged action, an action nesting several changes gives one
+ * event per change
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskEvent {
+    pub action_id: i64,
+    pub summary: String,
+    pub created_at: Date,
+    pub logged_at: Option<OffsetDateTime>,
+    pub event: TaskEventEnum,
+    pub changes: Vec<FieldChange>,
+}
@@ -0,0 +1,58 @@
+use time::Date;
+
+use super::{TaskPriorityEnum, TaskStatusEnum};
+
+/**
+ * Boolean filter over tasks, parsed from the `--where` expression
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskFilter {
+    And(Box<TaskFilter>, Box<TaskFilter>),
+    Or(Box<TaskFilter>, Box<TaskFilter>),
+    Not(Box<TaskFilter>),
+    Term(TaskFilterTerm),
+}
+
+/**
+ * A single condition of a task filter
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskFilterTerm {
+    /// `status:undone`
+    Status(TaskStatusEnum),
+    /// `priority>=high`
+    Priority(CompareEnum, TaskPriorityEnum),
+    /// `cat:backend`
+    Category(String),
+    /// `text:"login bug"` or a bare word
+    Text(String),
+    /// `due<+7d`, `None` for `due:none`
+    Deadline(CompareEnum, Option<Date>),
+    /// `created>=2024-01-01`
+    CreatedAt(CompareEnum, Date),
+    /// `updated:today`
+    UpdatedAt(CompareEnum, Date),
+    /// `is:overdue`
+    Overdue,
+    /// `is:blocked`
+    Blocked,
+    /// `is:ready`
+    Ready,
+}
+
+/**
+ * Comparison of a filter term, dates compare by whole days
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareEnum {
+    /// `:` or `=`
+    Eq,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
@@ -0,0 +1,109 @@
+use std::fmt::Display;
+
+use bincode::{Decode, Encode};
+use clap::ValueEnum;
+use rusqlite::{
+    types::{FromSql, ToSqlOutput},
+    ToSql,
+};
+use serde::{Deserialize, Serialize};
+
+/**
+ * Priority of a task, stored as an integer so that ordering in SQL follows importance
+ */
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    ValueEnum,
+    Encode,
+    Decode,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskPriorityEnum {
+    /// nice to have
+    Low,
+    /// regular work
+    #[default]
+    Normal,
+    /// important work
+    High,
+    /// urgent work
+    Critical,
+}
+
+impl TaskPriorityEnum {
+    pub fn as_i64(&self) -> i64 {
+        match self {
+            TaskPriorityEnum::Low => 0,
+            TaskPriorityEnum::Normal => 1,
+            TaskPriorityEnum::High => 2,
+            TaskPriorityEnum::Critical => 3,
+        }
+    }
+
+    pub fn from_i64(value: i64) -> Option<Self> {
+        match value {
+            0 => Some(TaskPriorityEnum::Low),
+            1 => Some(TaskPriorityEnum::Normal),
+            2 => Some(TaskPriorityEnum::High),
+            3 => Some(TaskPriorityEnum::Critical),
+            _ => None,
+        }
+    }
+}
+
+impl Display for TaskPriorityEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str((*self).into())
+    }
+}
+
+impl From<TaskPriorityEnum> for &'static str {
+    fn from(value: TaskPriorityEnum) -> Self {
+        match value {
+            TaskPriorityEnum::Low => "low",
+            TaskPriorityEnum::Normal => "normal",
+            TaskPriorityEnum::High => "high",
+            TaskPriorityEnum::Critical => "critical",
+        }
+    }
+}
+
+impl From<TaskPriorityEnum> for clap::builder::OsStr {
+    fn from(value: TaskPriorityEnum) -> Self {
+        Into::<&str>::into(value).into()
+    }
+}
+
+impl ToSql for TaskPriorityEnum {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_i64()))
+    }
+}
+
+impl FromSql for TaskPriorityEnum {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        let value: i64 = value.as_i64()?;
+
+        match TaskPriorityEnum::from_i64(value) {
+            Some(priority) => rusqlite::types::FromSqlResult::Ok(priority),
+            None => rusqlite::types::FromSqlResult::Err(rusqlite::types::FromSqlError::Other(
+                anyhow::anyhow!("Could not convert '{}' to TaskPriorityEnum", value).into(),
+            )),
+        }
+    }
+}
+
+impl From<TaskPriorityEnum> for sea_query::SimpleExpr {
+    fn from(value: TaskPriorityEnum) -> Self {
+        value.as_i64().into()
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+
+use super::TaskIden;
+
+/**
+ * Task columns that can be used as sort keys
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum TaskSortColumnEnum {
+    /// task id
+    Id,
+    /// task title
+    Title,
+    /// task description
+    Info,
+    /// task deadline
+    Deadline,
+    /// task status
+    Status,
+    /// task priority, by importance
+    Priority,
+    /// parent task id
+    #[value(alias = "parent_id")]
+    Parent,
+    /// recurrence rule
+    #[value(alias = "recurrence")]
+    Repeat,
+    /// creation date
+    #[value(alias = "created_at")]
+    Created,
+    /// last update date
+    #[value(alias = "updated_at")]
+    Updated,
+}
+
+impl TaskSortColumnEnum {
+    pub fn iden(&self) -> TaskIden {
+        match self {
+            TaskSortColumnEnum::Id => TaskIden::Id,
+            TaskSortColumnEnum::Title => TaskIden::Title,
+            TaskSortColumnEnum::Info => TaskIden::Info,
+            TaskSortColumnEnum::Deadline => TaskIden::Deadline,
+            TaskSortColumnEnum::Status => TaskIden::Status,
+            TaskSortColumnEnum::Priority => TaskIden::Priority,
+            TaskSortColumnEnum::Parent => TaskIden::ParentId,
+            TaskSortColumnEnum::Repeat => TaskIden::Recurrence,
+            TaskSortColumnEnum::Created => TaskIden::CreatedAt,
+            TaskSortColumnEnum::Updated => TaskIden::UpdatedAt,
+        }
+    }
+}
+
+impl Display for TaskSortColumnEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => f.write_str(value.get_name()),
+            None => Err(std::fmt::Error),
+        }
+    }
+}
@@ -1,11 +1,28 @@
+use std::fmt::Display;
+
 use bincode::{Decode, Encode};
 use clap::ValueEnum;
 use rusqlite::{
     types::{FromSql, ToSqlOutput},
     ToSql,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Encode, Decode)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    ValueEnum,
+    Encode,
+    Decode,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
 pub enum TaskStatusEnum {
     /// task done
     Done,
@@ -15,15 +32,15 @@ pub enum TaskStatusEnum {
     Archived,
 }
 
-impl ToString for TaskStatusEnum {
-    fn to_string(&self) -> String {
-        Into::<&str>::into(*self).to_string()
+impl Display for TaskStatusEnum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str((*self).into())
     }
 }
 
-impl Into<&'static str> for TaskStatusEnum {
-    fn into(self) -> &'static str {
-        match self {
+impl From<TaskStatusEnum> for &'static str {
+    fn from(value: TaskStatusEnum) -> Self {
+        match value {
             TaskStatusEnum::Done => "done",
             TaskStatusEnum::Undone => "undone",
             TaskStatusEnum::Archived => "archived",
@@ -31,9 +48,9 @@ impl Into<&'static str> for TaskStatusEnum {
     }
 }
 
-impl Into<clap::builder::OsStr> for TaskStatusEnum {
-    fn into(self) -> clap::builder::OsStr {
-        Into::<&str>::into(self).into()
+impl From<TaskStatusEnum> for clap::builder::OsStr {
+    fn from(value: TaskStatusEnum) -> Self {
+        Into::<&str>::into(value).into()
     }
 }
 
@@ -61,8 +78,8 @@ impl FromSql for TaskStatusEnum {
     }
 }
 
-impl Into<sea_query::SimpleExpr> for TaskStatusEnum {
-    fn into(self) -> sea_query::SimpleExpr {
-        self.to_string().into()
+impl From<TaskStatusEnum> for sea_query::SimpleExpr {
+    fn from(value: TaskStatusEnum) -> Self {
+        value.to_string().into()
     }
 }
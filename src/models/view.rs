@@ -0,0 +1,81 @@
+use sea_query::Iden;
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+use super::{TaskPriorityEnum, TaskStatusEnum};
+
+/**
+ * A saved `task list` query
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    pub name: String,
+    pub query: ViewQuery,
+    pub created_at: Date,
+    pub updated_at: Date,
+}
+
+/**
+ * Filters, sort keys and limit of a `QueryTaskPayload`, stored as JSON by a view.
+ * Dates and the `--where` expression are kept as written, so `+7d` stays relative to the day the view runs.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ViewQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<TaskStatusEnum>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<TaskPriorityEnum>,
+    /// tasks having any of the categories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub any_categories: Option<Vec<String>>,
+    /// tasks having all of the categories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_categories: Option<Vec<String>>,
+    /// tasks having none of the categories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_categories: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// date expression, tasks due strictly before this day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_before: Option<String>,
+    /// date expression, tasks due strictly after this day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_after: Option<String>,
+    /// period expression, tasks due from today until the end of the period, `today` for today only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_within: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub overdue: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub no_deadline: bool,
+    /// date expression, tasks created on or after this day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_since: Option<String>,
+    /// date expression, tasks updated on or after this day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_since: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub blocked: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ready: bool,
+    /// `--where` expression
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    pub limit: u64,
+    /// sort keys in order of precedence, e.g. `deadline:asc:nulls-last`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sort: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Iden)]
+pub enum ViewIden {
+    #[iden = "views"]
+    Table,
+    Name,
+    /// command line arguments stored by the views before they stored a query, only used by migrations
+    Arguments,
+    Query,
+    CreatedAt,
+    UpdatedAt,
+}
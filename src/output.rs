@@ -0,0 +1,549 @@
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::Serialize;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::{
+    migrations::Migration,
+    models::{
+        Action, ActionEnum, DeadlineEnum, FieldChange, OutputFormatEnum, RecurrenceEnum, Task,
+        TaskEvent, TaskEventEnum, TaskPriorityEnum, TaskStatusEnum, View, ViewQuery,
+    },
+    repositories::{
+        category_repository::CategoryRepository, dependency_repository::DependencyRepository,
+        task_repository::TaskRepository,
+    },
+};
+
+/**
+ * JSON representation of a task, documented in `docs/json-output.md`
+ */
+#[derive(Debug, Serialize)]
+pub struct TaskOutput {
+    pub id: i64,
+    pub title: String,
+    pub info: Option<String>,
+    pub deadline: Option<DeadlineEnum>,
+    pub status: TaskStatusEnum,
+    pub priority: TaskPriorityEnum,
+    pub parent_id: Option<i64>,
+    pub recurrence: Option<RecurrenceEnum>,
+    pub categories: Vec<String>,
+    pub depends_on: Vec<i64>,
+    pub blocks: Vec<i64>,
+    pub subtasks: Vec<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl TaskOutput {
+    /**
+     * Used to build the output of a task together with its categories and relations
+     */
+    pub fn create(conn: &Connection, task: Task) -> Result<Self> {
+        let task_repository = TaskRepository::create(conn);
+        let category_repository = CategoryRepository::create(conn);
+        let dependency_repository = DependencyRepository::create(conn);
+
+        Ok(Self {
+            categories: category_repository.fetch_task_categories(task.id)?,
+            depends_on: dependency_repository.fetch_dependencies(task.id)?,
+            blocks: dependency_repository.fetch_dependents(task.id)?,
+            subtasks: task_repository
+                .fetch_subtasks(task.id)?
+                .iter()
+                .map(|subtask| subtask.id)
+                .collect(),
+            id: task.id,
+            title: task.title,
+            info: task.info,
+            deadline: task.deadline,
+            status: task.status,
+            priority: task.priority,
+            parent_id: task.parent_id,
+            recurrence: task.recurrence,
+            created_at: task.created_at.to_string(),
+            updated_at: task.updated_at.to_string(),
+        })
+    }
+}
+
+/**
+ * JSON result of a command that changed a task
+ */
+#[derive(Debug, Serialize)]
+pub struct TaskChangeOutput {
+    pub task: TaskOutput,
+    /// the next instance created when a recurring task is completed
+    pub next_task: Option<TaskOutput>,
+}
+
+/**
+ * JSON result of a command that changed a dependency
+ */
+#[derive(Debug, Serialize)]
+pub struct DependencyOutput {
+    pub task_id: i64,
+    pub depends_on_id: i64,
+}
+
+/**
+ * JSON representation of a category and the number of tasks using it
+ */
+#[derive(Debug, Serialize)]
+pub struct CategoryOutput {
+    pub name: String,
+    pub count: i64,
+}
+
+/**
+ * JSON result of a command that changed a category
+ */
+#[derive(Debug, Serialize)]
+pub struct CategoryChangeOutput {
+    pub category: String,
+    pub new_category: Option<String>,
+    pub task_id: Option<i64>,
+}
+
+/**
+ * JSON result of a full text search, matched words are wrapped in `**`
+ */
+#[derive(Debug, Serialize)]
+pub struct SearchResultOutput {
+    pub task: TaskOutput,
+    pub score: f64,
+    pub title: String,
+    pub snippet: Option<String>,
+}
+
+/**
+ * JSON representation of a saved view
+ */
+#[derive(Debug, Serialize)]
+pub struct ViewOutput {
+    pub name: String,
+    pub query: ViewQuery,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<View> for ViewOutput {
+    fn from(value: View) -> Self {
+        Self {
+            name: value.name,
+            query: value.query,
+            created_at: value.created_at.to_string(),
+            updated_at: value.updated_at.to_string(),
+        }
+    }
+}
+
+/**
+ * JSON representation of an entry of the actions log
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionOutput {
+    pub id: i64,
+    pub summary: String,
+    pub restored: bool,
+    pub created_at: String,
+    pub logged_at: Option<String>,
+    pub action: ActionEnum,
+}
+
+impl From<Action> for ActionOutput {
+    fn from(value: Action) -> Self {
+        Self {
+            id: value.id,
+            summary: value.action.to_string(),
+            restored: value.restored,
+            created_at: value.created_at.to_string(),
+            logged_at: value.logged_at.and_then(format_logged_at),
+            action: value.action,
+        }
+    }
+}
+
+/**
+ * Used to write the moment an action was logged as RFC 3339
+ */
+fn format_logged_at(value: OffsetDateTime) -> Option<String> {
+    value.format(&Rfc3339).ok()
+}
+
+/**
+ * JSON entry of `task history`, one per change an action made to the task
+ */
+#[derive(Debug, Serialize)]
+pub struct TaskEventOutput {
+    pub action_id: i64,
+    pub summary: String,
+    pub event: TaskEventEnum,
+    pub changes: Vec<FieldChange>,
+    pub created_at: String,
+    pub logged_at: Option<String>,
+}
+
+impl From<TaskEvent> for TaskEventOutput {
+    fn from(value: TaskEvent) -> Self {
+        Self {
+            action_id: value.action_id,
+            summary: value.summary,
+            event: value.event,
+            changes: value.changes,
+            created_at: value.created_at.to_string(),
+            logged_at: value.logged_at.and_then(format_logged_at),
+        }
+    }
+}
+
+/**
+ * JSON comparison of a field stored in an action with its value now, every field of a deleted
+ * task is changed
+ */
+#[derive(Debug, Serialize)]
+pub struct FieldDiffOutput {
+    pub field: &'static str,
+    pub logged: Option<String>,
+    pub current: Option<String>,
+    pub changed: bool,
+}
+
+/**
+ * JSON comparison of a task stored in an action with the task now, `exists` is false
+ * when the task is deleted
+ */
+#[derive(Debug, Serialize)]
+pub struct TaskDiffOutput {
+    pub task_id: i64,
+    pub exists: bool,
+    pub fields: Vec<FieldDiffOutput>,
+}
+
+impl TaskDiffOutput {
+    /**
+     * Used to compare a task snapshot of an action with the task now, `None` for other actions.
+     * Categories and dependencies are only compared when the snapshot stored them.
+     */
+    pub fn create(conn: &Connection, snapshot: &ActionEnum) -> Result<Option<Self>> {
+        let ActionEnum::Task {
+            id,
+            title,
+            info,
+            deadline,
+            categories,
+            dependencies,
+            status,
+            priority,
+            parent_id,
+            recurrence,
+            created_at,
+            ..
+        } = snapshot
+        else {
+            return Ok(None);
+        };
+
+        let task = TaskRepository::create(conn).get_task(*id)?;
+        // lists are joined, an empty list has no value
+        let list = |items: Vec<String>| match items.is_empty() {
+            true => None,
+            false => Some(items.join(", ")),
+        };
+        let edges = |edges: &[(i64, i64)]| {
+            list(
+                edges
+                    .iter()
+                    .map(|(task_id, depends_on_id)| format!("#{} -> #{}", task_id, depends_on_id))
+                    .collect(),
+            )
+        };
+
+        let mut fields: Vec<(&'static str, Option<String>, Option<String>)> = vec![
+            (
+                "title",
+                Some(title.clone()),
+                task.as_ref().map(|task| task.title.clone()),
+            ),
+            (
+                "info",
+                info.clone(),
+                task.as_ref().and_then(|task| task.info.clone()),
+            ),
+            (
+                "deadline",
+                deadline.clone(),
+                task.as_ref()
+                    .and_then(|task| task.deadline.map(|v| v.to_string())),
+            ),
+            (
+                "status",
+                Some(status.to_string()),
+                task.as_ref().map(|task| task.status.to_string()),
+            ),
+            (
+                "priority",
+                Some(priority.to_string()),
+                task.as_ref().map(|task| task.priority.to_string()),
+            ),
+            (
+                "parent_id",
+                parent_id.map(|v| v.to_string()),
+                task.as_ref()
+                    .and_then(|task| task.parent_id.map(|v| v.to_string())),
+            ),
+            (
+                "recurrence",
+                recurrence.as_ref().map(|v| v.to_string()),
+                task.as_ref()
+                    .and_then(|task| task.recurrence.as_ref().map(|v| v.to_string())),
+            ),
+            (
+                "created_at",
+                Some(created_at.clone()),
+                task.as_ref().map(|task| task.created_at.to_string()),
+            ),
+        ];
+        if let Some(categories) = categories {
+            let current = match &task {
+                Some(task) => {
+                    list(CategoryRepository::create(conn).fetch_task_categories(task.id)?)
+                }
+                None => None,
+            };
+            fields.push(("categories", list(categories.clone()), current));
+        }
+        if let Some(dependencies) = dependencies {
+            let current = match &task {
+                Some(task) => edges(&DependencyRepository::create(conn).fetch_task_edges(task.id)?),
+                None => None,
+            };
+            fields.push(("dependencies", edges(dependencies), current));
+        }
+
+        Ok(Some(Self {
+            task_id: *id,
+            exists: task.is_some(),
+            fields: fields
+                .into_iter()
+                .map(|(field, logged, current)| FieldDiffOutput {
+                    field,
+                    changed: task.is_none() || logged != current,
+                    logged,
+                    current,
+                })
+                .collect(),
+        }))
+    }
+}
+
+/**
+ * JSON result of `actions show`
+ */
+#[derive(Debug, Serialize)]
+pub struct ActionDetailOutput {
+    #[serde(flatten)]
+    pub action: ActionOutput,
+    pub tasks: Vec<TaskDiffOutput>,
+}
+
+impl ActionDetailOutput {
+    /**
+     * Used to build the output of an action with a comparison of each task it stored
+     */
+    pub fn create(conn: &Connection, action: Action) -> Result<Self> {
+        let mut tasks = vec![];
+        for snapshot in action.action.task_snapshots() {
+            tasks.extend(TaskDiffOutput::create(conn, snapshot)?);
+        }
+
+        Ok(Self {
+            action: ActionOutput::from(action),
+            tasks,
+        })
+    }
+}
+
+/**
+ * JSON result of an undo or redo
+ */
+#[derive(Debug, Serialize)]
+pub struct UndoRedoOutput {
+    pub operation: &'static str,
+    /// first reverted action
+    pub action: ActionOutput,
+    /// every reverted action, in the order they were reverted
+    pub actions: Vec<ActionOutput>,
+}
+
+/**
+ * JSON result of housekeeping
+ */
+#[derive(Debug, Serialize)]
+pub struct HousekeepingOutput {
+    pub actions_deleted: i64,
+    pub tasks_deleted: i64,
+    pub tasks_updated: i64,
+    pub deleted_task_ids: Vec<i64>,
+    pub archived_task_ids: Vec<i64>,
+    pub dry_run: bool,
+}
+
+/**
+ * JSON representation of a schema migration
+ */
+#[derive(Debug, Serialize)]
+pub struct MigrationOutput {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+impl MigrationOutput {
+    pub fn create(migration: &Migration, version: i64) -> Self {
+        Self {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: migration.version <= version,
+        }
+    }
+}
+
+/**
+ * JSON result of `db status` and `db migrate`
+ */
+#[derive(Debug, Serialize)]
+pub struct DatabaseOutput {
+    /// schema version of the data file
+    pub version: i64,
+    /// schema version of this binary
+    pub latest_version: i64,
+    pub migrations: Vec<MigrationOutput>,
+}
+
+/**
+ * JSON result of rebuilding the full text index
+ */
+#[derive(Debug, Serialize)]
+pub struct ReindexOutput {
+    pub tasks_indexed: i64,
+}
+
+/**
+ * JSON result of an operation the user did not confirm
+ */
+#[derive(Debug, Serialize)]
+pub struct CanceledOutput {
+    pub canceled: bool,
+}
+
+/**
+ * Used to print a single object, pretty for `json` and on one line for `ndjson`
+ */
+pub fn print_item<T: Serialize>(format: OutputFormatEnum, item: &T) -> Result<()> {
+    match format {
+        OutputFormatEnum::Json => println!("{}", serde_json::to_string_pretty(item)?),
+        OutputFormatEnum::Ndjson | OutputFormatEnum::Text => {
+            println!("{}", serde_json::to_string(item)?)
+        }
+    }
+
+    Ok(())
+}
+
+/**
+ * Used to print a list, as an array for `json` and one object per line for `ndjson`
+ */
+pub fn print_items<T: Serialize>(format: OutputFormatEnum, items: &[T]) -> Result<()> {
+    match format {
+        OutputFormatEnum::Json => println!("{}", serde_json::to_string_pretty(items)?),
+        OutputFormatEnum::Ndjson | OutputFormatEnum::Text => {
+            for item in items {
+                println!("{}", serde_json::to_string(item)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use rusqlite::Connection;
+    use serde_json::json;
+    use time::macros::date;
+
+    use crate::{
+        models::{setup_database, AddTask, DeadlineEnum, TaskPriorityEnum, TaskStatusEnum},
+        repositories::{
+            action_repository::ActionRepository, add_task, add_task_dependency,
+            task_repository::TaskRepository,
+        },
+    };
+
+    use super::{ActionOutput, TaskOutput};
+
+    #[test]
+    fn test_json_schema() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let new_task = |title: &str, parent_id: Option<i64>| -> Result<AddTask> {
+            Ok(AddTask {
+                title: title.into(),
+                info: None,
+                deadline: Some(DeadlineEnum::Date(date!(2024 - 02 - 01))),
+                categories: Some(vec!["work".into()]),
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::High,
+                parent_id,
+                recurrence: Some("weekly:mon".parse()?),
+                created_at: date!(2024 - 01 - 01),
+            })
+        };
+        let task = add_task(&conn, new_task("Report", None)?)?;
+        add_task(&conn, new_task("Draft", Some(task.id))?)?;
+        add_task_dependency(&conn, 2, 1)?;
+
+        let task = TaskRepository::create(&conn).get_task(task.id)?.unwrap();
+        let value = serde_json::to_value(TaskOutput::create(&conn, task)?)?;
+        let updated_at = value["updated_at"].clone();
+        assert_eq!(
+            json!({
+                "id": 1,
+                "title": "Report",
+                "info": null,
+                "deadline": "2024-02-01",
+                "status": "undone",
+                "priority": "high",
+                "parent_id": null,
+                "recurrence": "weekly:mon",
+                "categories": ["work"],
+                "depends_on": [],
+                "blocks": [2],
+                "subtasks": [2],
+                "created_at": "2024-01-01",
+                "updated_at": updated_at,
+            }),
+            value
+        );
+
+        let action = ActionRepository::create(&conn)
+            .fetch_undo_actions(1, None)?
+            .remove(0);
+        let value = serde_json::to_value(ActionOutput::from(action))?;
+        assert_eq!(json!(false), value["restored"]);
+        assert_eq!(
+            json!("[Dependency][Create] - (#2) - [Depends on: 1]"),
+            value["summary"]
+        );
+        assert_eq!(
+            json!({"kind": "dependency", "action_type": "create", "task_id": 2, "depends_on_id": 1}),
+            value["action"]
+        );
+
+        Ok(())
+    }
+}
@@ -1,38 +1,128 @@
 use action_repository::ActionRepository;
 use anyhow::Result;
 use category_repository::CategoryRepository;
-use rusqlite::Connection;
-use std::time::SystemTime;
+use dependency_repository::DependencyRepository;
+use rusqlite::{
+    params_from_iter,
+    types::{ToSqlOutput, ValueRef},
+    Connection, Params, ToSql,
+};
+use sea_query::{time_format, Value, Values};
+use std::str::FromStr;
 use task_repository::TaskRepository;
-use time::{Date, OffsetDateTime};
+use time::Date;
 
 pub mod action_repository;
 pub mod category_repository;
+pub mod dependency_repository;
 pub mod task_repository;
+pub mod view_repository;
 
 use crate::{
-    models::{Action, ActionEnum, ActionTypeEnum, AddTask, QueryTaskPayload, Task, UpdateTask},
-    utils::{created_at_parser, date_parser},
+    models::{
+        Action, ActionEnum, ActionFilter, ActionTypeEnum, AddTask, DeadlineEnum, DeleteModeEnum,
+        FieldChange, HousekeepingPayload, HousekeepingReport, QueryTaskPayload, RecurrenceEnum,
+        Task, TaskEvent, TaskEventEnum, TaskPage, TaskPriorityEnum, TaskStatusEnum, UpdateTask,
+    },
+    utils::{created_at_parser, format_ids, get_today},
 };
 
+/**
+ * A value of a statement built with `build(SqliteQueryBuilder)`, bound to its placeholder
+ */
+struct SqlValue<'a>(&'a Value);
+
+impl ToSql for SqlValue<'_> {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let format_time = |result: Result<String, time::error::Format>| {
+            result
+                .map(ToSqlOutput::from)
+                .map_err(|err| rusqlite::Error::ToSqlConversionFailure(err.into()))
+        };
+
+        match self.0 {
+            Value::Bool(Some(v)) => Ok(ToSqlOutput::from(*v)),
+            Value::TinyInt(Some(v)) => Ok(ToSqlOutput::from(*v)),
+            Value::SmallInt(Some(v)) => Ok(ToSqlOutput::from(*v)),
+            Value::Int(Some(v)) => Ok(ToSqlOutput::from(*v)),
+            Value::BigInt(Some(v)) => Ok(ToSqlOutput::from(*v)),
+            Value::TinyUnsigned(Some(v)) => Ok(ToSqlOutput::from(*v)),
+            Value::SmallUnsigned(Some(v)) => Ok(ToSqlOutput::from(*v)),
+            Value::Unsigned(Some(v)) => Ok(ToSqlOutput::from(*v)),
+            Value::BigUnsigned(Some(v)) => v.to_sql(),
+            Value::Float(Some(v)) => Ok(ToSqlOutput::from(*v as f64)),
+            Value::Double(Some(v)) => Ok(ToSqlOutput::from(*v)),
+            Value::String(Some(v)) => Ok(ToSqlOutput::Borrowed(ValueRef::Text(v.as_bytes()))),
+            Value::Char(Some(v)) => Ok(ToSqlOutput::from(v.to_string())),
+            Value::Bytes(Some(v)) => Ok(ToSqlOutput::Borrowed(ValueRef::Blob(v))),
+            Value::TimeDate(Some(v)) => format_time(v.format(time_format::FORMAT_DATE)),
+            Value::TimeTime(Some(v)) => format_time(v.format(time_format::FORMAT_TIME)),
+            Value::TimeDateTime(Some(v)) => format_time(v.format(time_format::FORMAT_DATETIME)),
+            Value::TimeDateTimeWithTimeZone(Some(v)) => {
+                format_time(v.format(time_format::FORMAT_DATETIME_TZ))
+            }
+            // the remaining values are the NULL of each type
+            _ => Ok(ToSqlOutput::from(rusqlite::types::Null)),
+        }
+    }
+}
+
+/**
+ * Used to bind the values of a statement built with `build(SqliteQueryBuilder)`,
+ * so that user text never becomes part of the SQL
+ */
+fn bind(values: &Values) -> impl Params + '_ {
+    params_from_iter(values.0.iter().map(SqlValue))
+}
+
 /**
  * Used to get current date
  */
 fn get_now() -> Date {
-    let now = SystemTime::now();
-    let now = OffsetDateTime::from(now).date();
-    now
+    get_today()
 }
 
 /**
- * Used to create a Task and create its respective Action
+ * Used to snapshot a Task into its respective Action
  */
-pub fn add_task(conn: &Connection, task: AddTask) -> Result<Task> {
-    let now = get_now();
+fn task_action(
+    action_type: ActionTypeEnum,
+    task: &Task,
+    categories: Option<Vec<String>>,
+    dependencies: Option<Vec<(i64, i64)>>,
+) -> ActionEnum {
+    ActionEnum::Task {
+        action_type,
+        id: task.id,
+        title: task.title.clone(),
+        info: task.info.clone(),
+        deadline: task.deadline.map(|v| v.to_string()),
+        status: task.status,
+        priority: task.priority,
+        parent_id: task.parent_id,
+        recurrence: task.recurrence.clone(),
+        updated_at: task.updated_at.to_string(),
+        created_at: task.created_at.to_string(),
+        categories,
+        dependencies,
+    }
+}
 
-    let task_repository = TaskRepository::create(&conn);
-    let action_repository = ActionRepository::create(&conn);
-    let category_repository = CategoryRepository::create(&conn);
+/**
+ * Used to create a Task with its categories and build its respective Action
+ */
+fn insert_task(conn: &Connection, task: AddTask, now: Date) -> Result<(Task, ActionEnum)> {
+    let task_repository = TaskRepository::create(conn);
+    let category_repository = CategoryRepository::create(conn);
+
+    if let Some(parent_id) = task.parent_id {
+        if task_repository.get_task(parent_id)?.is_none() {
+            return Err(anyhow::anyhow!(
+                "Parent task with id (#{}) not found!",
+                parent_id
+            ));
+        }
+    }
 
     let categories = task.categories.clone();
     let task = task_repository.create_task(task)?;
@@ -48,87 +138,219 @@ pub fn add_task(conn: &Connection, task: AddTask) -> Result<Task> {
         info: task.info.clone(),
         deadline: task.deadline.map(|v| v.to_string()),
         status: task.status,
+        priority: task.priority,
+        parent_id: task.parent_id,
+        recurrence: task.recurrence.clone(),
         updated_at: now.to_string(),
         created_at: task.created_at.to_string(),
         categories,
+        dependencies: None,
     };
+
+    Ok((task, action))
+}
+
+/**
+ * Used to create a Task and create its respective Action
+ */
+pub fn add_task(conn: &Connection, task: AddTask) -> Result<Task> {
+    let now = get_now();
+
+    let action_repository = ActionRepository::create(conn);
+
+    let (task, action) = insert_task(conn, task, now)?;
     action_repository.create_action(action, &now.to_string())?;
 
     Ok(task)
 }
 
 /**
- * Used to edit a Task and create its respective Action
+ * Used to edit a Task and create its respective Action.
+ * Completing a recurring task also creates its next instance, which is returned alongside.
  */
-pub fn edit_task(conn: &Connection, id: i64, old_task: Task, new_task: UpdateTask) -> Result<Task> {
+pub fn edit_task(
+    conn: &Connection,
+    id: i64,
+    old_task: Task,
+    mut new_task: UpdateTask,
+) -> Result<(Task, Option<Task>)> {
     let now = get_now();
 
-    let task_repository = TaskRepository::create(&conn);
-    let action_repository = ActionRepository::create(&conn);
+    let task_repository = TaskRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
+    let category_repository = CategoryRepository::create(conn);
+
+    let recurrence = match &new_task.recurrence {
+        Some(recurrence) => recurrence.clone(),
+        None => old_task.recurrence.clone(),
+    };
+    let completed =
+        new_task.status == Some(TaskStatusEnum::Done) && old_task.status != TaskStatusEnum::Done;
+
+    // the recurrence rule moves on to the next instance
+    let recurrence = match recurrence {
+        Some(recurrence) if completed => {
+            new_task.recurrence = Some(None);
+            Some(recurrence)
+        }
+        _ => None,
+    };
 
     task_repository.update_task(id, new_task, &now.to_string())?;
 
-    let action = ActionEnum::Task {
-        action_type: ActionTypeEnum::Update,
-        id: old_task.id,
-        title: old_task.title.clone(),
-        info: old_task.info.clone(),
-        deadline: old_task.deadline.map(|v| v.to_string()),
-        status: old_task.status,
-        updated_at: old_task.updated_at.to_string(),
-        created_at: old_task.created_at.to_string(),
-        categories: None,
+    let action = task_action(ActionTypeEnum::Update, &old_task, None, None);
+
+    let task = task_repository
+        .get_task(id)?
+        .ok_or_else(|| anyhow::anyhow!("Task (#{}) no longer exists", id))?;
+
+    let (action, next_task) = match recurrence {
+        Some(recurrence) => {
+            let mut date = recurrence.next_date(task.deadline.map_or(now, |v| v.date()))?;
+            while date <= now {
+                date = recurrence.next_date(date)?;
+            }
+            let deadline = match task.deadline {
+                Some(deadline) => deadline.with_date(date),
+                None => DeadlineEnum::Date(date),
+            };
+
+            let categories = category_repository.fetch_task_categories(id)?;
+            let next_task = AddTask {
+                title: task.title.clone(),
+                info: task.info.clone(),
+                deadline: Some(deadline),
+                categories: if categories.is_empty() {
+                    None
+                } else {
+                    Some(categories)
+                },
+                status: TaskStatusEnum::Undone,
+                priority: task.priority,
+                parent_id: task.parent_id,
+                recurrence: Some(recurrence),
+                created_at: now,
+            };
+            let (next_task, create) = insert_task(conn, next_task, now)?;
+
+            let action = ActionEnum::TaskRecurrence {
+                update: Box::new(action),
+                create: Box::new(create),
+            };
+            (action, Some(next_task))
+        }
+        None => (action, None),
     };
     action_repository.create_action(action, &now.to_string())?;
 
-    let task = task_repository.get_task(id)?.expect("Task should exist");
-
-    Ok(task)
+    Ok((task, next_task))
 }
 
 /**
- * Used to delete a Task and its respective Action
+ * Used to delete a Task and its respective Action, subtasks are handled according to the mode
  */
-pub fn delete_task(conn: &Connection, task: &Task) -> Result<()> {
+pub fn delete_task(conn: &Connection, task: &Task, mode: DeleteModeEnum) -> Result<()> {
     let now = get_now();
 
-    let task_repository = TaskRepository::create(&conn);
-    let action_repository = ActionRepository::create(&conn);
-    let category_repository = CategoryRepository::create(&conn);
+    let task_repository = TaskRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
+    let category_repository = CategoryRepository::create(conn);
+    let dependency_repository = DependencyRepository::create(conn);
+
+    let subtasks = task_repository.fetch_subtasks(task.id)?;
 
-    let categories = category_repository.fetch_task_categories(task.id)?;
+    if !subtasks.is_empty() && mode == DeleteModeEnum::Refuse {
+        return Err(anyhow::anyhow!(
+            "Task (#{}) has {} subtask(s), use cascade or reparent to delete it",
+            task.id,
+            subtasks.len()
+        ));
+    }
 
-    category_repository.delete_task_categories(task.id)?;
+    let (tasks, reparented) = match mode {
+        DeleteModeEnum::Cascade => {
+            let mut tasks = vec![task.clone()];
+            tasks.extend(task_repository.fetch_descendants(task.id)?);
+            (tasks, vec![])
+        }
+        DeleteModeEnum::Refuse | DeleteModeEnum::Reparent => {
+            let reparented: Vec<i64> = subtasks.iter().map(|subtask| subtask.id).collect();
+            (vec![task.clone()], reparented)
+        }
+    };
 
-    task_repository.delete_task(task)?;
+    task_repository.set_parent(&reparented, task.parent_id)?;
+
+    let mut actions = vec![];
+    for task in tasks.iter().rev() {
+        let categories = category_repository.fetch_task_categories(task.id)?;
+        category_repository.delete_task_categories(task.id)?;
+        let dependencies = dependency_repository.fetch_task_edges(task.id)?;
+        dependency_repository.delete_task_dependencies(task.id)?;
+        task_repository.delete_task(task)?;
+        actions.push(task_action(
+            ActionTypeEnum::Delete,
+            task,
+            Some(categories),
+            Some(dependencies),
+        ));
+    }
+    actions.reverse();
 
-    let action = ActionEnum::Task {
-        action_type: ActionTypeEnum::Delete,
-        id: task.id,
-        title: task.title.clone(),
-        info: task.info.clone(),
-        deadline: task.deadline.map(|v| v.to_string()),
-        status: task.status,
-        updated_at: task.updated_at.to_string(),
-        created_at: task.created_at.to_string(),
-        categories: Some(categories),
+    let action = if actions.len() == 1 && reparented.is_empty() {
+        actions.remove(0)
+    } else {
+        ActionEnum::TaskTree {
+            action_type: ActionTypeEnum::Delete,
+            tasks: actions,
+            reparented,
+        }
     };
     action_repository.create_action(action, &now.to_string())?;
 
     Ok(())
 }
 
+/**
+ * Used to run several logged operations as one command: the actions they log are replaced
+ * by a single compound action, so that one undo reverses all of them
+ */
+pub fn compound_operation<T>(
+    conn: &Connection,
+    operation: impl FnOnce(&Connection) -> Result<T>,
+) -> Result<T> {
+    let action_repository = ActionRepository::create(conn);
+
+    let last_id = action_repository.last_action_id()?;
+    let result = operation(conn)?;
+    action_repository.compound_actions_after(last_id, &get_now().to_string())?;
+
+    Ok(result)
+}
+
 /**
  * Used to undo/redo a performed logged action
  */
 pub fn undo_redo_operation(conn: &Connection, action: Action) -> Result<String> {
-    let task_repository = TaskRepository::create(&conn);
-    let action_repository = ActionRepository::create(&conn);
-    let category_repository = CategoryRepository::create(&conn);
+    let action_repository = ActionRepository::create(conn);
 
     let message = action.action.to_string();
 
-    match action.action {
+    let new_action = revert_action(conn, action.action, action.restored)?;
+    action_repository.update_action(action.id, new_action, !action.restored)?;
+
+    Ok(format!("[Undo]{}", message))
+}
+
+/**
+ * Used to apply the inverse of an action, returns the action that reverts it back
+ */
+fn revert_action(conn: &Connection, action: ActionEnum, restored: bool) -> Result<ActionEnum> {
+    let task_repository = TaskRepository::create(conn);
+    let category_repository = CategoryRepository::create(conn);
+    let dependency_repository = DependencyRepository::create(conn);
+
+    let new_action = match action {
         ActionEnum::Task {
             action_type,
             id: task_id,
@@ -136,63 +358,65 @@ pub fn undo_redo_operation(conn: &Connection, action: Action) -> Result<String>
             info,
             deadline,
             status,
+            priority,
+            parent_id,
+            recurrence,
             updated_at,
             created_at,
             categories,
+            dependencies,
         } => match action_type {
             ActionTypeEnum::Create => {
                 let task = task_repository
                     .get_task(task_id)?
-                    .expect("Task should exist");
+                    .ok_or_else(|| anyhow::anyhow!("Task (#{}) no longer exists", task_id))?;
                 category_repository.delete_task_categories(task_id)?;
+                dependency_repository.delete_task_dependencies(task_id)?;
                 task_repository.delete_task(&task)?;
-                action_repository.update_action(
-                    action.id,
-                    ActionEnum::Task {
-                        action_type: ActionTypeEnum::Delete,
-                        id: task_id,
-                        title: title.clone(),
-                        info,
-                        deadline,
-                        status,
-                        updated_at,
-                        created_at,
-                        categories,
-                    },
-                    !action.restored,
-                )?;
+                ActionEnum::Task {
+                    action_type: ActionTypeEnum::Delete,
+                    id: task_id,
+                    title: title.clone(),
+                    info,
+                    deadline,
+                    status,
+                    priority,
+                    parent_id,
+                    recurrence,
+                    updated_at,
+                    created_at,
+                    categories,
+                    dependencies,
+                }
             }
             ActionTypeEnum::Update => {
                 let old_task = task_repository
                     .get_task(task_id)?
-                    .expect("Task should exist");
+                    .ok_or_else(|| anyhow::anyhow!("Task (#{}) no longer exists", task_id))?;
                 let new_task = UpdateTask {
                     title: Some(title.clone()),
                     info: Some(info),
-                    deadline: Some(deadline.map(|v| date_parser(&v)).transpose()?),
+                    deadline: Some(deadline.map(|v| DeadlineEnum::from_str(&v)).transpose()?),
                     status: Some(status),
+                    priority: Some(priority),
+                    recurrence: Some(recurrence),
                     created_at: Some(created_at_parser(&created_at)?),
                 };
                 task_repository.update_task(task_id, new_task, &updated_at)?;
-                let new_action = ActionEnum::Task {
-                    action_type: ActionTypeEnum::Update,
-                    id: task_id,
-                    title: old_task.title,
-                    info: old_task.info,
-                    deadline: old_task.deadline.map(|v| v.to_string()),
-                    status: old_task.status,
-                    updated_at: old_task.updated_at.to_string(),
-                    created_at: old_task.created_at.to_string(),
-                    categories,
-                };
-                action_repository.update_action(action.id, new_action, !action.restored)?;
+                task_action(ActionTypeEnum::Update, &old_task, categories, dependencies)
             }
             ActionTypeEnum::Delete => {
                 let new_task = AddTask {
                     title: title.clone(),
                     info: info.clone(),
-                    deadline: deadline.clone().map(|v| date_parser(&v)).transpose()?,
-                    status: status,
+                    deadline: deadline
+                        .clone()
+                        .map(|v| DeadlineEnum::from_str(&v))
+                        .transpose()?,
+                    status,
+                    priority,
+                    parent_id,
+                    recurrence: recurrence.clone(),
                     created_at: created_at_parser(&created_at)?,
                     categories: None,
                 };
@@ -200,23 +424,78 @@ pub fn undo_redo_operation(conn: &Connection, action: Action) -> Result<String>
                 if let Some(categories) = &categories {
                     category_repository.batch_create_task_categories(task_id, categories)?;
                 }
-                action_repository.update_action(
-                    action.id,
-                    ActionEnum::Task {
-                        action_type: ActionTypeEnum::Create,
-                        id: task_id,
-                        title,
-                        info,
-                        deadline,
-                        status,
-                        updated_at,
-                        created_at,
-                        categories,
-                    },
-                    !action.restored,
-                )?;
+                if let Some(dependencies) = &dependencies {
+                    dependency_repository.batch_create_dependencies(dependencies)?;
+                }
+                ActionEnum::Task {
+                    action_type: ActionTypeEnum::Create,
+                    id: task_id,
+                    title,
+                    info,
+                    deadline,
+                    status,
+                    priority,
+                    parent_id,
+                    recurrence,
+                    updated_at,
+                    created_at,
+                    categories,
+                    dependencies,
+                }
             }
         },
+        ActionEnum::TaskTree {
+            action_type,
+            tasks,
+            reparented,
+        } => {
+            let (root_id, root_parent_id) = match tasks.first() {
+                Some(ActionEnum::Task { id, parent_id, .. }) => (*id, *parent_id),
+                _ => return Err(anyhow::anyhow!("Task tree action without tasks!")),
+            };
+            match action_type {
+                ActionTypeEnum::Delete => {
+                    // recreate parents before their children
+                    let mut new_tasks = vec![];
+                    for task in tasks {
+                        new_tasks.push(revert_action(conn, task, restored)?);
+                    }
+                    task_repository.set_parent(&reparented, Some(root_id))?;
+                    ActionEnum::TaskTree {
+                        action_type: ActionTypeEnum::Create,
+                        tasks: new_tasks,
+                        reparented,
+                    }
+                }
+                ActionTypeEnum::Create => {
+                    // delete children before their parents
+                    task_repository.set_parent(&reparented, root_parent_id)?;
+                    let mut new_tasks = vec![];
+                    for task in tasks.into_iter().rev() {
+                        new_tasks.push(revert_action(conn, task, restored)?);
+                    }
+                    new_tasks.reverse();
+                    ActionEnum::TaskTree {
+                        action_type: ActionTypeEnum::Delete,
+                        tasks: new_tasks,
+                        reparented,
+                    }
+                }
+                ActionTypeEnum::Update => {
+                    return Err(anyhow::anyhow!(
+                        "Operation update not permitted for task tree!"
+                    ))
+                }
+            }
+        }
+        ActionEnum::TaskRecurrence { update, create } => {
+            let create = revert_action(conn, *create, restored)?;
+            let update = revert_action(conn, *update, restored)?;
+            ActionEnum::TaskRecurrence {
+                update: Box::new(update),
+                create: Box::new(create),
+            }
+        }
         ActionEnum::Category {
             action_type,
             category,
@@ -224,15 +503,11 @@ pub fn undo_redo_operation(conn: &Connection, action: Action) -> Result<String>
         } => match action_type {
             ActionTypeEnum::Create => {
                 category_repository.delete_category(task_id, &category)?;
-                action_repository.update_action(
-                    action.id,
-                    ActionEnum::Category {
-                        action_type: ActionTypeEnum::Delete,
-                        category,
-                        task_id,
-                    },
-                    !action.restored,
-                )?;
+                ActionEnum::Category {
+                    action_type: ActionTypeEnum::Delete,
+                    category,
+                    task_id,
+                }
             }
             ActionTypeEnum::Update => {
                 return Err(anyhow::anyhow!(
@@ -241,15 +516,44 @@ pub fn undo_redo_operation(conn: &Connection, action: Action) -> Result<String>
             }
             ActionTypeEnum::Delete => {
                 category_repository.create_category(task_id, &category)?;
-                action_repository.update_action(
-                    action.id,
-                    ActionEnum::Category {
-                        action_type: ActionTypeEnum::Create,
-                        category,
-                        task_id,
-                    },
-                    !action.restored,
-                )?;
+                ActionEnum::Category {
+                    action_type: ActionTypeEnum::Create,
+                    category,
+                    task_id,
+                }
+            }
+        },
+        ActionEnum::Dependency {
+            action_type,
+            task_id,
+            depends_on_id,
+        } => match action_type {
+            ActionTypeEnum::Create => {
+                dependency_repository.delete_dependency(task_id, depends_on_id)?;
+                ActionEnum::Dependency {
+                    action_type: ActionTypeEnum::Delete,
+                    task_id,
+                    depends_on_id,
+                }
+            }
+            ActionTypeEnum::Update => {
+                return Err(anyhow::anyhow!(
+                    "Operation update not permitted for dependency!"
+                ))
+            }
+            ActionTypeEnum::Delete => {
+                // a housekeeping purge keeps the action while one of the tasks remains
+                for id in [task_id, depends_on_id] {
+                    if task_repository.get_task(id)?.is_none() {
+                        anyhow::bail!("Task (#{}) no longer exists", id);
+                    }
+                }
+                dependency_repository.create_dependency(task_id, depends_on_id)?;
+                ActionEnum::Dependency {
+                    action_type: ActionTypeEnum::Create,
+                    task_id,
+                    depends_on_id,
+                }
             }
         },
         ActionEnum::RenameTaskCategory {
@@ -258,369 +562,1175 @@ pub fn undo_redo_operation(conn: &Connection, action: Action) -> Result<String>
             task_id,
         } => {
             category_repository.rename_category(task_id, &new_category, &old_category)?;
-            action_repository.update_action(
-                action.id,
-                ActionEnum::RenameTaskCategory {
-                    new_category: old_category,
-                    old_category: new_category,
-                    task_id,
-                },
-                !action.restored,
-            )?;
+            ActionEnum::RenameTaskCategory {
+                new_category: old_category,
+                old_category: new_category,
+                task_id,
+            }
         }
         ActionEnum::BatchCategoryDelete { task_ids, category } => {
-            if action.restored {
-                category_repository.batch_delete_category(&category)?;
+            if restored {
+                for task_id in &task_ids {
+                    category_repository.delete_category(*task_id, &category)?;
+                }
             } else {
                 category_repository.batch_create_category(&task_ids, &category)?;
             }
-            action_repository.update_action(
-                action.id,
-                ActionEnum::BatchCategoryDelete {
-                    task_ids: task_ids,
-                    category: category,
-                },
-                !action.restored,
-            )?;
+            ActionEnum::BatchCategoryDelete { task_ids, category }
         }
         ActionEnum::BatchCategoryRename {
             old_category,
             new_category,
+            task_ids,
         } => {
             category_repository.batch_rename_category(&new_category, &old_category)?;
-            action_repository.update_action(
-                action.id,
-                ActionEnum::BatchCategoryRename {
-                    old_category: new_category,
-                    new_category: old_category,
-                },
-                !action.restored,
-            )?;
+            ActionEnum::BatchCategoryRename {
+                old_category: new_category,
+                new_category: old_category,
+                task_ids,
+            }
+        }
+        // undoing a revert applies the reverted action again
+        ActionEnum::Revert { action_id, action } => ActionEnum::Revert {
+            action_id,
+            action: Box::new(revert_action(conn, *action, !restored)?),
+        },
+        ActionEnum::Compound { actions } => {
+            let mut new_actions = vec![];
+            if restored {
+                // redo in the order the actions were applied
+                for action in actions {
+                    new_actions.push(revert_action(conn, action, restored)?);
+                }
+            } else {
+                // undo the last applied action first
+                for action in actions.into_iter().rev() {
+                    new_actions.push(revert_action(conn, action, restored)?);
+                }
+                new_actions.reverse();
+            }
+            ActionEnum::Compound {
+                actions: new_actions,
+            }
         }
     };
 
-    Ok(format!("[Undo]{}", message))
+    Ok(new_action)
 }
 
 /**
- * Used to add a new category on the task
+ * Used to revert a single past action on top of the current state, like `git revert`.
+ * The revert is refused when the data it touches changed in a way it cannot merge,
+ * otherwise it is logged as a new undoable action. Returns the reverted action.
  */
-pub fn add_category_to_task(conn: &Connection, task_id: i64, category: &str) -> Result<()> {
+pub fn revert_past_action(conn: &Connection, action_id: i64) -> Result<Action> {
     let now = get_now();
 
-    let category_repository = CategoryRepository::create(&conn);
-    let action_repository = ActionRepository::create(&conn);
-
-    category_repository.create_category(task_id, category)?;
-
-    action_repository.create_action(
-        ActionEnum::Category {
-            action_type: ActionTypeEnum::Create,
-            category: category.to_string(),
-            task_id,
-        },
-        &now.to_string(),
-    )?;
+    let action_repository = ActionRepository::create(conn);
 
-    Ok(())
-}
-
-/**
- * Used to rename a category for a task
- */
-pub fn rename_task_category(
-    conn: &Connection,
-    task_id: i64,
-    old_category: &str,
-    new_category: &str,
-) -> Result<()> {
-    let now = get_now();
+    let action = match action_repository.get_action(action_id)? {
+        Some(action) => action,
+        None => {
+            return Err(anyhow::anyhow!(
+                "Action with id (#{}) not found!",
+                action_id
+            ))
+        }
+    };
+    if action.restored {
+        return Err(anyhow::anyhow!(
+            "Action (#{}) is undone, use redo to apply it again",
+            action_id
+        ));
+    }
 
-    let category_repository = CategoryRepository::create(&conn);
-    let action_repository = ActionRepository::create(&conn);
+    // the actions applied after it, oldest first
+    let mut later = action_repository.fetch_undo_actions(1, Some(action_id))?;
+    later.pop();
+    later.reverse();
 
-    category_repository.rename_category(task_id, old_category, new_category)?;
+    let inverse = revert_past(conn, action.action.clone(), &later)
+        .map_err(|err| anyhow::anyhow!("Cannot revert action (#{}): {}", action_id, err))?;
 
     action_repository.create_action(
-        ActionEnum::RenameTaskCategory {
-            old_category: old_category.to_string(),
-            new_category: new_category.to_string(),
-            task_id: task_id,
+        ActionEnum::Revert {
+            action_id,
+            action: Box::new(inverse),
         },
         &now.to_string(),
     )?;
 
-    Ok(())
+    Ok(action)
 }
 
 /**
- * Used to remove a category from a task
+ * Used to apply the inverse of a past action after checking it against the current state
+ * and the `later` actions, returns the applied inverse as an undone action
  */
-pub fn remove_task_category(conn: &Connection, task_id: i64, category: &str) -> Result<()> {
-    let now = get_now();
-
-    let category_repository = CategoryRepository::create(&conn);
-    let action_repository = ActionRepository::create(&conn);
-
-    category_repository.delete_category(task_id, category)?;
+fn revert_past(conn: &Connection, action: ActionEnum, later: &[Action]) -> Result<ActionEnum> {
+    let task_repository = TaskRepository::create(conn);
+    let category_repository = CategoryRepository::create(conn);
+    let dependency_repository = DependencyRepository::create(conn);
+
+    let existing_task = |task_id: i64| -> Result<Task> {
+        task_repository
+            .get_task(task_id)?
+            .ok_or_else(|| anyhow::anyhow!("task (#{}) was deleted since", task_id))
+    };
 
-    action_repository.create_action(
-        ActionEnum::Category {
+    match &action {
+        ActionEnum::Task {
+            action_type: ActionTypeEnum::Update,
+            ..
+        } => return revert_task_update(conn, &action, later),
+        ActionEnum::Task {
+            action_type: ActionTypeEnum::Create,
+            id,
+            ..
+        } => {
+            existing_task(*id)?;
+            if !task_repository.fetch_subtasks(*id)?.is_empty() {
+                anyhow::bail!("task (#{}) has subtasks now", id);
+            }
+            ensure_untouched(later, *id)?;
+        }
+        ActionEnum::Task {
             action_type: ActionTypeEnum::Delete,
-            category: category.to_string(),
+            ..
+        } => ensure_restorable(conn, &action, &[])?,
+        ActionEnum::TaskTree {
+            action_type: ActionTypeEnum::Delete,
+            tasks,
+            reparented,
+        } => {
+            let ids = action.task_ids();
+            for task in tasks {
+                ensure_restorable(conn, task, &ids)?;
+            }
+            for task_id in reparented {
+                existing_task(*task_id)?;
+            }
+        }
+        ActionEnum::TaskTree { .. } => anyhow::bail!("only deleted task trees can be reverted"),
+        ActionEnum::TaskRecurrence { update, create } => {
+            // drop the next instance before restoring the completed one
+            let create = revert_past(conn, *create.clone(), later)?;
+            let update = revert_past(conn, *update.clone(), later)?;
+            return Ok(ActionEnum::TaskRecurrence {
+                update: Box::new(update),
+                create: Box::new(create),
+            });
+        }
+        ActionEnum::Category {
+            action_type,
+            category,
             task_id,
-        },
-        &now.to_string(),
-    )?;
-
-    Ok(())
-}
-
-/**
- * used to batch rename a category
- */
-pub fn batch_rename_category(
-    conn: &Connection,
-    old_category: &str,
-    new_category: &str,
-) -> Result<()> {
-    let now = get_now();
-
-    let category_repository = CategoryRepository::create(&conn);
-    let action_repository = ActionRepository::create(&conn);
-
-    category_repository.batch_rename_category(old_category, new_category)?;
-
-    action_repository.create_action(
-        ActionEnum::BatchCategoryRename {
-            old_category: old_category.to_string(),
-            new_category: new_category.to_string(),
-        },
-        &now.to_string(),
-    )?;
+        } => {
+            existing_task(*task_id)?;
+            let present = category_repository
+                .fetch_category(*task_id, category)?
+                .is_some();
+            match (action_type, present) {
+                (ActionTypeEnum::Create, false) => anyhow::bail!(
+                    "task (#{}) no longer has the category {}",
+                    task_id,
+                    category
+                ),
+                (ActionTypeEnum::Delete, true) => {
+                    anyhow::bail!("task (#{}) has the category {} again", task_id, category)
+                }
+                _ => {}
+            }
+        }
+        ActionEnum::Dependency {
+            action_type,
+            task_id,
+            depends_on_id,
+        } => {
+            existing_task(*task_id)?;
+            existing_task(*depends_on_id)?;
+            let present = dependency_repository.has_dependency(*task_id, *depends_on_id)?;
+            match (action_type, present) {
+                (ActionTypeEnum::Create, false) => anyhow::bail!(
+                    "task (#{}) no longer depends on task (#{})",
+                    task_id,
+                    depends_on_id
+                ),
+                (ActionTypeEnum::Delete, true) => anyhow::bail!(
+                    "task (#{}) depends on task (#{}) again",
+                    task_id,
+                    depends_on_id
+                ),
+                (ActionTypeEnum::Delete, false)
+                    if dependency_repository.would_create_cycle(*task_id, *depends_on_id)? =>
+                {
+                    anyhow::bail!(
+                        "task (#{}) cannot depend on task (#{}) again, it would create a cycle",
+                        task_id,
+                        depends_on_id
+                    )
+                }
+                _ => {}
+            }
+        }
+        ActionEnum::RenameTaskCategory {
+            old_category,
+            new_category,
+            task_id,
+        } => {
+            existing_task(*task_id)?;
+            if category_repository
+                .fetch_category(*task_id, new_category)?
+                .is_none()
+            {
+                anyhow::bail!(
+                    "task (#{}) no longer has the category {}",
+                    task_id,
+                    new_category
+                );
+            }
+            if category_repository
+                .fetch_category(*task_id, old_category)?
+                .is_some()
+            {
+                anyhow::bail!(
+                    "task (#{}) has the category {} again",
+                    task_id,
+                    old_category
+                );
+            }
+        }
+        ActionEnum::BatchCategoryDelete { task_ids, category } => {
+            // merge: give the category back to the tasks that still exist and lack it
+            let mut restored_ids = vec![];
+            for task_id in task_ids {
+                if task_repository.get_task(*task_id)?.is_some()
+                    && category_repository
+                        .fetch_category(*task_id, category)?
+                        .is_none()
+                {
+                    restored_ids.push(*task_id);
+                }
+            }
+            if restored_ids.is_empty() {
+                anyhow::bail!("no task is left to get the category {} back", category);
+            }
+            category_repository.batch_create_category(&restored_ids, category)?;
+            return Ok(ActionEnum::BatchCategoryDelete {
+                task_ids: restored_ids,
+                category: category.clone(),
+            });
+        }
+        ActionEnum::BatchCategoryRename {
+            old_category,
+            new_category,
+            ..
+        } => {
+            let task_ids = category_repository.get_category_task_ids(new_category)?;
+            if task_ids.is_empty() {
+                anyhow::bail!("no task has the category {} anymore", new_category);
+            }
+            let both: Vec<i64> = category_repository
+                .get_category_task_ids(old_category)?
+                .into_iter()
+                .filter(|task_id| task_ids.contains(task_id))
+                .collect();
+            if !both.is_empty() {
+                anyhow::bail!(
+                    "task(s) {} have both the categories {} and {}",
+                    format_ids(&both),
+                    old_category,
+                    new_category
+                );
+            }
+        }
+        ActionEnum::Revert { .. } => {
+            for task_id in action.task_ids() {
+                existing_task(task_id)?;
+                ensure_untouched(later, task_id)?;
+            }
+        }
+        ActionEnum::Compound { actions } => {
+            // revert the last applied action first
+            let mut inverses = vec![];
+            for action in actions.iter().rev() {
+                inverses.push(revert_past(conn, action.clone(), later)?);
+            }
+            inverses.reverse();
+            return Ok(ActionEnum::Compound { actions: inverses });
+        }
+    }
 
-    Ok(())
+    revert_action(conn, action, false)
 }
 
 /**
- * Used to batch delete a category
+ * Used to refuse reverting an action when later actions changed one of its tasks
  */
-pub fn batch_delete_category(conn: &Connection, category: &str) -> Result<()> {
-    let now = get_now();
+fn ensure_untouched(later: &[Action], task_id: i64) -> Result<()> {
+    let ids: Vec<i64> = later
+        .iter()
+        .filter(|action| action.action.task_ids().contains(&task_id))
+        .map(|action| action.id)
+        .collect();
+
+    if !ids.is_empty() {
+        anyhow::bail!(
+            "task (#{}) was changed again by action(s) {}, revert them first",
+            task_id,
+            format_ids(&ids)
+        );
+    }
 
-    let category_repository = CategoryRepository::create(&conn);
-    let action_repository = ActionRepository::create(&conn);
+    Ok(())
+}
 
-    let task_ids = category_repository.get_category_task_ids(category)?;
+/**
+ * Used to check a deleted task can be created again: its id is free and the tasks it references
+ * still exist, `restored` lists the tasks created back along with it
+ */
+fn ensure_restorable(conn: &Connection, action: &ActionEnum, restored: &[i64]) -> Result<()> {
+    let task_repository = TaskRepository::create(conn);
+
+    let ActionEnum::Task {
+        id,
+        parent_id,
+        dependencies,
+        ..
+    } = action
+    else {
+        return Ok(());
+    };
 
-    category_repository.batch_delete_category(category)?;
+    if task_repository.get_task(*id)?.is_some() {
+        anyhow::bail!("a task with id (#{}) exists again", id);
+    }
 
-    action_repository.create_action(
-        ActionEnum::BatchCategoryDelete {
-            task_ids,
-            category: category.to_string(),
-        },
-        &now.to_string(),
-    )?;
+    let mut referenced: Vec<i64> = parent_id.iter().copied().collect();
+    for (task_id, depends_on_id) in dependencies.iter().flatten() {
+        referenced.extend([*task_id, *depends_on_id]);
+    }
+
+    let mut missing = vec![];
+    for task_id in referenced {
+        if task_id != *id
+            && !restored.contains(&task_id)
+            && !missing.contains(&task_id)
+            && task_repository.get_task(task_id)?.is_none()
+        {
+            missing.push(task_id);
+        }
+    }
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "task (#{}) refers to task(s) {} deleted since",
+            id,
+            format_ids(&missing)
+        );
+    }
 
     Ok(())
 }
 
 /**
- * Used to query tasks
+ * Editable fields of a task snapshot, compared field by field when reverting an update
  */
-pub fn query_tasks(conn: &Connection, payload: QueryTaskPayload) -> Result<Vec<Task>> {
-    let task_repository = TaskRepository::create(&conn);
-    task_repository.query_tasks(payload)
+struct TaskFields<'a> {
+    title: &'a String,
+    info: &'a Option<String>,
+    deadline: &'a Option<String>,
+    status: &'a TaskStatusEnum,
+    priority: &'a TaskPriorityEnum,
+    recurrence: &'a Option<RecurrenceEnum>,
+    created_at: &'a String,
+}
+
+impl<'a> TaskFields<'a> {
+    /**
+     * Used to read the fields of a task action
+     */
+    fn from_action(action: &'a ActionEnum) -> Option<Self> {
+        match action {
+            ActionEnum::Task {
+                title,
+                info,
+                deadline,
+                status,
+                priority,
+                recurrence,
+                created_at,
+                ..
+            } => Some(Self {
+                title,
+                info,
+                deadline,
+                status,
+                priority,
+                recurrence,
+                created_at,
+            }),
+            _ => None,
+        }
+    }
 }
 
 /**
- * Used to clean archived and actions to speedup database
+ * Used to find the snapshot of a task as it was before the action, when the action edited
+ * or deleted it
  */
-pub fn clean_database(conn: &Connection) -> Result<(i64, i64, i64)> {
-    let action_repository = ActionRepository::create(&conn);
-    let task_repository = TaskRepository::create(&conn);
-
-    let actions_deleted = action_repository.delete_all()?;
-
-    let tasks_deleted = task_repository.delete_archived()?;
+fn task_snapshot(action: &ActionEnum, task_id: i64) -> Option<&ActionEnum> {
+    match action {
+        ActionEnum::Task {
+            action_type: ActionTypeEnum::Update | ActionTypeEnum::Delete,
+            id,
+            ..
+        } if *id == task_id => Some(action),
+        ActionEnum::TaskTree { tasks, .. } => {
+            tasks.iter().find_map(|task| task_snapshot(task, task_id))
+        }
+        ActionEnum::TaskRecurrence { update, .. } => task_snapshot(update, task_id),
+        ActionEnum::Revert { action, .. } => task_snapshot(action, task_id),
+        ActionEnum::Compound { actions } => actions
+            .iter()
+            .find_map(|action| task_snapshot(action, task_id)),
+        _ => None,
+    }
+}
 
-    let tasks_updated = task_repository.archive_tasks()?;
+/**
+ * Used to pick the value a field takes back when reverting an update: `before` and `after`
+ * surround the update and `current` is the value now. A field changed again since is
+ * recorded as a conflict instead.
+ */
+fn merge_field<T: PartialEq + Clone>(
+    name: &str,
+    before: &T,
+    after: &T,
+    current: &T,
+    conflicts: &mut Vec<String>,
+) -> Option<T> {
+    if before == after || current == before {
+        return None;
+    }
+    if current != after {
+        conflicts.push(name.to_string());
+        return None;
+    }
 
-    Ok((actions_deleted, tasks_deleted, tasks_updated))
+    Some(before.clone())
 }
 
-#[cfg(test)]
-mod tests {
-    use anyhow::Result;
-    use rusqlite::Connection;
+/**
+ * Used to revert a past task update field by field, keeping the fields changed by other updates
+ */
+fn revert_task_update(
+    conn: &Connection,
+    action: &ActionEnum,
+    later: &[Action],
+) -> Result<ActionEnum> {
+    let now = get_now();
 
-    use crate::{
-        models::{setup_database, ActionEnum, ActionTypeEnum, AddTask, TaskStatusEnum, UpdateTask},
-        repositories::{add_category_to_task, category_repository::CategoryRepository, edit_task},
-        utils::date_parser,
+    let task_repository = TaskRepository::create(conn);
+
+    let ActionEnum::Task { id, .. } = action else {
+        anyhow::bail!("not a task update");
+    };
+    let task = task_repository
+        .get_task(*id)?
+        .ok_or_else(|| anyhow::anyhow!("task (#{}) was deleted since", id))?;
+
+    // the task as the update left it is the snapshot taken by the next change, or the task now
+    let current = task_action(ActionTypeEnum::Update, &task, None, None);
+    let after = later
+        .iter()
+        .find_map(|later| task_snapshot(&later.action, *id))
+        .unwrap_or(&current);
+
+    let (before, after, current) = match (
+        TaskFields::from_action(action),
+        TaskFields::from_action(after),
+        TaskFields::from_action(&current),
+    ) {
+        (Some(before), Some(after), Some(current)) => (before, after, current),
+        _ => anyhow::bail!("not a task update"),
     };
 
-    use super::{
-        action_repository::ActionRepository, add_task, batch_delete_category,
-        batch_rename_category, delete_task, get_now, remove_task_category, rename_task_category,
-        task_repository::TaskRepository, undo_redo_operation,
+    let mut conflicts = vec![];
+    let new_task = UpdateTask {
+        title: merge_field(
+            "title",
+            before.title,
+            after.title,
+            current.title,
+            &mut conflicts,
+        ),
+        info: merge_field(
+            "info",
+            before.info,
+            after.info,
+            current.info,
+            &mut conflicts,
+        ),
+        deadline: merge_field(
+            "deadline",
+            before.deadline,
+            after.deadline,
+            current.deadline,
+            &mut conflicts,
+        )
+        .map(|deadline| deadline.map(|v| DeadlineEnum::from_str(&v)).transpose())
+        .transpose()?,
+        status: merge_field(
+            "status",
+            before.status,
+            after.status,
+            current.status,
+            &mut conflicts,
+        ),
+        priority: merge_field(
+            "priority",
+            before.priority,
+            after.priority,
+            current.priority,
+            &mut conflicts,
+        ),
+        recurrence: merge_field(
+            "recurrence",
+            before.recurrence,
+            after.recurrence,
+            current.recurrence,
+            &mut conflicts,
+        ),
+        created_at: merge_field(
+            "created_at",
+            before.created_at,
+            after.created_at,
+            current.created_at,
+            &mut conflicts,
+        )
+        .map(|created_at| created_at_parser(&created_at))
+        .transpose()?,
     };
 
-    #[test]
-    fn test_crud_task() -> Result<()> {
-        let conn = Connection::open_in_memory()?;
-        setup_database(&conn)?;
+    if !conflicts.is_empty() {
+        anyhow::bail!(
+            "task (#{}) was edited again since, conflicting fields: {}",
+            id,
+            conflicts.join(", ")
+        );
+    }
 
-        let now = get_now();
-        let task_repository = TaskRepository::create(&conn);
-        let action_repository = ActionRepository::create(&conn);
-        let category_repository = CategoryRepository::create(&conn);
+    let changed = new_task.title.is_some()
+        || new_task.info.is_some()
+        || new_task.deadline.is_some()
+        || new_task.status.is_some()
+        || new_task.priority.is_some()
+        || new_task.recurrence.is_some()
+        || new_task.created_at.is_some();
+    if !changed {
+        anyhow::bail!(
+            "task (#{}) already has the values from before the action",
+            id
+        );
+    }
 
-        // test create
-        let task = {
-            let task = AddTask {
-                title: "Demo Task".into(),
-                info: Some("Some info".into()),
-                deadline: Some(date_parser("2024-01-01")?),
-                categories: Some(vec!["one".into(), "two".into()]),
-                status: TaskStatusEnum::Undone,
-                created_at: now,
-            };
-            add_task(&conn, task)?;
+    task_repository.update_task(*id, new_task, &now.to_string())?;
 
-            let task = task_repository.get_task(1)?.unwrap();
-            assert_eq!("Demo Task", &task.title);
-            assert_eq!(&TaskStatusEnum::Undone, &task.status);
+    Ok(task_action(ActionTypeEnum::Update, &task, None, None))
+}
 
-            let categories = category_repository.fetch_task_categories(1)?;
-            assert!(categories.contains(&"one".to_string()));
-            assert!(categories.contains(&"two".to_string()));
+/**
+ * Used to build the timeline of a task, oldest first, from the applied actions that touched it.
+ * The values an update left are read from the next snapshot of the task, or from the task now.
+ */
+pub fn fetch_task_history(conn: &Connection, task_id: i64) -> Result<Vec<TaskEvent>> {
+    let task_repository = TaskRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
+
+    let task = task_repository.get_task(task_id)?;
+    let filter = ActionFilter {
+        task_id: Some(task_id),
+        restored: Some(false),
+        ..Default::default()
+    };
+    let actions = action_repository.fetch_actions(None, &filter)?;
+    if task.is_none() && actions.is_empty() {
+        anyhow::bail!("Task with id (#{}) not found!", task_id);
+    }
 
-            task
-        };
+    // walk back from now, the task before an action is the task after the previous one
+    let mut after = task.map(|task| task_action(ActionTypeEnum::Update, &task, None, None));
+    let mut history = vec![];
+    for action in actions {
+        let mut events = vec![];
+        task_events(&action.action, task_id, after.as_ref(), &mut events);
+        for (event, changes) in events.into_iter().rev() {
+            history.push(TaskEvent {
+                action_id: action.id,
+                summary: action.action.to_string(),
+                created_at: action.created_at,
+                logged_at: action.logged_at,
+                event,
+                changes,
+            });
+        }
+        if let Some(snapshot) = task_snapshot(&action.action, task_id) {
+            after = Some(snapshot.clone());
+        }
+    }
+    history.reverse();
 
-        // test undo/redo create
-        {
-            let action = action_repository.get_last_unrestored_action()?;
-            match &action.action {
-                ActionEnum::Task {
-                    action_type,
-                    id: _,
-                    title,
-                    info: _,
-                    deadline: _,
-                    categories: _,
-                    status: _,
-                    updated_at: _,
-                    created_at: _,
-                } => {
-                    assert_eq!(&ActionTypeEnum::Create, action_type);
-                    assert_eq!("Demo Task", title);
-                }
-                _ => return Err(anyhow::anyhow!("Should not reach this point")),
-            };
-            assert_eq!(false, action.restored);
+    Ok(history)
+}
 
-            undo_redo_operation(&conn, action)?;
+/**
+ * Used to list the fields of a task snapshot shown in its history
+ */
+fn task_values(action: &ActionEnum) -> Vec<(&'static str, Option<String>)> {
+    let ActionEnum::Task {
+        title,
+        info,
+        deadline,
+        status,
+        priority,
+        parent_id,
+        recurrence,
+        created_at,
+        ..
+    } = action
+    else {
+        return vec![];
+    };
 
-            let task = task_repository.get_task(1)?;
-            if let None = task {
-                assert!(true)
-            } else {
-                assert!(false)
-            }
+    vec![
+        ("title", Some(title.clone())),
+        ("info", info.clone()),
+        ("deadline", deadline.clone()),
+        ("status", Some(status.to_string())),
+        ("priority", Some(priority.to_string())),
+        ("parent_id", parent_id.map(|v| v.to_string())),
+        ("recurrence", recurrence.as_ref().map(|v| v.to_string())),
+        ("created_at", Some(created_at.clone())),
+    ]
+}
 
-            let action = action_repository.get_first_restored_action()?;
-            match &action.action {
-                ActionEnum::Task {
-                    action_type,
-                    id: _,
-                    title,
-                    info: _,
-                    deadline: _,
-                    categories: _,
-                    status: _,
-                    updated_at: _,
-                    created_at: _,
-                } => {
-                    assert_eq!(&ActionTypeEnum::Delete, action_type);
-                    assert_eq!("Demo Task", title);
-                }
-                _ => return Err(anyhow::anyhow!("Should not reach this point")),
-            };
-            assert_eq!(true, action.restored);
+/**
+ * Used to collect what an action did to a task, in the order it was done.
+ * `after` is the task as the action left it, when it is known.
+ */
+fn task_events(
+    action: &ActionEnum,
+    task_id: i64,
+    after: Option<&ActionEnum>,
+    events: &mut Vec<(TaskEventEnum, Vec<FieldChange>)>,
+) {
+    let change = |field: &'static str, before: Option<String>, after: Option<String>| {
+        vec![FieldChange {
+            field,
+            before,
+            after,
+        }]
+    };
 
-            undo_redo_operation(&conn, action)?;
-            let action = action_repository.get_last_unrestored_action()?;
-            match &action.action {
-                ActionEnum::Task {
-                    action_type,
-                    id: _,
-                    title,
-                    info: _,
-                    deadline: _,
-                    categories: _,
-                    status: _,
-                    updated_at: _,
-                    created_at: _,
-                } => {
-                    assert_eq!(&ActionTypeEnum::Create, action_type);
-                    assert_eq!("Demo Task", title);
+    match action {
+        ActionEnum::Task {
+            action_type, id, ..
+        } if *id == task_id => match action_type {
+            ActionTypeEnum::Create => {
+                let mut changes: Vec<FieldChange> = task_values(action)
+                    .into_iter()
+                    .filter(|(_, value)| value.is_some())
+                    .map(|(field, value)| FieldChange {
+                        field,
+                        before: None,
+                        after: value,
+                    })
+                    .collect();
+                if let ActionEnum::Task {
+                    categories: Some(categories),
+                    ..
+                } = action
+                {
+                    if !categories.is_empty() {
+                        changes.extend(change("categories", None, Some(categories.join(", "))));
+                    }
                 }
-                _ => return Err(anyhow::anyhow!("Should not reach this point")),
+                events.push((TaskEventEnum::Created, changes));
+            }
+            ActionTypeEnum::Update => {
+                let after = after.map(task_values).unwrap_or_default();
+                let changes = task_values(action)
+                    .into_iter()
+                    .zip(after)
+                    .filter(|((_, before), (_, after))| before != after)
+                    .map(|((field, before), (_, after))| FieldChange {
+                        field,
+                        before,
+                        after,
+                    })
+                    .collect();
+                events.push((TaskEventEnum::Updated, changes));
+            }
+            ActionTypeEnum::Delete => events.push((TaskEventEnum::Deleted, vec![])),
+        },
+        ActionEnum::Task { .. } => {}
+        ActionEnum::TaskTree {
+            tasks, reparented, ..
+        } => {
+            for task in tasks {
+                task_events(task, task_id, after, events);
+            }
+            if let (true, Some(ActionEnum::Task { id, parent_id, .. })) =
+                (reparented.contains(&task_id), tasks.first())
+            {
+                events.push((
+                    TaskEventEnum::Reparented,
+                    change(
+                        "parent_id",
+                        Some(id.to_string()),
+                        parent_id.map(|v| v.to_string()),
+                    ),
+                ));
+            }
+        }
+        ActionEnum::TaskRecurrence { update, create } => {
+            task_events(update, task_id, after, events);
+            task_events(create, task_id, after, events);
+        }
+        ActionEnum::Category {
+            action_type,
+            category,
+            task_id: id,
+        } if *id == task_id => match action_type {
+            ActionTypeEnum::Delete => events.push((
+                TaskEventEnum::CategoryRemoved,
+                change("categories", Some(category.clone()), None),
+            )),
+            _ => events.push((
+                TaskEventEnum::CategoryAdded,
+                change("categories", None, Some(category.clone())),
+            )),
+        },
+        ActionEnum::Dependency {
+            action_type,
+            task_id: id,
+            depends_on_id,
+        } if *id == task_id || *depends_on_id == task_id => {
+            let (field, other) = match *id == task_id {
+                true => ("depends_on", depends_on_id),
+                false => ("dependents", id),
             };
-            assert_eq!(false, action.restored);
+            let other = Some(format!("#{}", other));
+            match action_type {
+                ActionTypeEnum::Delete => {
+                    events.push((TaskEventEnum::DependencyRemoved, change(field, other, None)))
+                }
+                _ => events.push((TaskEventEnum::DependencyAdded, change(field, None, other))),
+            }
+        }
+        ActionEnum::RenameTaskCategory {
+            old_category,
+            new_category,
+            task_id: id,
+        } if *id == task_id => events.push((
+            TaskEventEnum::CategoryRenamed,
+            change(
+                "categories",
+                Some(old_category.clone()),
+                Some(new_category.clone()),
+            ),
+        )),
+        ActionEnum::BatchCategoryDelete { task_ids, category } if task_ids.contains(&task_id) => {
+            events.push((
+                TaskEventEnum::CategoryRemoved,
+                change("categories", Some(category.clone()), None),
+            ))
         }
+        ActionEnum::BatchCategoryRename {
+            old_category,
+            new_category,
+            task_ids,
+        } if task_ids.contains(&task_id) => events.push((
+            TaskEventEnum::CategoryRenamed,
+            change(
+                "categories",
+                Some(old_category.clone()),
+                Some(new_category.clone()),
+            ),
+        )),
+        // a revert stores what it did like any other action
+        ActionEnum::Revert { action, .. } => task_events(action, task_id, after, events),
+        ActionEnum::Compound { actions } => {
+            for action in actions {
+                task_events(action, task_id, after, events);
+            }
+        }
+        _ => {}
+    }
+}
 
-        // test edit
-        {
-            edit_task(
-                &conn,
-                1,
-                task,
-                UpdateTask {
-                    title: Some("New Title".into()),
-                    info: None,
-                    deadline: None,
-                    status: None,
-                    created_at: None,
-                },
-            )?;
+/**
+ * Used to add a new category on the task
+ */
+pub fn add_category_to_task(conn: &Connection, task_id: i64, category: &str) -> Result<()> {
+    let now = get_now();
 
-            let task = task_repository.get_task(1)?.unwrap();
-            assert_eq!("New Title", &task.title);
-            assert_eq!(&TaskStatusEnum::Undone, &task.status);
-        };
+    let category_repository = CategoryRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
 
-        // test undo / redo edit
-        let task = {
-            let action = action_repository.get_last_unrestored_action()?;
-            match &action.action {
-                ActionEnum::Task {
-                    action_type,
-                    id: _,
-                    title,
-                    info: _,
-                    deadline: _,
-                    categories: _,
-                    status: _,
-                    updated_at: _,
-                    created_at: _,
-                } => {
-                    assert_eq!(&ActionTypeEnum::Update, action_type);
-                    assert_eq!("Demo Task", title);
+    category_repository.create_category(task_id, category)?;
+
+    action_repository.create_action(
+        ActionEnum::Category {
+            action_type: ActionTypeEnum::Create,
+            category: category.to_string(),
+            task_id,
+        },
+        &now.to_string(),
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Used to rename a category for a task
+ */
+pub fn rename_task_category(
+    conn: &Connection,
+    task_id: i64,
+    old_category: &str,
+    new_category: &str,
+) -> Result<()> {
+    let now = get_now();
+
+    let category_repository = CategoryRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
+
+    category_repository.rename_category(task_id, old_category, new_category)?;
+
+    action_repository.create_action(
+        ActionEnum::RenameTaskCategory {
+            old_category: old_category.to_string(),
+            new_category: new_category.to_string(),
+            task_id,
+        },
+        &now.to_string(),
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Used to remove a category from a task
+ */
+pub fn remove_task_category(conn: &Connection, task_id: i64, category: &str) -> Result<()> {
+    let now = get_now();
+
+    let category_repository = CategoryRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
+
+    category_repository.delete_category(task_id, category)?;
+
+    action_repository.create_action(
+        ActionEnum::Category {
+            action_type: ActionTypeEnum::Delete,
+            category: category.to_string(),
+            task_id,
+        },
+        &now.to_string(),
+    )?;
+
+    Ok(())
+}
+
+/**
+ * used to batch rename a category
+ */
+pub fn batch_rename_category(
+    conn: &Connection,
+    old_category: &str,
+    new_category: &str,
+) -> Result<()> {
+    let now = get_now();
+
+    let category_repository = CategoryRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
+
+    let task_ids = category_repository.get_category_task_ids(old_category)?;
+
+    category_repository.batch_rename_category(old_category, new_category)?;
+
+    action_repository.create_action(
+        ActionEnum::BatchCategoryRename {
+            old_category: old_category.to_string(),
+            new_category: new_category.to_string(),
+            task_ids,
+        },
+        &now.to_string(),
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Used to batch delete a category
+ */
+pub fn batch_delete_category(conn: &Connection, category: &str) -> Result<()> {
+    let now = get_now();
+
+    let category_repository = CategoryRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
+
+    let task_ids = category_repository.get_category_task_ids(category)?;
+
+    category_repository.batch_delete_category(category)?;
+
+    action_repository.create_action(
+        ActionEnum::BatchCategoryDelete {
+            task_ids,
+            category: category.to_string(),
+        },
+        &now.to_string(),
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Used to make a task depend on another task
+ */
+pub fn add_task_dependency(conn: &Connection, task_id: i64, depends_on_id: i64) -> Result<()> {
+    let now = get_now();
+
+    let task_repository = TaskRepository::create(conn);
+    let dependency_repository = DependencyRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
+
+    for id in [task_id, depends_on_id] {
+        if task_repository.get_task(id)?.is_none() {
+            return Err(anyhow::anyhow!("Task with id (#{}) not found!", id));
+        }
+    }
+
+    if dependency_repository.has_dependency(task_id, depends_on_id)? {
+        return Err(anyhow::anyhow!(
+            "Task (#{}) already depends on task (#{})",
+            task_id,
+            depends_on_id
+        ));
+    }
+
+    if dependency_repository.would_create_cycle(task_id, depends_on_id)? {
+        return Err(anyhow::anyhow!(
+            "Task (#{}) cannot depend on task (#{}), it would create a cycle",
+            task_id,
+            depends_on_id
+        ));
+    }
+
+    dependency_repository.create_dependency(task_id, depends_on_id)?;
+
+    action_repository.create_action(
+        ActionEnum::Dependency {
+            action_type: ActionTypeEnum::Create,
+            task_id,
+            depends_on_id,
+        },
+        &now.to_string(),
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Used to remove a dependency between two tasks
+ */
+pub fn remove_task_dependency(conn: &Connection, task_id: i64, depends_on_id: i64) -> Result<()> {
+    let now = get_now();
+
+    let dependency_repository = DependencyRepository::create(conn);
+    let action_repository = ActionRepository::create(conn);
+
+    dependency_repository.delete_dependency(task_id, depends_on_id)?;
+
+    action_repository.create_action(
+        ActionEnum::Dependency {
+            action_type: ActionTypeEnum::Delete,
+            task_id,
+            depends_on_id,
+        },
+        &now.to_string(),
+    )?;
+
+    Ok(())
+}
+
+/**
+ * Used to query a page of tasks
+ */
+pub fn query_tasks(conn: &Connection, payload: QueryTaskPayload) -> Result<TaskPage> {
+    let task_repository = TaskRepository::create(conn);
+    task_repository.query_tasks(payload)
+}
+
+/**
+ * Used to clean archived tasks, completed tasks and actions to speedup database, in this order.
+ * A dry run applies the steps in a savepoint and rolls them back, reporting what would change.
+ */
+pub fn clean_database(
+    conn: &Connection,
+    payload: &HousekeepingPayload,
+) -> Result<HousekeepingReport> {
+    if !payload.dry_run {
+        return run_housekeeping(conn, payload);
+    }
+
+    conn.execute_batch("SAVEPOINT housekeeping")?;
+    let result = run_housekeeping(conn, payload);
+    conn.execute_batch("ROLLBACK TO housekeeping; RELEASE housekeeping")?;
+
+    result
+}
+
+/**
+ * Used to apply the selected housekeeping steps, see `clean_database`
+ */
+fn run_housekeeping(
+    conn: &Connection,
+    payload: &HousekeepingPayload,
+) -> Result<HousekeepingReport> {
+    let now = get_now();
+
+    let action_repository = ActionRepository::create(conn);
+    let task_repository = TaskRepository::create(conn);
+    let category_repository = CategoryRepository::create(conn);
+    let dependency_repository = DependencyRepository::create(conn);
+
+    let mut report = HousekeepingReport::default();
+    // the tasks left in place that the purge or the archiving changed, as they were before
+    let mut changed: Vec<Task> = vec![];
+
+    if let Some(until) = payload.purge_archived_until {
+        for task in task_repository.fetch_stale_tasks(TaskStatusEnum::Archived, until)? {
+            // read it again, purging its parent may have moved it up
+            let task = task_repository
+                .get_task(task.id)?
+                .ok_or_else(|| anyhow::anyhow!("Task (#{}) no longer exists", task.id))?;
+            // the subtasks left move up to the parent of the purged task
+            let subtasks = task_repository.fetch_subtasks(task.id)?;
+            let subtask_ids: Vec<i64> = subtasks.iter().map(|subtask| subtask.id).collect();
+            task_repository.set_parent(&subtask_ids, task.parent_id)?;
+            for subtask in subtasks {
+                if !changed.iter().any(|task| task.id == subtask.id) {
+                    changed.push(subtask);
                 }
-                _ => return Err(anyhow::anyhow!("Should not reach this point")),
+            }
+            category_repository.delete_task_categories(task.id)?;
+            dependency_repository.delete_task_dependencies(task.id)?;
+            task_repository.delete_task(&task)?;
+            report.purged_task_ids.push(task.id);
+        }
+        changed.retain(|task| !report.purged_task_ids.contains(&task.id));
+        report.actions_deleted += action_repository.delete_task_actions(&report.purged_task_ids)?;
+    }
+
+    if let Some(until) = payload.archive_done_until {
+        let tasks = task_repository.fetch_stale_tasks(TaskStatusEnum::Done, until)?;
+        report.archived_task_ids = tasks.iter().map(|task| task.id).collect();
+        task_repository.archive_tasks(&report.archived_task_ids, &now.to_string())?;
+        changed.extend(tasks);
+    }
+
+    // a moved task keeps its new parent on undo, its former parent is gone
+    let actions: Vec<ActionEnum> = changed
+        .iter()
+        .map(|task| task_action(ActionTypeEnum::Update, task, None, None))
+        .collect();
+    if !actions.is_empty() {
+        action_repository.create_action(ActionEnum::Compound { actions }, &now.to_string())?;
+    }
+
+    if let Some(keep) = payload.keep_actions {
+        report.actions_deleted += action_repository.delete_all_but(keep)?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::Result;
+    use rusqlite::Connection;
+    use time::{
+        macros::{date, time},
+        Duration,
+    };
+
+    use crate::{
+        filter::parse_filter,
+        models::{
+            setup_database, Action, ActionEnum, ActionFilter, ActionTypeEnum, AddTask,
+            DeadlineEnum, DeleteModeEnum, FieldChange, HousekeepingPayload, HousekeepingReport,
+            QueryTaskPayload, RecurrenceEnum, SearchTaskPayload, Task, TaskEventEnum,
+            TaskPriorityEnum, TaskStatusEnum, UpdateTask, ViewQuery,
+        },
+        repositories::{
+            add_category_to_task, add_task_dependency, category_repository::CategoryRepository,
+            dependency_repository::DependencyRepository, edit_task, query_tasks,
+            remove_task_dependency, view_repository::ViewRepository,
+        },
+        search::fts_query,
+        utils::date_parser,
+    };
+
+    use super::{
+        action_repository::ActionRepository, add_task, batch_delete_category,
+        batch_rename_category, clean_database, compound_operation, delete_task, fetch_task_history,
+        get_now, remove_task_category, rename_task_category, revert_past_action,
+        task_repository::TaskRepository, undo_redo_operation,
+    };
+
+    #[test]
+    fn test_crud_task() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+        let category_repository = CategoryRepository::create(&conn);
+
+        // test create
+        let task = {
+            let task = AddTask {
+                title: "Demo Task".into(),
+                info: Some("Some info".into()),
+                deadline: Some(date_parser("2024-01-01")?.into()),
+                categories: Some(vec!["one".into(), "two".into()]),
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                created_at: now,
             };
-            assert_eq!(false, action.restored);
+            add_task(&conn, task)?;
 
-            undo_redo_operation(&conn, action)?;
             let task = task_repository.get_task(1)?.unwrap();
             assert_eq!("Demo Task", &task.title);
             assert_eq!(&TaskStatusEnum::Undone, &task.status);
 
-            let action = action_repository.get_last_unrestored_action()?;
+            let categories = category_repository.fetch_task_categories(1)?;
+            assert!(categories.contains(&"one".to_string()));
+            assert!(categories.contains(&"two".to_string()));
+
+            task
+        };
+
+        // test undo/redo create
+        {
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::Task {
                     action_type,
@@ -629,7 +1739,11 @@ mod tests {
                     info: _,
                     deadline: _,
                     categories: _,
+                    dependencies: _,
                     status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
                     updated_at: _,
                     created_at: _,
                 } => {
@@ -638,9 +1752,14 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
+
+            undo_redo_operation(&conn, action)?;
+
+            let task = task_repository.get_task(1)?;
+            assert!(task.is_none());
 
-            let action = action_repository.get_first_restored_action()?;
+            let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::Task {
                     action_type,
@@ -649,24 +1768,23 @@ mod tests {
                     info: _,
                     deadline: _,
                     categories: _,
+                    dependencies: _,
                     status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
                     updated_at: _,
                     created_at: _,
                 } => {
-                    assert_eq!(&ActionTypeEnum::Update, action_type);
-                    assert_eq!("New Title", title);
+                    assert_eq!(&ActionTypeEnum::Delete, action_type);
+                    assert_eq!("Demo Task", title);
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(true, action.restored);
+            assert!(action.restored);
 
             undo_redo_operation(&conn, action)?;
-
-            let task = task_repository.get_task(1)?.unwrap();
-            assert_eq!("New Title", &task.title);
-            assert_eq!(&TaskStatusEnum::Undone, &task.status);
-
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::Task {
                     action_type,
@@ -675,7 +1793,61 @@ mod tests {
                     info: _,
                     deadline: _,
                     categories: _,
+                    dependencies: _,
                     status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
+                    updated_at: _,
+                    created_at: _,
+                } => {
+                    assert_eq!(&ActionTypeEnum::Create, action_type);
+                    assert_eq!("Demo Task", title);
+                }
+                _ => return Err(anyhow::anyhow!("Should not reach this point")),
+            };
+            assert!(!action.restored);
+        }
+
+        // test edit
+        {
+            edit_task(
+                &conn,
+                1,
+                task,
+                UpdateTask {
+                    title: Some("New Title".into()),
+                    info: None,
+                    deadline: None,
+                    status: None,
+                    priority: Some(TaskPriorityEnum::Critical),
+                    recurrence: None,
+                    created_at: None,
+                },
+            )?;
+
+            let task = task_repository.get_task(1)?.unwrap();
+            assert_eq!("New Title", &task.title);
+            assert_eq!(&TaskStatusEnum::Undone, &task.status);
+            assert_eq!(&TaskPriorityEnum::Critical, &task.priority);
+        };
+
+        // test undo / redo edit
+        let task = {
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+            match &action.action {
+                ActionEnum::Task {
+                    action_type,
+                    id: _,
+                    title,
+                    info: _,
+                    deadline: _,
+                    categories: _,
+                    dependencies: _,
+                    status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
                     updated_at: _,
                     created_at: _,
                 } => {
@@ -684,24 +1856,104 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
+
+            undo_redo_operation(&conn, action)?;
+            let task = task_repository.get_task(1)?.unwrap();
+            assert_eq!("Demo Task", &task.title);
+            assert_eq!(&TaskStatusEnum::Undone, &task.status);
+            assert_eq!(&TaskPriorityEnum::Normal, &task.priority);
+
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+            match &action.action {
+                ActionEnum::Task {
+                    action_type,
+                    id: _,
+                    title,
+                    info: _,
+                    deadline: _,
+                    categories: _,
+                    dependencies: _,
+                    status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
+                    updated_at: _,
+                    created_at: _,
+                } => {
+                    assert_eq!(&ActionTypeEnum::Create, action_type);
+                    assert_eq!("Demo Task", title);
+                }
+                _ => return Err(anyhow::anyhow!("Should not reach this point")),
+            };
+            assert!(!action.restored);
+
+            let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
+            match &action.action {
+                ActionEnum::Task {
+                    action_type,
+                    id: _,
+                    title,
+                    info: _,
+                    deadline: _,
+                    categories: _,
+                    dependencies: _,
+                    status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
+                    updated_at: _,
+                    created_at: _,
+                } => {
+                    assert_eq!(&ActionTypeEnum::Update, action_type);
+                    assert_eq!("New Title", title);
+                }
+                _ => return Err(anyhow::anyhow!("Should not reach this point")),
+            };
+            assert!(action.restored);
+
+            undo_redo_operation(&conn, action)?;
+
+            let task = task_repository.get_task(1)?.unwrap();
+            assert_eq!("New Title", &task.title);
+            assert_eq!(&TaskStatusEnum::Undone, &task.status);
+
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+            match &action.action {
+                ActionEnum::Task {
+                    action_type,
+                    id: _,
+                    title,
+                    info: _,
+                    deadline: _,
+                    categories: _,
+                    dependencies: _,
+                    status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
+                    updated_at: _,
+                    created_at: _,
+                } => {
+                    assert_eq!(&ActionTypeEnum::Update, action_type);
+                    assert_eq!("Demo Task", title);
+                }
+                _ => return Err(anyhow::anyhow!("Should not reach this point")),
+            };
+            assert!(!action.restored);
             task
         };
 
         // test delete
         {
-            delete_task(&conn, &task)?;
+            delete_task(&conn, &task, DeleteModeEnum::Refuse)?;
             let task = task_repository.get_task(1)?;
-            if let None = task {
-                assert!(true)
-            } else {
-                assert!(false)
-            }
+            assert!(task.is_none());
         }
 
         // undo / redo delete
         {
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::Task {
                     action_type,
@@ -710,7 +1962,11 @@ mod tests {
                     info: _,
                     deadline: _,
                     categories: _,
+                    dependencies: _,
                     status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
                     updated_at: _,
                     created_at: _,
                 } => {
@@ -719,7 +1975,7 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
 
             undo_redo_operation(&conn, action)?;
 
@@ -727,7 +1983,7 @@ mod tests {
             assert_eq!("New Title", &task.title);
             assert_eq!(&TaskStatusEnum::Undone, &task.status);
 
-            let action = action_repository.get_first_restored_action()?;
+            let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::Task {
                     action_type,
@@ -736,7 +1992,11 @@ mod tests {
                     info: _,
                     deadline: _,
                     categories: _,
+                    dependencies: _,
                     status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
                     updated_at: _,
                     created_at: _,
                 } => {
@@ -745,11 +2005,11 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(true, action.restored);
+            assert!(action.restored);
 
             undo_redo_operation(&conn, action)?;
 
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::Task {
                     action_type,
@@ -758,7 +2018,11 @@ mod tests {
                     info: _,
                     deadline: _,
                     categories: _,
+                    dependencies: _,
                     status: _,
+                    priority: _,
+                    parent_id: _,
+                    recurrence: _,
                     updated_at: _,
                     created_at: _,
                 } => {
@@ -767,12 +2031,114 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
         }
 
         Ok(())
     }
 
+    #[test]
+    fn test_undo_redo_steps() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+
+        for title in ["One", "Two", "Three"] {
+            let task = AddTask {
+                title: title.into(),
+                info: None,
+                deadline: None,
+                categories: None,
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                created_at: now,
+            };
+            add_task(&conn, task)?;
+        }
+        let task = task_repository.get_task(1)?.unwrap();
+        edit_task(
+            &conn,
+            1,
+            task,
+            UpdateTask {
+                title: Some("New One".into()),
+                info: None,
+                deadline: None,
+                status: None,
+                priority: None,
+                recurrence: None,
+                created_at: None,
+            },
+        )?;
+
+        let revert = |actions: Vec<Action>| -> Result<Vec<i64>> {
+            let mut ids = vec![];
+            for action in actions {
+                ids.push(action.id);
+                undo_redo_operation(&conn, action)?;
+            }
+            Ok(ids)
+        };
+        let titles = || -> Result<Vec<String>> {
+            let mut titles = vec![];
+            for id in 1..=3 {
+                if let Some(task) = task_repository.get_task(id)? {
+                    titles.push(task.title);
+                }
+            }
+            Ok(titles)
+        };
+
+        // test undo several steps, newest first
+        assert_eq!(
+            vec![4, 3],
+            revert(action_repository.fetch_undo_actions(2, None)?)?
+        );
+        assert_eq!(vec!["One", "Two"], titles()?);
+
+        // test undo down to an action
+        assert_eq!(
+            vec![2, 1],
+            revert(action_repository.fetch_undo_actions(1, Some(1))?)?
+        );
+        assert!(titles()?.is_empty());
+        assert!(action_repository.fetch_undo_actions(1, None).is_err());
+
+        // test the bounds of --to, an action already undone or missing is refused
+        assert!(action_repository.fetch_undo_actions(1, Some(2)).is_err());
+        assert!(action_repository.fetch_redo_actions(1, Some(9)).is_err());
+        assert!(action_repository.fetch_redo_actions(1, Some(0)).is_err());
+
+        // test redo up to an action, oldest first
+        assert_eq!(
+            vec![1, 2],
+            revert(action_repository.fetch_redo_actions(1, Some(2))?)?
+        );
+        assert_eq!(vec!["One", "Two"], titles()?);
+
+        // test redo stops partway when the history runs out
+        assert_eq!(
+            vec![3, 4],
+            revert(action_repository.fetch_redo_actions(10, None)?)?
+        );
+        assert_eq!(vec!["New One", "Two", "Three"], titles()?);
+        assert!(action_repository.fetch_redo_actions(1, None).is_err());
+
+        // test undo stops partway when the history runs out
+        assert_eq!(
+            vec![4, 3, 2, 1],
+            revert(action_repository.fetch_undo_actions(10, None)?)?
+        );
+        assert!(titles()?.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_category_operations() -> Result<()> {
         let conn = Connection::open_in_memory()?;
@@ -785,9 +2151,12 @@ mod tests {
         let task_one = AddTask {
             title: "Task One".into(),
             info: Some("Some info".into()),
-            deadline: Some(date_parser("2024-01-01")?),
+            deadline: Some(date_parser("2024-01-01")?.into()),
             categories: Some(vec!["one".into(), "two".into()]),
             status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
             created_at: now,
         };
         let task_one = add_task(&conn, task_one)?;
@@ -795,9 +2164,12 @@ mod tests {
         let task_two = AddTask {
             title: "Task Two".into(),
             info: Some("Some info".into()),
-            deadline: Some(date_parser("2024-01-01")?),
+            deadline: Some(date_parser("2024-01-01")?.into()),
             categories: Some(vec!["two".into(), "three".into()]),
             status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
             created_at: now,
         };
         let task_two = add_task(&conn, task_two)?;
@@ -815,7 +2187,7 @@ mod tests {
 
         // undo redo operation
         {
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::Category {
                     action_type,
@@ -827,7 +2199,7 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
 
             undo_redo_operation(&conn, action)?;
 
@@ -835,7 +2207,7 @@ mod tests {
             assert_eq!(2, categories.len());
             assert!(!categories.contains(&"test".to_string()));
 
-            let action = action_repository.get_first_restored_action()?;
+            let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::Category {
                     action_type,
@@ -847,7 +2219,7 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(true, action.restored);
+            assert!(action.restored);
 
             undo_redo_operation(&conn, action)?;
 
@@ -855,7 +2227,7 @@ mod tests {
             assert_eq!(3, categories.len());
             assert!(categories.contains(&"test".to_string()));
 
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::Category {
                     action_type,
@@ -867,7 +2239,7 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
         }
 
         // test rename task
@@ -881,7 +2253,7 @@ mod tests {
 
         // undo redo operation
         {
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::RenameTaskCategory {
                     old_category,
@@ -893,7 +2265,7 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
 
             undo_redo_operation(&conn, action)?;
 
@@ -902,7 +2274,7 @@ mod tests {
             assert!(categories.contains(&"test".to_string()));
             assert!(!categories.contains(&"tost".to_string()));
 
-            let action = action_repository.get_first_restored_action()?;
+            let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::RenameTaskCategory {
                     old_category,
@@ -914,7 +2286,7 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(true, action.restored);
+            assert!(action.restored);
 
             undo_redo_operation(&conn, action)?;
 
@@ -923,7 +2295,7 @@ mod tests {
             assert!(!categories.contains(&"test".to_string()));
             assert!(categories.contains(&"tost".to_string()));
 
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::RenameTaskCategory {
                     old_category,
@@ -935,7 +2307,7 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
         }
 
         // test remove task
@@ -966,18 +2338,20 @@ mod tests {
 
         // undo redo operation
         {
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::BatchCategoryRename {
                     old_category,
                     new_category,
+                    task_ids,
                 } => {
                     assert_eq!("two", old_category);
                     assert_eq!("too", new_category);
+                    assert_eq!(&vec![task_one.id, task_two.id], task_ids);
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
 
             undo_redo_operation(&conn, action)?;
 
@@ -986,18 +2360,19 @@ mod tests {
             assert!(categories.contains(&"two".to_string()));
             assert!(!categories.contains(&"too".to_string()));
 
-            let action = action_repository.get_first_restored_action()?;
+            let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::BatchCategoryRename {
                     old_category,
                     new_category,
+                    ..
                 } => {
                     assert_eq!("too", old_category);
                     assert_eq!("two", new_category);
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(true, action.restored);
+            assert!(action.restored);
 
             undo_redo_operation(&conn, action)?;
 
@@ -1006,18 +2381,19 @@ mod tests {
             assert!(!categories.contains(&"two".to_string()));
             assert!(categories.contains(&"too".to_string()));
 
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::BatchCategoryRename {
                     old_category,
                     new_category,
+                    ..
                 } => {
                     assert_eq!("two", old_category);
                     assert_eq!("too", new_category);
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
         }
 
         // test batch delete category
@@ -1035,7 +2411,7 @@ mod tests {
 
         // undo redo operation
         {
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::BatchCategoryDelete { category, task_ids } => {
                     assert_eq!("too", category);
@@ -1044,7 +2420,7 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
 
             undo_redo_operation(&conn, action)?;
 
@@ -1058,7 +2434,7 @@ mod tests {
             assert!(categories.contains(&"three".to_string()));
             assert!(categories.contains(&"too".to_string()));
 
-            let action = action_repository.get_first_restored_action()?;
+            let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::BatchCategoryDelete { category, task_ids } => {
                     assert_eq!("too", category);
@@ -1067,7 +2443,7 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(true, action.restored);
+            assert!(action.restored);
 
             undo_redo_operation(&conn, action)?;
 
@@ -1079,7 +2455,7 @@ mod tests {
             assert_eq!(1, categories.len());
             assert!(categories.contains(&"three".to_string()));
 
-            let action = action_repository.get_last_unrestored_action()?;
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
             match &action.action {
                 ActionEnum::BatchCategoryDelete { category, task_ids } => {
                     assert_eq!("too", category);
@@ -1088,9 +2464,1536 @@ mod tests {
                 }
                 _ => return Err(anyhow::anyhow!("Should not reach this point")),
             };
-            assert_eq!(false, action.restored);
+            assert!(!action.restored);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subtasks() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+        let category_repository = CategoryRepository::create(&conn);
+
+        let new_task = |title: &str, parent_id: Option<i64>| AddTask {
+            title: title.into(),
+            info: None,
+            deadline: None,
+            categories: Some(vec![title.to_lowercase()]),
+            status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id,
+            recurrence: None,
+            created_at: now,
+        };
+
+        // root (#1) -> child (#2) -> grandchild (#3), child (#2) -> sibling (#4)
+        let root = add_task(&conn, new_task("Root", None))?;
+        let child = add_task(&conn, new_task("Child", Some(root.id)))?;
+        let grandchild = add_task(&conn, new_task("Grandchild", Some(child.id)))?;
+        let sibling = add_task(&conn, new_task("Sibling", Some(child.id)))?;
+
+        // test parent must exist
+        assert!(add_task(&conn, new_task("Orphan", Some(404))).is_err());
+
+        let ids: Vec<i64> = task_repository
+            .fetch_descendants(root.id)?
+            .iter()
+            .map(|task| task.id)
+            .collect();
+        assert_eq!(vec![child.id, grandchild.id, sibling.id], ids);
+
+        // test refuse
+        assert!(delete_task(&conn, &child, DeleteModeEnum::Refuse).is_err());
+        assert!(task_repository.get_task(child.id)?.is_some());
+
+        // test reparent and its undo / redo
+        {
+            delete_task(&conn, &child, DeleteModeEnum::Reparent)?;
+            assert!(task_repository.get_task(child.id)?.is_none());
+            let ids: Vec<i64> = task_repository
+                .fetch_subtasks(root.id)?
+                .iter()
+                .map(|task| task.id)
+                .collect();
+            assert_eq!(vec![grandchild.id, sibling.id], ids);
+
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+            undo_redo_operation(&conn, action)?;
+            assert_eq!(
+                Some(child.id),
+                task_repository.get_task(grandchild.id)?.unwrap().parent_id
+            );
+            assert_eq!(
+                Some(root.id),
+                task_repository.get_task(child.id)?.unwrap().parent_id
+            );
+            assert_eq!(
+                vec!["child"],
+                category_repository.fetch_task_categories(child.id)?
+            );
+
+            let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
+            undo_redo_operation(&conn, action)?;
+            assert!(task_repository.get_task(child.id)?.is_none());
+            assert_eq!(
+                Some(root.id),
+                task_repository.get_task(sibling.id)?.unwrap().parent_id
+            );
+
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+            undo_redo_operation(&conn, action)?;
+            assert_eq!(
+                Some(child.id),
+                task_repository.get_task(sibling.id)?.unwrap().parent_id
+            );
+        }
+
+        // test cascade and its undo / redo
+        {
+            let root = task_repository.get_task(root.id)?.unwrap();
+            delete_task(&conn, &root, DeleteModeEnum::Cascade)?;
+            for id in [root.id, child.id, grandchild.id, sibling.id] {
+                assert!(task_repository.get_task(id)?.is_none());
+                assert_eq!(0, category_repository.fetch_task_categories(id)?.len());
+            }
+
+            let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+            match &action.action {
+                ActionEnum::TaskTree {
+                    action_type,
+                    tasks,
+                    reparented,
+                } => {
+                    assert_eq!(&ActionTypeEnum::Delete, action_type);
+                    assert_eq!(4, tasks.len());
+                    assert_eq!(0, reparented.len());
+                }
+                _ => return Err(anyhow::anyhow!("Should not reach this point")),
+            };
+            undo_redo_operation(&conn, action)?;
+
+            let ids: Vec<i64> = task_repository
+                .fetch_descendants(root.id)?
+                .iter()
+                .map(|task| task.id)
+                .collect();
+            assert_eq!(vec![child.id, grandchild.id, sibling.id], ids);
+            assert_eq!(
+                vec!["grandchild"],
+                category_repository.fetch_task_categories(grandchild.id)?
+            );
+
+            let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
+            undo_redo_operation(&conn, action)?;
+            for id in [root.id, child.id, grandchild.id, sibling.id] {
+                assert!(task_repository.get_task(id)?.is_none());
+            }
         }
 
         Ok(())
     }
+
+    #[test]
+    fn test_dependencies() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+        let dependency_repository = DependencyRepository::create(&conn);
+
+        let new_task = |title: &str| AddTask {
+            title: title.into(),
+            info: None,
+            deadline: None,
+            categories: None,
+            status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
+            created_at: now,
+        };
+        let payload = QueryTaskPayload {
+            status: None,
+            priority: None,
+            any_categories: None,
+            all_categories: None,
+            exclude_categories: None,
+            text: None,
+            due_before: None,
+            due_after: None,
+            overdue: false,
+            no_deadline: false,
+            created_since: None,
+            updated_since: None,
+            blocked: false,
+            ready: false,
+            filter: None,
+            limit: 10,
+            offset: 0,
+            after: None,
+            sort: vec![],
+        };
+        let query_ids = |payload: QueryTaskPayload| -> Result<Vec<i64>> {
+            Ok(query_tasks(&conn, payload)?
+                .tasks
+                .iter()
+                .map(|task| task.id)
+                .collect())
+        };
+
+        let design = add_task(&conn, new_task("Design"))?;
+        let build = add_task(&conn, new_task("Build"))?;
+        let ship = add_task(&conn, new_task("Ship"))?;
+
+        // ship -> build -> design
+        add_task_dependency(&conn, build.id, design.id)?;
+        add_task_dependency(&conn, ship.id, build.id)?;
+
+        // test invalid edges
+        assert!(add_task_dependency(&conn, ship.id, build.id).is_err());
+        assert!(add_task_dependency(&conn, design.id, ship.id).is_err());
+        assert!(add_task_dependency(&conn, design.id, design.id).is_err());
+        assert!(add_task_dependency(&conn, design.id, 404).is_err());
+
+        // test blocked / ready filters
+        let blocked = QueryTaskPayload {
+            blocked: true,
+            ..payload.clone()
+        };
+        let ready = QueryTaskPayload {
+            ready: true,
+            ..payload.clone()
+        };
+        assert_eq!(vec![build.id, ship.id], query_ids(blocked.clone())?);
+        assert_eq!(vec![design.id], query_ids(ready.clone())?);
+
+        edit_task(
+            &conn,
+            design.id,
+            design.clone(),
+            UpdateTask {
+                title: None,
+                info: None,
+                deadline: None,
+                status: Some(TaskStatusEnum::Done),
+                priority: None,
+                recurrence: None,
+                created_at: None,
+            },
+        )?;
+        assert_eq!(vec![ship.id], query_ids(blocked.clone())?);
+        assert_eq!(vec![build.id], query_ids(ready.clone())?);
+
+        // test remove and its undo / redo
+        remove_task_dependency(&conn, ship.id, build.id)?;
+        assert!(remove_task_dependency(&conn, ship.id, build.id).is_err());
+        assert_eq!(0, query_ids(blocked.clone())?.len());
+
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        match &action.action {
+            ActionEnum::Dependency {
+                action_type,
+                task_id,
+                depends_on_id,
+            } => {
+                assert_eq!(&ActionTypeEnum::Delete, action_type);
+                assert_eq!(&ship.id, task_id);
+                assert_eq!(&build.id, depends_on_id);
+            }
+            _ => return Err(anyhow::anyhow!("Should not reach this point")),
+        };
+        undo_redo_operation(&conn, action)?;
+        assert!(dependency_repository.has_dependency(ship.id, build.id)?);
+
+        let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+        assert!(!dependency_repository.has_dependency(ship.id, build.id)?);
+
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+        assert!(dependency_repository.has_dependency(ship.id, build.id)?);
+
+        // test delete keeps the edges in the action log
+        let build = task_repository.get_task(build.id)?.unwrap();
+        delete_task(&conn, &build, DeleteModeEnum::Refuse)?;
+        assert_eq!(0, dependency_repository.fetch_dependents(design.id)?.len());
+        assert_eq!(0, dependency_repository.fetch_dependencies(ship.id)?.len());
+
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+        assert_eq!(
+            vec![(build.id, design.id), (ship.id, build.id)],
+            dependency_repository.fetch_task_edges(build.id)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recurring_task() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+        let category_repository = CategoryRepository::create(&conn);
+
+        let complete = |task: Task| {
+            edit_task(
+                &conn,
+                task.id,
+                task,
+                UpdateTask {
+                    title: None,
+                    info: None,
+                    deadline: None,
+                    status: Some(TaskStatusEnum::Done),
+                    priority: None,
+                    recurrence: None,
+                    created_at: None,
+                },
+            )
+        };
+
+        let task = add_task(
+            &conn,
+            AddTask {
+                title: "Water plants".into(),
+                info: Some("Balcony".into()),
+                deadline: Some(DeadlineEnum::DateTime(
+                    (now - Duration::days(10))
+                        .with_time(time!(07:30))
+                        .assume_utc(),
+                )),
+                categories: Some(vec!["home".into()]),
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::High,
+                parent_id: None,
+                recurrence: Some(RecurrenceEnum::EveryDays { days: 3 }),
+                created_at: now,
+            },
+        )?;
+
+        // test completing spawns the next instance after today
+        let (done, next) = complete(task.clone())?;
+        let next = next.expect("Next instance should be created");
+        assert_eq!(TaskStatusEnum::Done, done.status);
+        assert_eq!(None, done.recurrence);
+        assert_eq!(TaskStatusEnum::Undone, next.status);
+        assert_eq!(task.title, next.title);
+        assert_eq!(task.info, next.info);
+        assert_eq!(task.priority, next.priority);
+        assert_eq!(task.recurrence, next.recurrence);
+        // test the time of day is kept
+        assert_eq!(
+            Some(DeadlineEnum::DateTime(
+                (now + Duration::days(2))
+                    .with_time(time!(07:30))
+                    .assume_utc()
+            )),
+            next.deadline
+        );
+        assert_eq!(
+            vec!["home".to_string()],
+            category_repository.fetch_task_categories(next.id)?
+        );
+
+        // test completing an already done task does not spawn
+        assert!(complete(done.clone())?.1.is_none());
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+
+        // test undo removes the instance and restores the rule
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        match &action.action {
+            ActionEnum::TaskRecurrence { update, create } => {
+                assert!(matches!(
+                    **update,
+                    ActionEnum::Task {
+                        action_type: ActionTypeEnum::Update,
+                        ..
+                    }
+                ));
+                assert!(matches!(
+                    **create,
+                    ActionEnum::Task {
+                        action_type: ActionTypeEnum::Create,
+                        ..
+                    }
+                ));
+            }
+            _ => return Err(anyhow::anyhow!("Should not reach this point")),
+        };
+        undo_redo_operation(&conn, action)?;
+        assert!(task_repository.get_task(next.id)?.is_none());
+        assert_eq!(0, category_repository.fetch_task_categories(next.id)?.len());
+        let restored = task_repository.get_task(task.id)?.unwrap();
+        assert_eq!(TaskStatusEnum::Undone, restored.status);
+        assert_eq!(task.recurrence, restored.recurrence);
+
+        // test redo brings them back
+        let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+        assert_eq!(Some(next.clone()), task_repository.get_task(next.id)?);
+        assert_eq!(
+            vec!["home".to_string()],
+            category_repository.fetch_task_categories(next.id)?
+        );
+        let redone = task_repository.get_task(task.id)?.unwrap();
+        assert_eq!(TaskStatusEnum::Done, redone.status);
+        assert_eq!(None, redone.recurrence);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_revert_past_action() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+        let category_repository = CategoryRepository::create(&conn);
+
+        let new_task = |title: &str, categories: Option<Vec<String>>| AddTask {
+            title: title.into(),
+            info: None,
+            deadline: None,
+            categories,
+            status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
+            created_at: now,
+        };
+        let update = |id: i64, title: Option<&str>, priority: TaskPriorityEnum| -> Result<()> {
+            let task = task_repository.get_task(id)?.unwrap();
+            edit_task(
+                &conn,
+                id,
+                task,
+                UpdateTask {
+                    title: title.map(|title| title.to_string()),
+                    info: None,
+                    deadline: None,
+                    status: None,
+                    priority: Some(priority),
+                    recurrence: None,
+                    created_at: None,
+                },
+            )?;
+            Ok(())
+        };
+        let last_action_id =
+            || -> Result<i64> { Ok(action_repository.fetch_undo_actions(1, None)?[0].id) };
+
+        let alpha = add_task(&conn, new_task("Alpha", None))?;
+        let create_alpha = last_action_id()?;
+        let beta = add_task(&conn, new_task("Beta", Some(vec!["ops".into()])))?;
+
+        update(alpha.id, Some("Alpha 2"), TaskPriorityEnum::High)?;
+        let rename = last_action_id()?;
+        update(alpha.id, None, TaskPriorityEnum::Low)?;
+
+        // test a field edited again since is a conflict
+        let err = revert_past_action(&conn, rename).unwrap_err().to_string();
+        assert!(err.contains("conflicting fields: priority"));
+
+        // test the fields are reverted once they match the update again, the others are kept
+        update(alpha.id, None, TaskPriorityEnum::High)?;
+        revert_past_action(&conn, rename)?;
+        let task = task_repository.get_task(alpha.id)?.unwrap();
+        assert_eq!("Alpha", task.title);
+        assert_eq!(TaskPriorityEnum::Normal, task.priority);
+        assert!(revert_past_action(&conn, rename).is_err());
+
+        // test the revert is logged and undone like any action
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        assert!(
+            matches!(action.action, ActionEnum::Revert { action_id, .. } if action_id == rename)
+        );
+        undo_redo_operation(&conn, action)?;
+        assert_eq!(
+            "Alpha 2",
+            task_repository.get_task(alpha.id)?.unwrap().title
+        );
+        let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+        assert_eq!("Alpha", task_repository.get_task(alpha.id)?.unwrap().title);
+
+        // test a task changed since cannot be removed by reverting its creation
+        let err = revert_past_action(&conn, create_alpha)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("was changed again by action(s)"));
+
+        // test a batch delete gives the category back to the remaining tasks only
+        add_category_to_task(&conn, alpha.id, "ops")?;
+        batch_delete_category(&conn, "ops")?;
+        let batch_delete = last_action_id()?;
+        let beta = task_repository.get_task(beta.id)?.unwrap();
+        delete_task(&conn, &beta, DeleteModeEnum::Refuse)?;
+        let delete_beta = last_action_id()?;
+        revert_past_action(&conn, batch_delete)?;
+        assert_eq!(
+            vec!["ops".to_string()],
+            category_repository.fetch_task_categories(alpha.id)?
+        );
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+        assert!(category_repository
+            .fetch_task_categories(alpha.id)?
+            .is_empty());
+
+        // test a deleted task comes back with its id, without the category it lost before
+        revert_past_action(&conn, delete_beta)?;
+        assert_eq!(Some(beta.clone()), task_repository.get_task(beta.id)?);
+        assert!(category_repository
+            .fetch_task_categories(beta.id)?
+            .is_empty());
+
+        // test undone and unknown actions are refused
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        let id = action.id;
+        undo_redo_operation(&conn, action)?;
+        assert!(revert_past_action(&conn, id).is_err());
+        assert!(revert_past_action(&conn, 999).is_err());
+
+        // test an update of a task removed outside the log fails instead of panicking
+        update(alpha.id, Some("Alpha 3"), TaskPriorityEnum::Normal)?;
+        conn.execute("DELETE FROM tasks WHERE id = ?1", [alpha.id])?;
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        let err = undo_redo_operation(&conn, action).unwrap_err().to_string();
+        assert!(err.contains(&format!("Task (#{}) no longer exists", alpha.id)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compound_operation() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+        let category_repository = CategoryRepository::create(&conn);
+
+        let new_task = |title: &str| AddTask {
+            title: title.into(),
+            info: None,
+            deadline: None,
+            categories: None,
+            status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
+            created_at: now,
+        };
+
+        let first = add_task(&conn, new_task("First"))?;
+
+        // test the actions of the operation are logged as one, in the order they were applied
+        let (second, third) = compound_operation(&conn, |conn| {
+            let second = add_task(conn, new_task("Second"))?;
+            let third = add_task(conn, new_task("Third"))?;
+            add_category_to_task(conn, third.id, "ops")?;
+            add_task_dependency(conn, third.id, second.id)?;
+            Ok((second, third))
+        })?;
+        let actions = action_repository.fetch_actions(Some(10), &ActionFilter::default())?;
+        assert_eq!(2, actions.len());
+        match &actions[0].action {
+            ActionEnum::Compound { actions } => {
+                assert_eq!(4, actions.len());
+                assert!(matches!(actions[0], ActionEnum::Task { id, .. } if id == second.id));
+                assert!(matches!(actions[3], ActionEnum::Dependency { .. }));
+            }
+            _ => return Err(anyhow::anyhow!("Should not reach this point")),
+        }
+        assert_eq!(vec![second.id, third.id], actions[0].action.task_ids());
+
+        // test a single undo reverses all of them, the last applied first
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+        assert!(task_repository.get_task(second.id)?.is_none());
+        assert!(task_repository.get_task(third.id)?.is_none());
+        assert!(task_repository.get_task(first.id)?.is_some());
+
+        // test a single redo applies them again in order
+        let action = action_repository.fetch_redo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+        assert_eq!(Some(third.clone()), task_repository.get_task(third.id)?);
+        assert_eq!(
+            vec!["ops".to_string()],
+            category_repository.fetch_task_categories(third.id)?
+        );
+        assert_eq!(
+            vec![second.id],
+            DependencyRepository::create(&conn).fetch_dependencies(third.id)?
+        );
+
+        // test an operation logging a single action keeps it as is
+        compound_operation(&conn, |conn| add_category_to_task(conn, first.id, "home"))?;
+        assert!(matches!(
+            action_repository.fetch_actions(Some(1), &ActionFilter::default())?[0].action,
+            ActionEnum::Category { .. }
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_task_history() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+
+        let task = add_task(
+            &conn,
+            AddTask {
+                title: "Alpha".into(),
+                info: None,
+                deadline: None,
+                categories: Some(vec!["ops".into()]),
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                created_at: now,
+            },
+        )?;
+        let other = add_task(
+            &conn,
+            AddTask {
+                title: "Beta".into(),
+                info: None,
+                deadline: None,
+                categories: Some(vec!["ops".into()]),
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                created_at: now,
+            },
+        )?;
+
+        let update = |deadline: &str, priority: TaskPriorityEnum| -> Result<()> {
+            let old_task = task_repository.get_task(task.id)?.unwrap();
+            edit_task(
+                &conn,
+                task.id,
+                old_task,
+                UpdateTask {
+                    title: None,
+                    info: None,
+                    deadline: Some(Some(DeadlineEnum::from_str(deadline)?)),
+                    status: None,
+                    priority: Some(priority),
+                    recurrence: None,
+                    created_at: None,
+                },
+            )?;
+            Ok(())
+        };
+        update("2026-11-01", TaskPriorityEnum::Normal)?;
+        update("2026-11-10", TaskPriorityEnum::High)?;
+        batch_rename_category(&conn, "ops", "devops")?;
+        add_task_dependency(&conn, other.id, task.id)?;
+
+        // test undone actions are left out
+        add_category_to_task(&conn, task.id, "home")?;
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        undo_redo_operation(&conn, action)?;
+
+        delete_task(
+            &conn,
+            &task_repository.get_task(task.id)?.unwrap(),
+            DeleteModeEnum::Refuse,
+        )?;
+
+        // test the timeline of a deleted task, oldest first
+        let history = fetch_task_history(&conn, task.id)?;
+        let events: Vec<TaskEventEnum> = history.iter().map(|event| event.event).collect();
+        assert_eq!(
+            vec![
+                TaskEventEnum::Created,
+                TaskEventEnum::Updated,
+                TaskEventEnum::Updated,
+                TaskEventEnum::CategoryRenamed,
+                TaskEventEnum::DependencyAdded,
+                TaskEventEnum::Deleted,
+            ],
+            events
+        );
+        assert!(history.iter().all(|event| event.logged_at.is_some()));
+
+        let change = |field: &'static str, before: &str, after: &str| FieldChange {
+            field,
+            before: Some(before.into()),
+            after: Some(after.into()),
+        };
+        assert!(history[0].changes.contains(&FieldChange {
+            field: "categories",
+            before: None,
+            after: Some("ops".into()),
+        }));
+        assert_eq!(
+            vec![
+                change("deadline", "2026-11-01", "2026-11-10"),
+                change("priority", "normal", "high"),
+            ],
+            history[2].changes
+        );
+        assert_eq!(
+            vec![change("categories", "ops", "devops")],
+            history[3].changes
+        );
+        assert_eq!(
+            vec![FieldChange {
+                field: "dependents",
+                before: None,
+                after: Some(format!("#{}", other.id)),
+            }],
+            history[4].changes
+        );
+
+        // test the other task renamed by the same batch rename
+        let history = fetch_task_history(&conn, other.id)?;
+        assert_eq!(TaskEventEnum::CategoryRenamed, history[1].event);
+        assert!(fetch_task_history(&conn, 99).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_database() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let old = now - Duration::days(60);
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+        let category_repository = CategoryRepository::create(&conn);
+
+        let new_task = |title: &str, status: TaskStatusEnum, parent_id: Option<i64>| AddTask {
+            title: title.into(),
+            info: None,
+            deadline: None,
+            categories: Some(vec!["ops".into()]),
+            status,
+            priority: TaskPriorityEnum::Normal,
+            parent_id,
+            recurrence: None,
+            created_at: now,
+        };
+
+        let done_old = add_task(&conn, new_task("Done old", TaskStatusEnum::Done, None))?;
+        let done_new = add_task(&conn, new_task("Done new", TaskStatusEnum::Done, None))?;
+        let archived = add_task(&conn, new_task("Archived", TaskStatusEnum::Archived, None))?;
+        let subtask = add_task(
+            &conn,
+            new_task("Subtask", TaskStatusEnum::Undone, Some(archived.id)),
+        )?;
+        add_task_dependency(&conn, subtask.id, archived.id)?;
+        conn.execute(
+            "UPDATE tasks SET updated_at = ? WHERE id IN (?, ?)",
+            (old.to_string(), done_old.id, archived.id),
+        )?;
+
+        let payload = HousekeepingPayload {
+            purge_archived_until: Some(now - Duration::days(30)),
+            archive_done_until: Some(now - Duration::days(30)),
+            keep_actions: Some(1),
+            dry_run: true,
+        };
+        let expected = HousekeepingReport {
+            purged_task_ids: vec![archived.id],
+            archived_task_ids: vec![done_old.id],
+            // the creation of the archived task, then all but one once the changes are logged
+            actions_deleted: 5,
+        };
+        let count_actions = || -> Result<usize> {
+            Ok(action_repository
+                .fetch_actions(Some(10), &ActionFilter::default())?
+                .len())
+        };
+
+        // test a dry run reports the changes without applying them
+        assert_eq!(expected, clean_database(&conn, &payload)?);
+        assert!(task_repository.get_task(archived.id)?.is_some());
+        assert_eq!(
+            TaskStatusEnum::Done,
+            task_repository.get_task(done_old.id)?.unwrap().status
+        );
+        assert_eq!(5, count_actions()?);
+
+        // test only the tasks old enough are changed
+        let report = clean_database(
+            &conn,
+            &HousekeepingPayload {
+                dry_run: false,
+                ..payload
+            },
+        )?;
+        assert_eq!(expected, report);
+        assert!(task_repository.get_task(archived.id)?.is_none());
+        assert!(category_repository
+            .fetch_task_categories(archived.id)?
+            .is_empty());
+        assert_eq!(
+            Vec::<i64>::new(),
+            DependencyRepository::create(&conn).fetch_dependencies(subtask.id)?
+        );
+        assert_eq!(
+            None,
+            task_repository.get_task(subtask.id)?.unwrap().parent_id
+        );
+        assert_eq!(
+            TaskStatusEnum::Archived,
+            task_repository.get_task(done_old.id)?.unwrap().status
+        );
+        assert_eq!(
+            TaskStatusEnum::Done,
+            task_repository.get_task(done_new.id)?.unwrap().status
+        );
+        assert_eq!(1, count_actions()?);
+
+        // test the archived and moved tasks are logged as one action, the dependency on the
+        // purged task is kept for the remaining task
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        assert_eq!(vec![subtask.id, done_old.id], action.action.task_ids());
+        undo_redo_operation(&conn, action)?;
+        assert_eq!(
+            TaskStatusEnum::Done,
+            task_repository.get_task(done_old.id)?.unwrap().status
+        );
+        assert_eq!(
+            None,
+            task_repository.get_task(subtask.id)?.unwrap().parent_id
+        );
+
+        // test steps that are not selected do nothing
+        let report = clean_database(&conn, &HousekeepingPayload::default())?;
+        assert_eq!(HousekeepingReport::default(), report);
+        assert_eq!(1, count_actions()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_database_nested_archived() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+
+        let new_task = |title: &str, status: TaskStatusEnum, parent_id: Option<i64>| AddTask {
+            title: title.into(),
+            info: None,
+            deadline: None,
+            categories: None,
+            status,
+            priority: TaskPriorityEnum::Normal,
+            parent_id,
+            recurrence: None,
+            created_at: now,
+        };
+
+        let root = add_task(&conn, new_task("Root", TaskStatusEnum::Undone, None))?;
+        let parent = add_task(
+            &conn,
+            new_task("Parent", TaskStatusEnum::Archived, Some(root.id)),
+        )?;
+        let child = add_task(
+            &conn,
+            new_task("Child", TaskStatusEnum::Archived, Some(parent.id)),
+        )?;
+        let grandchild = add_task(
+            &conn,
+            new_task("Grandchild", TaskStatusEnum::Undone, Some(child.id)),
+        )?;
+        conn.execute(
+            "UPDATE tasks SET updated_at = ? WHERE id IN (?, ?)",
+            ((now - Duration::days(60)).to_string(), parent.id, child.id),
+        )?;
+
+        // test the live grandchild moves up to the closest remaining ancestor
+        let report = clean_database(
+            &conn,
+            &HousekeepingPayload {
+                purge_archived_until: Some(now - Duration::days(30)),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(vec![parent.id, child.id], report.purged_task_ids);
+        assert_eq!(
+            Some(root.id),
+            task_repository.get_task(grandchild.id)?.unwrap().parent_id
+        );
+
+        // test only the remaining grandchild is logged, as it was before the purge
+        let action = action_repository.fetch_undo_actions(1, None)?.remove(0);
+        assert!(matches!(
+            action.action,
+            ActionEnum::Compound { ref actions } if matches!(
+                actions.as_slice(),
+                [ActionEnum::Task { id, parent_id, .. }] if *id == grandchild.id && *parent_id == Some(child.id)
+            )
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_filters() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+
+        let new_task = |title: &str, categories: &[&str]| AddTask {
+            title: title.into(),
+            info: None,
+            deadline: None,
+            categories: match categories.is_empty() {
+                true => None,
+                false => Some(categories.iter().map(|v| v.to_string()).collect()),
+            },
+            status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
+            created_at: now,
+        };
+        let payload = QueryTaskPayload {
+            status: None,
+            priority: None,
+            any_categories: None,
+            all_categories: None,
+            exclude_categories: None,
+            text: None,
+            due_before: None,
+            due_after: None,
+            overdue: false,
+            no_deadline: false,
+            created_since: None,
+            updated_since: None,
+            blocked: false,
+            ready: false,
+            filter: None,
+            limit: 10,
+            offset: 0,
+            after: None,
+            sort: vec![],
+        };
+        let categories = |values: &[&str]| Some(values.iter().map(|v| v.to_string()).collect());
+        let query_ids = |payload: QueryTaskPayload| -> Result<Vec<i64>> {
+            Ok(query_tasks(&conn, payload)?
+                .tasks
+                .iter()
+                .map(|task| task.id)
+                .collect())
+        };
+
+        add_task(&conn, new_task("API", &["backend", "urgent"]))?;
+        add_task(&conn, new_task("Login page", &["frontend", "urgent"]))?;
+        add_task(&conn, new_task("Database", &["backend"]))?;
+        add_task(&conn, new_task("Groceries", &[]))?;
+        add_task(
+            &conn,
+            new_task("Release", &["backend", "frontend", "urgent"]),
+        )?;
+
+        // test any-of
+        assert_eq!(
+            vec![1, 3, 5],
+            query_ids(QueryTaskPayload {
+                any_categories: categories(&["backend"]),
+                ..payload.clone()
+            })?
+        );
+        assert_eq!(
+            vec![1, 2, 3, 5],
+            query_ids(QueryTaskPayload {
+                any_categories: categories(&["backend", "frontend"]),
+                ..payload.clone()
+            })?
+        );
+
+        // test all-of, repeated categories are counted once
+        assert_eq!(
+            vec![1, 5],
+            query_ids(QueryTaskPayload {
+                all_categories: categories(&["backend", "urgent", "backend"]),
+                ..payload.clone()
+            })?
+        );
+        assert_eq!(
+            Vec::<i64>::new(),
+            query_ids(QueryTaskPayload {
+                all_categories: categories(&["backend", "missing"]),
+                ..payload.clone()
+            })?
+        );
+
+        // test none-of keeps tasks without categories
+        assert_eq!(
+            vec![3, 4],
+            query_ids(QueryTaskPayload {
+                exclude_categories: categories(&["urgent"]),
+                ..payload.clone()
+            })?
+        );
+        assert_eq!(
+            vec![4],
+            query_ids(QueryTaskPayload {
+                exclude_categories: categories(&["backend", "frontend"]),
+                ..payload.clone()
+            })?
+        );
+
+        // test the filters combine
+        assert_eq!(
+            vec![1],
+            query_ids(QueryTaskPayload {
+                any_categories: categories(&["backend", "frontend"]),
+                all_categories: categories(&["urgent"]),
+                exclude_categories: categories(&["frontend"]),
+                ..payload.clone()
+            })?
+        );
+        // test the total counts every match, not only the page
+        let page = query_tasks(
+            &conn,
+            QueryTaskPayload {
+                all_categories: categories(&["backend", "urgent"]),
+                limit: 1,
+                ..payload
+            },
+        )?;
+        assert_eq!((1, 2), (page.tasks.len(), page.total));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_filters() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let new_task = |deadline: Option<&str>, status: TaskStatusEnum, created_at: &str| {
+            Ok::<_, anyhow::Error>(AddTask {
+                title: "Task".into(),
+                info: None,
+                deadline: deadline.map(DeadlineEnum::from_str).transpose()?,
+                categories: None,
+                status,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                created_at: date_parser(created_at)?,
+            })
+        };
+        let payload = QueryTaskPayload {
+            status: None,
+            priority: None,
+            any_categories: None,
+            all_categories: None,
+            exclude_categories: None,
+            text: None,
+            due_before: None,
+            due_after: None,
+            overdue: false,
+            no_deadline: false,
+            created_since: None,
+            updated_since: None,
+            blocked: false,
+            ready: false,
+            filter: None,
+            limit: 10,
+            offset: 0,
+            after: None,
+            sort: vec![],
+        };
+        let query_ids = |payload: QueryTaskPayload| -> Result<Vec<i64>> {
+            Ok(query_tasks(&conn, payload)?
+                .tasks
+                .iter()
+                .map(|task| task.id)
+                .collect())
+        };
+
+        let undone = TaskStatusEnum::Undone;
+        add_task(&conn, new_task(Some("2000-01-01"), undone, "2000-01-01")?)?;
+        add_task(
+            &conn,
+            new_task(Some("2000-01-01T10:00:00+05:00"), undone, "2000-01-01")?,
+        )?;
+        add_task(
+            &conn,
+            new_task(Some("2000-01-02"), TaskStatusEnum::Done, "2020-06-01")?,
+        )?;
+        add_task(&conn, new_task(Some("2999-01-01"), undone, "2020-06-01")?)?;
+        add_task(
+            &conn,
+            new_task(Some("2999-01-01T10:00:00Z"), undone, "2030-01-01")?,
+        )?;
+        add_task(&conn, new_task(None, undone, "2030-01-01")?)?;
+
+        // test deadline ranges, time deadlines count for the day they were set on
+        assert_eq!(
+            vec![1, 2, 3],
+            query_ids(QueryTaskPayload {
+                due_before: Some(date!(2500 - 01 - 01)),
+                ..payload.clone()
+            })?
+        );
+        assert_eq!(
+            vec![3, 4, 5],
+            query_ids(QueryTaskPayload {
+                due_after: Some(date!(2000 - 01 - 01)),
+                ..payload.clone()
+            })?
+        );
+        assert_eq!(
+            vec![1, 2],
+            query_ids(QueryTaskPayload {
+                due_after: Some(date!(1999 - 12 - 31)),
+                due_before: Some(date!(2000 - 01 - 02)),
+                ..payload.clone()
+            })?
+        );
+
+        // test overdue skips done tasks and upcoming deadlines
+        assert_eq!(
+            vec![1, 2],
+            query_ids(QueryTaskPayload {
+                overdue: true,
+                ..payload.clone()
+            })?
+        );
+
+        assert_eq!(
+            vec![6],
+            query_ids(QueryTaskPayload {
+                no_deadline: true,
+                ..payload.clone()
+            })?
+        );
+
+        // test creation and update dates are inclusive
+        assert_eq!(
+            vec![3, 4, 5, 6],
+            query_ids(QueryTaskPayload {
+                created_since: Some(date!(2020 - 06 - 01)),
+                ..payload.clone()
+            })?
+        );
+        assert_eq!(
+            vec![5, 6],
+            query_ids(QueryTaskPayload {
+                updated_since: Some(date!(2030 - 01 - 01)),
+                ..payload
+            })?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_where_filter() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let today = date!(2024 - 01 - 31);
+        let new_task = |title: &str, categories: &[&str], deadline: Option<&str>| {
+            Ok::<_, anyhow::Error>(AddTask {
+                title: title.into(),
+                info: None,
+                deadline: deadline.map(DeadlineEnum::from_str).transpose()?,
+                categories: Some(categories.iter().map(|v| v.to_string()).collect()),
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                created_at: today,
+            })
+        };
+        let payload = QueryTaskPayload {
+            status: None,
+            priority: None,
+            any_categories: None,
+            all_categories: None,
+            exclude_categories: None,
+            text: None,
+            due_before: None,
+            due_after: None,
+            overdue: false,
+            no_deadline: false,
+            created_since: None,
+            updated_since: None,
+            blocked: false,
+            ready: false,
+            filter: None,
+            limit: 10,
+            offset: 0,
+            after: None,
+            sort: vec![],
+        };
+        let query_ids = |filter: &str| -> Result<Vec<i64>> {
+            Ok(query_tasks(
+                &conn,
+                QueryTaskPayload {
+                    filter: Some(parse_filter(filter, today)?),
+                    ..payload.clone()
+                },
+            )?
+            .tasks
+            .iter()
+            .map(|task| task.id)
+            .collect())
+        };
+
+        add_task(
+            &conn,
+            new_task("Fix login bug", &["backend"], Some("2024-02-02"))?,
+        )?;
+        add_task(
+            &conn,
+            new_task("Rotate keys", &["ops"], Some("2024-03-01"))?,
+        )?;
+        add_task(
+            &conn,
+            new_task("Login page", &["frontend"], Some("2024-02-01"))?,
+        )?;
+        add_task(&conn, new_task("Backup", &["ops"], None)?)?;
+        let done = add_task(
+            &conn,
+            new_task("Old login bug", &["backend"], Some("2024-01-10"))?,
+        )?;
+        edit_task(
+            &conn,
+            done.id,
+            done.clone(),
+            UpdateTask {
+                title: None,
+                info: None,
+                deadline: None,
+                status: Some(TaskStatusEnum::Done),
+                priority: Some(TaskPriorityEnum::High),
+                recurrence: None,
+                created_at: None,
+            },
+        )?;
+
+        assert_eq!(
+            vec![1],
+            query_ids(
+                r#"status:undone and (cat:backend or cat:ops) and due<+7d and text:"login bug""#
+            )?
+        );
+        assert_eq!(
+            vec![1, 4],
+            query_ids("(cat:backend or cat:ops) status:undone not due:2024-03-01")?
+        );
+        assert_eq!(vec![1, 3, 5], query_ids("login")?);
+        assert_eq!(vec![3], query_ids("due:2024-02-01")?);
+        assert_eq!(vec![2, 4], query_ids("not due<=2024-02-02")?);
+        assert_eq!(vec![4], query_ids("due:none")?);
+        assert_eq!(
+            vec![5],
+            query_ids("priority>normal or is:overdue and status:done")?
+        );
+        assert_eq!(
+            vec![1, 2, 3, 4],
+            query_ids("priority<=normal created:today")?
+        );
+        assert_eq!(Vec::<i64>::new(), query_ids("is:blocked")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_search() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+        let task_repository = TaskRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+
+        let new_task = |title: &str, categories: &[&str]| AddTask {
+            title: title.into(),
+            info: None,
+            deadline: None,
+            categories: match categories.is_empty() {
+                true => None,
+                false => Some(categories.iter().map(|v| v.to_string()).collect()),
+            },
+            status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
+            created_at: now,
+        };
+        let search = |conn: &Connection, text: &str| -> Result<Vec<i64>> {
+            let payload = QueryTaskPayload {
+                status: None,
+                priority: None,
+                any_categories: None,
+                all_categories: None,
+                exclude_categories: None,
+                text: Some(text.into()),
+                due_before: None,
+                due_after: None,
+                overdue: false,
+                no_deadline: false,
+                created_since: None,
+                updated_since: None,
+                blocked: false,
+                ready: false,
+                filter: None,
+                limit: 10,
+                offset: 0,
+                after: None,
+                sort: vec![],
+            };
+
+            Ok(query_tasks(conn, payload)?
+                .tasks
+                .iter()
+                .map(|task| task.id)
+                .collect())
+        };
+        let undo = || {
+            undo_redo_operation(
+                &conn,
+                action_repository.fetch_undo_actions(1, None)?.remove(0),
+            )
+        };
+        let redo = || {
+            undo_redo_operation(
+                &conn,
+                action_repository.fetch_redo_actions(1, None)?.remove(0),
+            )
+        };
+
+        let api = add_task(&conn, new_task("API", &["backend"]))?;
+        add_task(&conn, new_task("Login page", &["frontend"]))?;
+        add_task(&conn, new_task("Groceries", &[]))?;
+        assert_eq!(vec![1], search(&conn, "backend")?);
+
+        // test single category changes
+        add_category_to_task(&conn, 2, "backend")?;
+        assert_eq!(vec![1, 2], search(&conn, "backend")?);
+
+        rename_task_category(&conn, 2, "backend", "ops")?;
+        assert_eq!(vec![1], search(&conn, "backend")?);
+        assert_eq!(vec![2], search(&conn, "ops")?);
+
+        remove_task_category(&conn, 2, "ops")?;
+        assert!(search(&conn, "ops")?.is_empty());
+        undo()?;
+        assert_eq!(vec![2], search(&conn, "ops")?);
+        redo()?;
+        assert!(search(&conn, "ops")?.is_empty());
+
+        // test batch changes
+        batch_rename_category(&conn, "backend", "server")?;
+        assert!(search(&conn, "backend")?.is_empty());
+        assert_eq!(vec![1], search(&conn, "server")?);
+        undo()?;
+        assert_eq!(vec![1], search(&conn, "backend")?);
+
+        batch_delete_category(&conn, "backend")?;
+        assert!(search(&conn, "backend")?.is_empty());
+        undo()?;
+        assert_eq!(vec![1], search(&conn, "backend")?);
+
+        delete_task(&conn, &api, DeleteModeEnum::Refuse)?;
+        assert!(search(&conn, "backend")?.is_empty());
+        undo()?;
+        assert_eq!(vec![1], search(&conn, "backend")?);
+
+        // test reindex rebuilds a broken index
+        conn.execute("DELETE FROM tasks_fts WHERE id = 2", ())?;
+        conn.execute("UPDATE tasks_fts SET categories = NULL", ())?;
+        assert!(search(&conn, "backend OR frontend")?.is_empty());
+        assert_eq!(3, task_repository.reindex()?);
+        assert_eq!(vec![1, 2], search(&conn, "backend OR frontend")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_special_characters() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let task_repository = TaskRepository::create(&conn);
+        let category_repository = CategoryRepository::create(&conn);
+        let view_repository = ViewRepository::create(&conn);
+        let action_repository = ActionRepository::create(&conn);
+
+        let texts = [
+            "it's",
+            "say \"hi\"",
+            "nul\0byte",
+            "NEAR(alpha beta) OR gamma* ^delta:epsilon",
+            "'); DROP TABLE tasks; --",
+            "100% _match_ \\ back\\slash",
+        ];
+        let undo = || {
+            undo_redo_operation(
+                &conn,
+                action_repository.fetch_undo_actions(1, None)?.remove(0),
+            )
+        };
+        let redo = || {
+            undo_redo_operation(
+                &conn,
+                action_repository.fetch_redo_actions(1, None)?.remove(0),
+            )
+        };
+
+        for text in texts {
+            let edited = format!("{} edited", text);
+            let renamed = format!("{} renamed", text);
+
+            // test create and read
+            let task = add_task(
+                &conn,
+                AddTask {
+                    title: text.into(),
+                    info: Some(text.into()),
+                    deadline: None,
+                    categories: Some(vec![text.into()]),
+                    status: TaskStatusEnum::Undone,
+                    priority: TaskPriorityEnum::Normal,
+                    parent_id: None,
+                    recurrence: None,
+                    created_at: get_now(),
+                },
+            )?;
+            let fetched = task_repository
+                .get_task(task.id)?
+                .expect("Task should exist");
+            assert_eq!(text, fetched.title);
+            assert_eq!(Some(text.to_string()), fetched.info);
+            assert_eq!(
+                vec![text.to_string()],
+                category_repository.fetch_task_categories(task.id)?
+            );
+
+            // test filters and full text search
+            let payload = QueryTaskPayload {
+                status: None,
+                priority: None,
+                any_categories: Some(vec![text.into()]),
+                all_categories: Some(vec![text.into()]),
+                exclude_categories: Some(vec![renamed.clone()]),
+                text: Some(text.into()),
+                due_before: None,
+                due_after: None,
+                overdue: false,
+                no_deadline: false,
+                created_since: None,
+                updated_since: None,
+                blocked: false,
+                ready: false,
+                filter: None,
+                limit: 10,
+                offset: 0,
+                after: None,
+                sort: vec![],
+            };
+            let ids: Vec<i64> = query_tasks(&conn, payload)?
+                .tasks
+                .iter()
+                .map(|task| task.id)
+                .collect();
+            assert_eq!(vec![task.id], ids);
+
+            let results = task_repository.search_tasks(SearchTaskPayload {
+                query: fts_query(text)?,
+                status: None,
+                limit: 10,
+                marks: ("'".into(), "\"".into()),
+            })?;
+            assert!(results.iter().any(|result| result.task.id == task.id));
+
+            // test updates and their undo and redo
+            edit_task(
+                &conn,
+                task.id,
+                fetched,
+                UpdateTask {
+                    title: Some(edited.clone()),
+                    info: Some(None),
+                    deadline: None,
+                    status: None,
+                    priority: None,
+                    recurrence: None,
+                    created_at: None,
+                },
+            )?;
+            undo()?;
+            assert_eq!(text, task_repository.get_task(task.id)?.unwrap().title);
+            redo()?;
+            assert_eq!(edited, task_repository.get_task(task.id)?.unwrap().title);
+
+            // test category operations and their undo
+            rename_task_category(&conn, task.id, text, &renamed)?;
+            batch_rename_category(&conn, &renamed, text)?;
+            remove_task_category(&conn, task.id, text)?;
+            undo()?;
+            add_category_to_task(&conn, task.id, &renamed)?;
+            batch_delete_category(&conn, text)?;
+            assert_eq!(
+                vec![renamed.clone()],
+                category_repository.fetch_task_categories(task.id)?
+            );
+            undo()?;
+            assert_eq!(2, category_repository.fetch_task_categories(task.id)?.len());
+
+            // test delete and its undo
+            let task = task_repository.get_task(task.id)?.unwrap();
+            delete_task(&conn, &task, DeleteModeEnum::Refuse)?;
+            undo()?;
+            assert_eq!(Some(task.clone()), task_repository.get_task(task.id)?);
+
+            // test saved views
+            let query = ViewQuery {
+                text: Some(text.to_string()),
+                limit: 10,
+                ..Default::default()
+            };
+            let view = view_repository.save_view(text, &query, get_now())?;
+            assert_eq!(query, view.query);
+            assert_eq!(Some(view), view_repository.get_view(text)?);
+            assert!(view_repository.delete_view(text)?);
+        }
+
+        assert_eq!(texts.len(), task_repository.reindex()? as usize);
+        assert_eq!(texts.len(), category_repository.all_categories()?.len() / 2);
+
+        Ok(())
+    }
 }
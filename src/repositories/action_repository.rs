@@ -1,8 +1,51 @@
 use anyhow::Result;
 use rusqlite::Connection;
 use sea_query::{Expr, Query, SqliteQueryBuilder};
-
-use crate::models::{Action, ActionEnum, ActionIden};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::{
+    models::{Action, ActionEnum, ActionFilter, ActionIden},
+    utils::get_local_now,
+};
+
+use super::bind;
+
+const ACTION_COLUMNS: [ActionIden; 5] = [
+    ActionIden::Id,
+    ActionIden::Action,
+    ActionIden::Restored,
+    ActionIden::CreatedAt,
+    ActionIden::LoggedAt,
+];
+
+fn action_from_row(row: &rusqlite::Row) -> rusqlite::Result<Action> {
+    let id: i64 = row.get(0)?;
+    let data: Vec<u8> = row.get(1)?;
+
+    Ok(Action {
+        id,
+        action: ActionEnum::from_blob(&data).map_err(|err| {
+            rusqlite::Error::FromSqlConversionFailure(
+                1,
+                rusqlite::types::Type::Blob,
+                format!("(#{}) - {:#}", id, err).into(),
+            )
+        })?,
+        restored: row.get(2)?,
+        created_at: row.get(3)?,
+        logged_at: row
+            .get::<_, Option<String>>(4)?
+            .map(|value| OffsetDateTime::parse(&value, &Rfc3339))
+            .transpose()
+            .map_err(|err| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    4,
+                    rusqlite::types::Type::Text,
+                    format!("(#{}) - {}", id, err).into(),
+                )
+            })?,
+    })
+}
 
 /**
  * Action database repository
@@ -20,29 +63,37 @@ impl<'a> ActionRepository<'a> {
     }
 
     /**
-     * Used to create a single (reversible) action record
+     * Used to create a single (reversible) action record, logged at the current moment
      */
     pub fn create_action(&self, action: ActionEnum, now: &str) -> Result<i64> {
+        let logged_at = get_local_now().replace_nanosecond(0)?.format(&Rfc3339)?;
+
         // create new action
-        let sql = Query::insert()
+        let (sql, values) = Query::insert()
             .into_table(ActionIden::Table)
             .columns([
                 ActionIden::Action,
                 ActionIden::Restored,
                 ActionIden::CreatedAt,
+                ActionIden::LoggedAt,
             ])
-            .values([action.to_blob().into(), false.into(), now.into()])?
-            .to_string(SqliteQueryBuilder);
-        self.conn.execute(&sql, ())?;
+            .values([
+                action.to_blob()?.into(),
+                false.into(),
+                now.into(),
+                logged_at.into(),
+            ])?
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
         let id = self.conn.last_insert_rowid();
 
         // delete restored actions
-        let sql = Query::delete()
+        let (sql, values) = Query::delete()
             .from_table(ActionIden::Table)
             .and_where(Expr::col(ActionIden::Restored).eq(true))
-            .to_string(SqliteQueryBuilder);
-        self.conn.execute(&sql, ())?;
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
         Ok(id)
     }
@@ -51,125 +102,338 @@ impl<'a> ActionRepository<'a> {
      * Used to create a single (reversible) action record
      */
     pub fn update_action(&self, id: i64, action: ActionEnum, restored: bool) -> Result<()> {
-        let sql = Query::update()
+        let (sql, values) = Query::update()
             .table(ActionIden::Table)
             .values([
-                (ActionIden::Action, action.to_blob().into()),
+                (ActionIden::Action, action.to_blob()?.into()),
                 (ActionIden::Restored, restored.into()),
             ])
             .and_where(Expr::col(ActionIden::Id).eq(id))
-            .to_string(SqliteQueryBuilder);
-        self.conn.execute(&sql, ())?;
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
         Ok(())
     }
 
     /**
-     * Used to get the last non restored Action log record
+     * Used to get the id of the last logged action, 0 when the log is empty
      */
-    pub fn get_last_unrestored_action(&self) -> Result<Action> {
-        let sql = Query::select()
+    pub fn last_action_id(&self) -> Result<i64> {
+        let (sql, values) = Query::select()
             .from(ActionIden::Table)
-            .columns([
-                ActionIden::Id,
-                ActionIden::Action,
-                ActionIden::Restored,
-                ActionIden::CreatedAt,
-            ])
-            .and_where(Expr::col(ActionIden::Restored).eq(false))
-            .order_by(ActionIden::Id, sea_query::Order::Desc)
-            .limit(1)
-            .to_string(SqliteQueryBuilder);
-
-        let action: Action = self.conn.query_row(&sql, (), |row| {
-            Ok(Action {
-                id: row.get(0)?,
-                action: row.get(1)?,
-                restored: row.get(2)?,
-                created_at: row.get(3)?,
-            })
-        })?;
+            .expr(Expr::col(ActionIden::Id).max())
+            .build(SqliteQueryBuilder);
+
+        let id: Option<i64> = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_row(bind(&values), |row| row.get(0))?;
 
-        Ok(action)
+        Ok(id.unwrap_or(0))
     }
 
     /**
-     * Used to get the last restored Action log record
+     * Used to replace the actions logged after `id` with a single compound action,
+     * nothing changes when there are less than two of them
      */
-    pub fn get_first_restored_action(&self) -> Result<Action> {
-        let sql = Query::select()
+    pub fn compound_actions_after(&self, id: i64, now: &str) -> Result<()> {
+        let (sql, values) = Query::select()
             .from(ActionIden::Table)
-            .columns([
-                ActionIden::Id,
-                ActionIden::Action,
-                ActionIden::Restored,
-                ActionIden::CreatedAt,
-            ])
-            .and_where(Expr::col(ActionIden::Restored).eq(true))
+            .columns(ACTION_COLUMNS)
+            .and_where(Expr::col(ActionIden::Id).gt(id))
             .order_by(ActionIden::Id, sea_query::Order::Asc)
-            .limit(1)
-            .to_string(SqliteQueryBuilder);
-
-        let action: Action = self.conn.query_row(&sql, (), |row| {
-            Ok(Action {
-                id: row.get(0)?,
-                action: row.get(1)?,
-                restored: row.get(2)?,
-                created_at: row.get(3)?,
-            })
-        })?;
+            .build(SqliteQueryBuilder);
 
-        Ok(action)
+        let actions = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), action_from_row)?
+            .collect::<Result<Vec<Action>, _>>()?;
+
+        if actions.len() < 2 {
+            return Ok(());
+        }
+
+        let (sql, values) = Query::delete()
+            .from_table(ActionIden::Table)
+            .and_where(Expr::col(ActionIden::Id).gt(id))
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        self.create_action(
+            ActionEnum::Compound {
+                actions: actions.into_iter().map(|action| action.action).collect(),
+            },
+            now,
+        )?;
+
+        Ok(())
     }
 
-    /** Used to fetch all actions */
-    pub fn fetch_actions(&self, limit: u64) -> Result<Vec<Action>> {
-        let sql = Query::select()
+    /**
+     * Used to get a single action by id
+     */
+    pub fn get_action(&self, id: i64) -> Result<Option<Action>> {
+        let (sql, values) = Query::select()
             .from(ActionIden::Table)
-            .columns([
-                ActionIden::Id,
-                ActionIden::Action,
-                ActionIden::Restored,
-                ActionIden::CreatedAt,
-            ])
-            .order_by(ActionIden::Id, sea_query::Order::Desc)
-            .limit(limit)
-            .to_string(SqliteQueryBuilder);
-
-        let mut stmt = self.conn.prepare(&sql)?;
-
-        let rows = stmt
-            .query_map((), |row| {
-                Ok(Action {
-                    id: row.get(0)?,
-                    action: row.get(1)?,
-                    restored: row.get(2)?,
-                    created_at: row.get(3)?,
-                })
-            })?
+            .columns(ACTION_COLUMNS)
+            .and_where(Expr::col(ActionIden::Id).eq(id))
+            .build(SqliteQueryBuilder);
+
+        let action = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_row(bind(&values), action_from_row);
+
+        match action {
+            Ok(action) => Ok(Some(action)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /**
+     * Used to get the actions to undo, newest first: the last `steps` of them, or all of them
+     * down to and including the action `to`
+     */
+    pub fn fetch_undo_actions(&self, steps: u64, to: Option<i64>) -> Result<Vec<Action>> {
+        self.fetch_steps(false, steps, to)
+    }
+
+    /**
+     * Used to get the undone actions to redo, oldest first: the first `steps` of them, or all of
+     * them up to and including the action `to`
+     */
+    pub fn fetch_redo_actions(&self, steps: u64, to: Option<i64>) -> Result<Vec<Action>> {
+        self.fetch_steps(true, steps, to)
+    }
+
+    /**
+     * Used to get the actions an undo (`restored` false) or a redo (`restored` true) goes through,
+     * in the order they have to be reverted
+     */
+    fn fetch_steps(&self, restored: bool, steps: u64, to: Option<i64>) -> Result<Vec<Action>> {
+        let operation = if restored { "redo" } else { "undo" };
+
+        let mut query = Query::select();
+        query
+            .from(ActionIden::Table)
+            .columns(ACTION_COLUMNS)
+            .and_where(Expr::col(ActionIden::Restored).eq(restored));
+
+        match (to, restored) {
+            (Some(to), false) => query
+                .and_where(Expr::col(ActionIden::Id).gte(to))
+                .order_by(ActionIden::Id, sea_query::Order::Desc),
+            (Some(to), true) => query
+                .and_where(Expr::col(ActionIden::Id).lte(to))
+                .order_by(ActionIden::Id, sea_query::Order::Asc),
+            (None, false) => query
+                .order_by(ActionIden::Id, sea_query::Order::Desc)
+                .limit(steps),
+            (None, true) => query
+                .order_by(ActionIden::Id, sea_query::Order::Asc)
+                .limit(steps),
+        };
+        let (sql, values) = query.build(SqliteQueryBuilder);
+
+        let actions = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), action_from_row)?
             .collect::<Result<Vec<Action>, _>>()?;
 
-        Ok(rows)
+        if let Some(to) = to {
+            if actions.last().map(|action| action.id) != Some(to) {
+                anyhow::bail!(
+                    "Action with id (#{}) not found among the actions to {}!",
+                    to,
+                    operation
+                );
+            }
+        }
+        if actions.is_empty() {
+            anyhow::bail!("Nothing to {}", operation);
+        }
+
+        Ok(actions)
     }
 
     /**
-     * Used to delete all actions
+     * Used to fetch the last actions matching the filter, newest first, all of them without a limit
      */
-    pub fn delete_all(&self) -> Result<i64> {
-        let sql = Query::select()
+    pub fn fetch_actions(&self, limit: Option<u64>, filter: &ActionFilter) -> Result<Vec<Action>> {
+        let mut query = Query::select();
+        query
             .from(ActionIden::Table)
-            .expr(Expr::col(ActionIden::Id).count())
-            .to_string(SqliteQueryBuilder);
+            .columns(ACTION_COLUMNS)
+            .order_by(ActionIden::Id, sea_query::Order::Desc);
 
-        let count = self.conn.query_row(&sql, (), |row| Ok(row.get(0)?))?;
+        if let Some(restored) = filter.restored {
+            query.and_where(Expr::col(ActionIden::Restored).eq(restored));
+        }
+        if let Some(since) = filter.since {
+            query.and_where(Expr::col(ActionIden::CreatedAt).gte(since));
+        }
+        if let Some(until) = filter.until {
+            query.and_where(Expr::col(ActionIden::CreatedAt).lte(until));
+        }
+        // the remaining filters need the decoded action
+        if let (Some(limit), None, None) = (limit, filter.task_id, filter.kind) {
+            query.limit(limit);
+        }
+        let (sql, values) = query.build(SqliteQueryBuilder);
+
+        let mut stmt = self.conn.prepare_cached(&sql)?;
+
+        let mut actions = vec![];
+        for action in stmt.query_map(bind(&values), action_from_row)? {
+            if limit.is_some_and(|limit| actions.len() as u64 >= limit) {
+                break;
+            }
+            let action = action?;
+            if filter.matches(&action.action) {
+                actions.push(action);
+            }
+        }
 
-        let sql = Query::delete()
+        Ok(actions)
+    }
+
+    /**
+     * Used to delete the actions log but its last `keep` actions
+     */
+    pub fn delete_all_but(&self, keep: u64) -> Result<i64> {
+        let (sql, values) = Query::delete()
             .from_table(ActionIden::Table)
-            .to_string(SqliteQueryBuilder);
+            .and_where(
+                Expr::col(ActionIden::Id).not_in_subquery(
+                    Query::select()
+                        .from(ActionIden::Table)
+                        .column(ActionIden::Id)
+                        .order_by(ActionIden::Id, sea_query::Order::Desc)
+                        .limit(keep)
+                        .to_owned(),
+                ),
+            )
+            .build(SqliteQueryBuilder);
+
+        let count = self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        Ok(count as i64)
+    }
 
-        self.conn.execute(&sql, ())?;
+    /**
+     * Used to delete the actions that changed any of the tasks, they could not be reverted
+     * once the tasks are gone. A compound or batch action only loses its part for the tasks,
+     * a dependency action is kept while its other task remains and actions that cannot be
+     * decoded are kept as they are.
+     */
+    pub fn delete_task_actions(&self, task_ids: &[i64]) -> Result<i64> {
+        if task_ids.is_empty() {
+            return Ok(0);
+        }
 
-        Ok(count)
+        let (sql, values) = Query::select()
+            .from(ActionIden::Table)
+            .columns([ActionIden::Id, ActionIden::Action, ActionIden::Restored])
+            .build(SqliteQueryBuilder);
+
+        let rows: Vec<(i64, Vec<u8>, bool)> = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<Result<_, _>>()?;
+
+        let mut ids = vec![];
+        for (id, data, restored) in rows {
+            let Ok(action) = ActionEnum::from_blob(&data) else {
+                continue;
+            };
+            if !touches(&action, task_ids) {
+                continue;
+            }
+
+            match without_tasks(action, task_ids) {
+                Some(action) => self.update_action(id, action, restored)?,
+                None => ids.push(id),
+            }
+        }
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let (sql, values) = Query::delete()
+            .from_table(ActionIden::Table)
+            .and_where(Expr::col(ActionIden::Id).is_in(ids.iter().copied()))
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        Ok(ids.len() as i64)
+    }
+}
+
+/**
+ * Used to check if an action changed any of the tasks
+ */
+fn touches(action: &ActionEnum, task_ids: &[i64]) -> bool {
+    action
+        .task_ids()
+        .iter()
+        .any(|task_id| task_ids.contains(task_id))
+}
+
+/**
+ * Used to remove the part of an action that changed any of the tasks, see `delete_task_actions`.
+ * Returns None when nothing is left to revert.
+ */
+fn without_tasks(action: ActionEnum, task_ids: &[i64]) -> Option<ActionEnum> {
+    match action {
+        ActionEnum::Compound { actions } => {
+            let actions: Vec<ActionEnum> = actions
+                .into_iter()
+                .filter_map(|action| without_tasks(action, task_ids))
+                .collect();
+            (!actions.is_empty()).then_some(ActionEnum::Compound { actions })
+        }
+        ActionEnum::BatchCategoryDelete {
+            task_ids: batch_ids,
+            category,
+        } => {
+            let batch_ids: Vec<i64> = batch_ids
+                .into_iter()
+                .filter(|task_id| !task_ids.contains(task_id))
+                .collect();
+            (!batch_ids.is_empty()).then_some(ActionEnum::BatchCategoryDelete {
+                task_ids: batch_ids,
+                category,
+            })
+        }
+        ActionEnum::BatchCategoryRename {
+            old_category,
+            new_category,
+            task_ids: batch_ids,
+        } => {
+            let batch_ids: Vec<i64> = batch_ids
+                .into_iter()
+                .filter(|task_id| !task_ids.contains(task_id))
+                .collect();
+            (!batch_ids.is_empty()).then_some(ActionEnum::BatchCategoryRename {
+                old_category,
+                new_category,
+                task_ids: batch_ids,
+            })
+        }
+        ActionEnum::Dependency {
+            task_id,
+            depends_on_id,
+            ..
+        } if task_ids.contains(&task_id) && task_ids.contains(&depends_on_id) => None,
+        action @ ActionEnum::Dependency { .. } => Some(action),
+        action if touches(&action, task_ids) => None,
+        action => Some(action),
     }
 }
 
@@ -179,9 +443,13 @@ mod tests {
     use rusqlite::Connection;
 
     use crate::{
-        models::{setup_database, Action, ActionEnum},
+        models::{
+            setup_database, Action, ActionEnum, ActionFilter, ActionKindEnum, ActionTypeEnum,
+        },
         repositories::get_now,
+        utils::get_local_now,
     };
+    use time::Duration;
 
     use super::ActionRepository;
 
@@ -197,22 +465,28 @@ mod tests {
         let action = ActionEnum::BatchCategoryRename {
             old_category: "test".into(),
             new_category: "tost".into(),
+            task_ids: vec![],
         };
 
         // test create
-        let id = {
+        let (id, logged_at) = {
             let id = repository.create_action(action.clone(), &now.to_string())?;
-            let actions = repository.fetch_actions(1)?;
+            let actions = repository.fetch_actions(Some(1), &ActionFilter::default())?;
+            let logged_at = actions[0].logged_at;
+            assert!(logged_at.is_some_and(
+                |logged_at| (get_local_now() - logged_at).abs() < Duration::minutes(1)
+            ));
             assert_eq!(
                 vec![Action {
                     id,
                     created_at: now,
                     action,
-                    restored: false
+                    restored: false,
+                    logged_at
                 }],
                 actions
             );
-            id
+            (id, logged_at)
         };
 
         // test update
@@ -220,15 +494,17 @@ mod tests {
             let action = ActionEnum::BatchCategoryRename {
                 old_category: "1".into(),
                 new_category: "2".into(),
+                task_ids: vec![],
             };
             repository.update_action(id, action.clone(), true)?;
-            let actions = repository.fetch_actions(1)?;
+            let actions = repository.fetch_actions(Some(1), &ActionFilter::default())?;
             assert_eq!(
                 vec![Action {
                     id,
                     created_at: now,
                     action,
-                    restored: true
+                    restored: true,
+                    logged_at
                 }],
                 actions
             );
@@ -238,7 +514,7 @@ mod tests {
     }
 
     #[test]
-    fn test_get_last_first_unrestored_restored_action() -> Result<()> {
+    fn test_fetch_undo_redo_actions() -> Result<()> {
         let conn = Connection::open_in_memory()?;
         setup_database(&conn)?;
 
@@ -246,13 +522,14 @@ mod tests {
 
         let now = get_now();
 
-        let old_texts = vec!["one", "two", "three", "four"];
-        let new_texts = vec!["1", "2", "3", "4"];
+        let old_texts = ["one", "two", "three", "four"];
+        let new_texts = ["1", "2", "3", "4"];
 
         for id in 0..4 {
             let action = ActionEnum::BatchCategoryRename {
                 old_category: old_texts.get(id).unwrap().to_string(),
                 new_category: new_texts.get(id).unwrap().to_string(),
+                task_ids: vec![],
             };
 
             repository.create_action(action, &now.to_string())?;
@@ -262,6 +539,7 @@ mod tests {
             let action = ActionEnum::BatchCategoryRename {
                 old_category: old_texts.get(id).unwrap().to_string(),
                 new_category: new_texts.get(id).unwrap().to_string(),
+                task_ids: vec![],
             };
 
             repository.update_action((id + 1) as i64, action, true)?;
@@ -269,7 +547,7 @@ mod tests {
 
         // test get last unrestored
         {
-            let last = repository.get_last_unrestored_action()?;
+            let last = repository.fetch_undo_actions(1, None)?.remove(0);
             assert_eq!(
                 Action {
                     id: 2,
@@ -277,8 +555,10 @@ mod tests {
                     action: ActionEnum::BatchCategoryRename {
                         old_category: old_texts.get(1).unwrap().to_string(),
                         new_category: new_texts.get(1).unwrap().to_string(),
+                        task_ids: vec![],
                     },
-                    restored: false
+                    restored: false,
+                    logged_at: last.logged_at
                 },
                 last
             );
@@ -286,7 +566,7 @@ mod tests {
 
         // test get first restored
         {
-            let first = repository.get_first_restored_action()?;
+            let first = repository.fetch_redo_actions(1, None)?.remove(0);
             assert_eq!(
                 Action {
                     id: 3,
@@ -294,13 +574,262 @@ mod tests {
                     action: ActionEnum::BatchCategoryRename {
                         old_category: old_texts.get(2).unwrap().to_string(),
                         new_category: new_texts.get(2).unwrap().to_string(),
+                        task_ids: vec![],
                     },
-                    restored: true
+                    restored: true,
+                    logged_at: first.logged_at
                 },
                 first
             );
         }
 
+        // test several steps and steps up to an action
+        {
+            let ids = |actions: Vec<Action>| -> Vec<i64> {
+                actions.into_iter().map(|action| action.id).collect()
+            };
+
+            assert_eq!(vec![2, 1], ids(repository.fetch_undo_actions(5, None)?));
+            assert_eq!(vec![2, 1], ids(repository.fetch_undo_actions(1, Some(1))?));
+            assert_eq!(vec![2], ids(repository.fetch_undo_actions(1, Some(2))?));
+            assert_eq!(vec![3, 4], ids(repository.fetch_redo_actions(5, None)?));
+            assert_eq!(vec![3, 4], ids(repository.fetch_redo_actions(1, Some(4))?));
+
+            // test actions in the other state or missing are refused
+            assert!(repository.fetch_undo_actions(1, Some(3)).is_err());
+            assert!(repository.fetch_undo_actions(1, Some(9)).is_err());
+            assert!(repository.fetch_redo_actions(1, Some(2)).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_filtered_actions() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let repository = ActionRepository::create(&conn);
+
+        let now = get_now();
+        let yesterday = now.previous_day().unwrap();
+
+        let actions = [
+            ActionEnum::Category {
+                action_type: ActionTypeEnum::Create,
+                category: "backend".into(),
+                task_id: 1,
+            },
+            ActionEnum::BatchCategoryRename {
+                old_category: "backend".into(),
+                new_category: "api".into(),
+                task_ids: vec![],
+            },
+            ActionEnum::Category {
+                action_type: ActionTypeEnum::Delete,
+                category: "api".into(),
+                task_id: 2,
+            },
+            ActionEnum::BatchCategoryDelete {
+                category: "api".into(),
+                task_ids: vec![1, 2],
+            },
+        ];
+        for (index, action) in actions.into_iter().enumerate() {
+            let created_at = match index {
+                0 => yesterday,
+                _ => now,
+            };
+            repository.create_action(action, &created_at.to_string())?;
+        }
+        repository.update_action(
+            4,
+            ActionEnum::BatchCategoryDelete {
+                category: "api".into(),
+                task_ids: vec![1, 2],
+            },
+            true,
+        )?;
+
+        let ids = |filter: ActionFilter, limit: u64| -> Result<Vec<i64>> {
+            Ok(repository
+                .fetch_actions(Some(limit), &filter)?
+                .into_iter()
+                .map(|action| action.id)
+                .collect())
+        };
+
+        // test filters on the decoded action
+        let task = |task_id| ActionFilter {
+            task_id: Some(task_id),
+            ..Default::default()
+        };
+        assert_eq!(vec![4, 1], ids(task(1), 10)?);
+        assert_eq!(vec![4], ids(task(1), 1)?);
+        assert_eq!(Vec::<i64>::new(), ids(task(1), 0)?);
+        assert_eq!(
+            vec![3, 1],
+            ids(
+                ActionFilter {
+                    kind: Some(ActionKindEnum::Category),
+                    ..Default::default()
+                },
+                10
+            )?
+        );
+
+        // test filters on the stored columns
+        assert_eq!(
+            vec![4],
+            ids(
+                ActionFilter {
+                    restored: Some(true),
+                    ..Default::default()
+                },
+                10
+            )?
+        );
+        assert_eq!(
+            vec![1],
+            ids(
+                ActionFilter {
+                    until: Some(yesterday),
+                    ..Default::default()
+                },
+                10
+            )?
+        );
+        assert_eq!(
+            vec![3],
+            ids(
+                ActionFilter {
+                    since: Some(now),
+                    restored: Some(false),
+                    kind: Some(ActionKindEnum::Category),
+                    ..Default::default()
+                },
+                10
+            )?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupt_action() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let repository = ActionRepository::create(&conn);
+
+        let now = get_now();
+
+        let action = ActionEnum::BatchCategoryRename {
+            old_category: "test".into(),
+            new_category: "tost".into(),
+            task_ids: vec![],
+        };
+        repository.create_action(action.clone(), &now.to_string())?;
+        let id = repository.create_action(action, &now.to_string())?;
+
+        conn.execute("UPDATE actions SET action = x'54410107' WHERE id = ?", [id])?;
+
+        // test a corrupt row is an error naming the action instead of a panic
+        let err = repository
+            .fetch_actions(Some(10), &ActionFilter::default())
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains(&format!("(#{})", id)));
+        assert!(repository.fetch_undo_actions(1, None).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_task_actions() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let repository = ActionRepository::create(&conn);
+
+        let now = get_now();
+
+        let category = |task_id: i64| ActionEnum::Category {
+            action_type: ActionTypeEnum::Create,
+            category: "one".into(),
+            task_id,
+        };
+
+        repository.create_action(category(1), &now.to_string())?;
+        repository.create_action(category(2), &now.to_string())?;
+        let mixed = repository.create_action(
+            ActionEnum::Compound {
+                actions: vec![category(1), category(2)],
+            },
+            &now.to_string(),
+        )?;
+        repository.create_action(
+            ActionEnum::Compound {
+                actions: vec![category(1), category(1)],
+            },
+            &now.to_string(),
+        )?;
+        repository.update_action(mixed, repository.get_action(mixed)?.unwrap().action, true)?;
+        conn.execute(
+            "INSERT INTO actions (action, restored, created_at) VALUES (X'FF', 0, ?1)",
+            [now.to_string()],
+        )?;
+
+        // test only the actions of the tasks are deleted, compounds keep the other actions
+        assert_eq!(2, repository.delete_task_actions(&[1])?);
+        let ids: Vec<i64> = conn
+            .prepare("SELECT id FROM actions ORDER BY id")?
+            .query_map((), |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(vec![2, 3, 5], ids);
+
+        let action = repository.get_action(mixed)?.unwrap();
+        assert_eq!(
+            ActionEnum::Compound {
+                actions: vec![category(2)],
+            },
+            action.action
+        );
+        assert!(action.restored);
+
+        assert_eq!(0, repository.delete_task_actions(&[1])?);
+
+        // test batch actions lose the tasks, dependencies are kept while one task remains
+        let batch = repository.create_action(
+            ActionEnum::BatchCategoryRename {
+                old_category: "one".into(),
+                new_category: "two".into(),
+                task_ids: vec![2, 3],
+            },
+            &now.to_string(),
+        )?;
+        let dependency = |task_id: i64, depends_on_id: i64| ActionEnum::Dependency {
+            action_type: ActionTypeEnum::Create,
+            task_id,
+            depends_on_id,
+        };
+        let kept = repository.create_action(dependency(2, 3), &now.to_string())?;
+        let deleted = repository.create_action(dependency(3, 4), &now.to_string())?;
+
+        assert_eq!(1, repository.delete_task_actions(&[3, 4])?);
+        assert_eq!(
+            ActionEnum::BatchCategoryRename {
+                old_category: "one".into(),
+                new_category: "two".into(),
+                task_ids: vec![2],
+            },
+            repository.get_action(batch)?.unwrap().action
+        );
+        assert_eq!(
+            dependency(2, 3),
+            repository.get_action(kept)?.unwrap().action
+        );
+        assert!(repository.get_action(deleted)?.is_none());
+
         Ok(())
     }
 }
@@ -1,8 +1,30 @@
 use anyhow::Result;
 use rusqlite::Connection;
-use sea_query::{Expr, Query, SqliteQueryBuilder};
+use sea_query::{Alias, Expr, Func, Query, SimpleExpr, SqliteQueryBuilder};
 
-use crate::models::TaskCategoryIden;
+use crate::models::{TaskCategoryIden, TaskFtsIden};
+
+use super::bind;
+
+/**
+ * Used to select the categories of a task separated by spaces, as stored in `tasks_fts`
+ */
+pub fn categories_text(task_id: SimpleExpr) -> SimpleExpr {
+    let sql = Query::select()
+        .expr(
+            Func::cust(Alias::new("group_concat"))
+                .arg(Expr::col((
+                    TaskCategoryIden::Table,
+                    TaskCategoryIden::Category,
+                )))
+                .arg(" "),
+        )
+        .from(TaskCategoryIden::Table)
+        .and_where(Expr::col((TaskCategoryIden::Table, TaskCategoryIden::TaskId)).eq(task_id))
+        .to_owned();
+
+    SimpleExpr::SubQuery(None, Box::new(sql.into_sub_query_statement()))
+}
 
 /**
  * Category database repository
@@ -23,13 +45,15 @@ impl<'a> CategoryRepository<'a> {
      * Used to create a category for a task
      */
     pub fn create_category(&self, task_id: i64, category: &str) -> Result<()> {
-        let sql = Query::insert()
+        let (sql, values) = Query::insert()
             .into_table(TaskCategoryIden::Table)
             .columns([TaskCategoryIden::TaskId, TaskCategoryIden::Category])
             .values([task_id.into(), category.into()])?
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
-        self.conn.execute(&sql, ())?;
+        self.index_categories(&[task_id])?;
 
         Ok(())
     }
@@ -42,7 +66,7 @@ impl<'a> CategoryRepository<'a> {
         task_id: i64,
         categories: &Vec<String>,
     ) -> Result<()> {
-        if categories.len() == 0 {
+        if categories.is_empty() {
             return Ok(());
         }
 
@@ -55,9 +79,11 @@ impl<'a> CategoryRepository<'a> {
             sql.values([task_id.into(), category.into()])?;
         }
 
-        let sql = sql.to_string(SqliteQueryBuilder);
+        let (sql, values) = sql.build(SqliteQueryBuilder);
 
-        self.conn.execute(&sql, ())?;
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        self.index_categories(&[task_id])?;
 
         Ok(())
     }
@@ -66,17 +92,17 @@ impl<'a> CategoryRepository<'a> {
      * Used to fetch all categories
      */
     pub fn all_categories(&self) -> Result<Vec<(String, i64)>> {
-        let sql = Query::select()
+        let (sql, values) = Query::select()
             .expr(Expr::col(TaskCategoryIden::TaskId).count())
             .column(TaskCategoryIden::Category)
             .from(TaskCategoryIden::Table)
             .group_by_col(TaskCategoryIden::Category)
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
 
         let records: Vec<_> = self
             .conn
-            .prepare(&sql)?
-            .query_map((), |row| {
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), |row| {
                 let count: i64 = row.get(0)?;
                 let category: String = row.get(1)?;
 
@@ -91,16 +117,16 @@ impl<'a> CategoryRepository<'a> {
      * Used to fetch all categories for a task
      */
     pub fn fetch_task_categories(&self, task_id: i64) -> Result<Vec<String>> {
-        let sql = Query::select()
+        let (sql, values) = Query::select()
             .from(TaskCategoryIden::Table)
             .column(TaskCategoryIden::Category)
             .and_where(Expr::col(TaskCategoryIden::TaskId).eq(task_id))
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
 
         let records = self
             .conn
-            .prepare(&sql)?
-            .query_map((), |row| {
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), |row| {
                 let category: String = row.get(0)?;
 
                 Ok(category)
@@ -114,17 +140,20 @@ impl<'a> CategoryRepository<'a> {
      * Used to fetch a category for a task
      */
     pub fn fetch_category(&self, task_id: i64, category: &str) -> Result<Option<String>> {
-        let sql = Query::select()
+        let (sql, values) = Query::select()
             .from(TaskCategoryIden::Table)
             .column(TaskCategoryIden::Category)
             .and_where(Expr::col(TaskCategoryIden::TaskId).eq(task_id))
             .and_where(Expr::col(TaskCategoryIden::Category).eq(category))
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
 
-        let category = self.conn.query_row(&sql, (), |row| {
-            let category: String = row.get(0)?;
-            Ok(category)
-        });
+        let category = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_row(bind(&values), |row| {
+                let category: String = row.get(0)?;
+                Ok(category)
+            });
 
         match category {
             Ok(category) => Ok(Some(category)),
@@ -139,17 +168,19 @@ impl<'a> CategoryRepository<'a> {
     pub fn delete_category(&self, task_id: i64, category: &str) -> Result<()> {
         let task = self.fetch_category(task_id, category)?;
 
-        if let None = task {
+        if task.is_none() {
             return Err(anyhow::anyhow!("Category not found (#{})", category));
         }
 
-        let sql = Query::delete()
+        let (sql, values) = Query::delete()
             .from_table(TaskCategoryIden::Table)
             .and_where(Expr::col(TaskCategoryIden::TaskId).eq(task_id))
             .and_where(Expr::col(TaskCategoryIden::Category).eq(category))
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
 
-        self.conn.execute(&sql, ())?;
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        self.index_categories(&[task_id])?;
 
         Ok(())
     }
@@ -165,18 +196,20 @@ impl<'a> CategoryRepository<'a> {
     ) -> Result<()> {
         let task = self.fetch_category(task_id, old_category)?;
 
-        if let None = task {
+        if task.is_none() {
             return Err(anyhow::anyhow!("Category not found (#{})", old_category));
         }
 
-        let sql = Query::update()
+        let (sql, values) = Query::update()
             .table(TaskCategoryIden::Table)
             .value(TaskCategoryIden::Category, new_category)
             .and_where(Expr::col(TaskCategoryIden::TaskId).eq(task_id))
             .and_where(Expr::col(TaskCategoryIden::Category).eq(old_category))
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
-        self.conn.execute(&sql, ())?;
+        self.index_categories(&[task_id])?;
 
         Ok(())
     }
@@ -194,9 +227,11 @@ impl<'a> CategoryRepository<'a> {
             sql.values([(*id).into(), category.into()])?;
         }
 
-        let sql = sql.to_string(SqliteQueryBuilder);
+        let (sql, values) = sql.build(SqliteQueryBuilder);
 
-        self.conn.execute(&sql, ())?;
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        self.index_categories(task_ids)?;
 
         Ok(())
     }
@@ -205,12 +240,38 @@ impl<'a> CategoryRepository<'a> {
      * Used to batch delete a category
      */
     pub fn batch_delete_category(&self, category: &str) -> Result<()> {
-        let sql = Query::delete()
+        let task_ids = self.get_category_task_ids(category)?;
+
+        let (sql, values) = Query::delete()
             .from_table(TaskCategoryIden::Table)
             .and_where(Expr::col(TaskCategoryIden::Category).eq(category))
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
-        self.conn.execute(&sql, ())?;
+        self.index_categories(&task_ids)?;
+
+        Ok(())
+    }
+
+    /**
+     * Used to refresh the categories of tasks in the full text index
+     */
+    fn index_categories(&self, task_ids: &[i64]) -> Result<()> {
+        if task_ids.is_empty() {
+            return Ok(());
+        }
+
+        let (sql, values) = Query::update()
+            .table(TaskFtsIden::Table)
+            .value(
+                TaskFtsIden::Categories,
+                categories_text(Expr::col((TaskFtsIden::Table, TaskFtsIden::Id)).into()),
+            )
+            .and_where(Expr::col(TaskFtsIden::Id).is_in(task_ids.iter().copied()))
+            .build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
         Ok(())
     }
@@ -219,16 +280,16 @@ impl<'a> CategoryRepository<'a> {
      * Used to fetch all task ids associated with the category
      */
     pub fn get_category_task_ids(&self, category: &str) -> Result<Vec<i64>> {
-        let sql = Query::select()
+        let (sql, values) = Query::select()
             .from(TaskCategoryIden::Table)
             .column(TaskCategoryIden::TaskId)
             .and_where(Expr::col(TaskCategoryIden::Category).eq(category))
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
 
         let result = self
             .conn
-            .prepare(&sql)?
-            .query_map((), |row| {
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), |row| {
                 let id: i64 = row.get(0)?;
                 Ok(id)
             })?
@@ -241,13 +302,17 @@ impl<'a> CategoryRepository<'a> {
      * Used to batch rename a category
      */
     pub fn batch_rename_category(&self, old_category: &str, new_category: &str) -> Result<()> {
-        let sql = Query::update()
+        let task_ids = self.get_category_task_ids(old_category)?;
+
+        let (sql, values) = Query::update()
             .table(TaskCategoryIden::Table)
             .value(TaskCategoryIden::Category, new_category)
             .and_where(Expr::col(TaskCategoryIden::Category).eq(old_category))
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
-        self.conn.execute(&sql, ())?;
+        self.index_categories(&task_ids)?;
 
         Ok(())
     }
@@ -257,12 +322,14 @@ impl<'a> CategoryRepository<'a> {
      * TODO: test
      */
     pub fn delete_task_categories(&self, task_id: i64) -> Result<()> {
-        let sql = Query::delete()
+        let (sql, values) = Query::delete()
             .from_table(TaskCategoryIden::Table)
             .and_where(Expr::col(TaskCategoryIden::TaskId).eq(task_id))
-            .to_string(SqliteQueryBuilder);
+            .build(SqliteQueryBuilder);
 
-        self.conn.execute(&sql, ())?;
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        self.index_categories(&[task_id])?;
 
         Ok(())
     }
@@ -274,7 +341,7 @@ mod tests {
     use rusqlite::Connection;
 
     use crate::{
-        models::{setup_database, AddTask, TaskStatusEnum},
+        models::{setup_database, AddTask, TaskPriorityEnum, TaskStatusEnum},
         repositories::{get_now, task_repository::TaskRepository},
     };
 
@@ -297,6 +364,9 @@ mod tests {
             deadline: None,
             categories: None,
             status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
             created_at: now,
         })?;
         let categories = repository.fetch_task_categories(task.id)?;
@@ -317,18 +387,12 @@ mod tests {
         assert_eq!(None, data);
 
         // test rename not found
-        if let Err(_) = repository.rename_category(task.id, "404", "impossible") {
-            assert!(true);
-        } else {
-            assert!(false);
-        }
+        assert!(repository
+            .rename_category(task.id, "404", "impossible")
+            .is_err());
 
         // test delete not found
-        if let Err(_) = repository.delete_category(task.id, "404") {
-            assert!(true);
-        } else {
-            assert!(false);
-        }
+        assert!(repository.delete_category(task.id, "404").is_err());
 
         // test delete
         repository.delete_category(task.id, "dummy")?;
@@ -357,6 +421,9 @@ mod tests {
             deadline: None,
             categories: None,
             status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
             created_at: now,
         })?;
         let categories = repository.fetch_task_categories(task_one.id)?;
@@ -368,6 +435,9 @@ mod tests {
             deadline: None,
             categories: None,
             status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
             created_at: now,
         })?;
         let categories = repository.fetch_task_categories(task_two.id)?;
@@ -379,6 +449,9 @@ mod tests {
             deadline: None,
             categories: None,
             status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
             created_at: now,
         })?;
         let categories = repository.fetch_task_categories(task_three.id)?;
@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use rusqlite::Connection;
+use sea_query::{Cond, Expr, Query, SqliteQueryBuilder};
+
+use crate::models::TaskDependencyIden;
+
+use super::bind;
+
+/**
+ * Task dependency database repository
+ */
+pub struct DependencyRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> DependencyRepository<'a> {
+    /**
+     * Used to initialize the repository
+     */
+    pub fn create(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /**
+     * Used to mark that a task cannot start until another task is done
+     */
+    pub fn create_dependency(&self, task_id: i64, depends_on_id: i64) -> Result<()> {
+        let (sql, values) = Query::insert()
+            .into_table(TaskDependencyIden::Table)
+            .columns([TaskDependencyIden::TaskId, TaskDependencyIden::DependsOnId])
+            .values([task_id.into(), depends_on_id.into()])?
+            .build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        Ok(())
+    }
+
+    /**
+     * Used to create batch dependency edges
+     */
+    pub fn batch_create_dependencies(&self, edges: &[(i64, i64)]) -> Result<()> {
+        if edges.is_empty() {
+            return Ok(());
+        }
+
+        let mut sql = Query::insert();
+
+        sql.into_table(TaskDependencyIden::Table)
+            .columns([TaskDependencyIden::TaskId, TaskDependencyIden::DependsOnId]);
+
+        for (task_id, depends_on_id) in edges {
+            sql.values([(*task_id).into(), (*depends_on_id).into()])?;
+        }
+
+        let (sql, values) = sql.build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        Ok(())
+    }
+
+    /**
+     * Used to check if a dependency edge exists
+     */
+    pub fn has_dependency(&self, task_id: i64, depends_on_id: i64) -> Result<bool> {
+        let (sql, values) = Query::select()
+            .from(TaskDependencyIden::Table)
+            .expr(Expr::col(TaskDependencyIden::TaskId).count())
+            .and_where(Expr::col(TaskDependencyIden::TaskId).eq(task_id))
+            .and_where(Expr::col(TaskDependencyIden::DependsOnId).eq(depends_on_id))
+            .build(SqliteQueryBuilder);
+
+        let count: i64 = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_row(bind(&values), |row| row.get(0))?;
+
+        Ok(count > 0)
+    }
+
+    /**
+     * Used to fetch the ids of the tasks a task depends on
+     */
+    pub fn fetch_dependencies(&self, task_id: i64) -> Result<Vec<i64>> {
+        let (sql, values) = Query::select()
+            .from(TaskDependencyIden::Table)
+            .column(TaskDependencyIden::DependsOnId)
+            .and_where(Expr::col(TaskDependencyIden::TaskId).eq(task_id))
+            .order_by(TaskDependencyIden::DependsOnId, sea_query::Order::Asc)
+            .build(SqliteQueryBuilder);
+
+        let records = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+
+        Ok(records)
+    }
+
+    /**
+     * Used to fetch the ids of the tasks that depend on a task
+     */
+    pub fn fetch_dependents(&self, task_id: i64) -> Result<Vec<i64>> {
+        let (sql, values) = Query::select()
+            .from(TaskDependencyIden::Table)
+            .column(TaskDependencyIden::TaskId)
+            .and_where(Expr::col(TaskDependencyIden::DependsOnId).eq(task_id))
+            .order_by(TaskDependencyIden::TaskId, sea_query::Order::Asc)
+            .build(SqliteQueryBuilder);
+
+        let records = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+
+        Ok(records)
+    }
+
+    /**
+     * Used to fetch every dependency edge touching a task, in both directions
+     */
+    pub fn fetch_task_edges(&self, task_id: i64) -> Result<Vec<(i64, i64)>> {
+        let mut edges: Vec<(i64, i64)> = self
+            .fetch_dependencies(task_id)?
+            .into_iter()
+            .map(|depends_on_id| (task_id, depends_on_id))
+            .collect();
+
+        edges.extend(
+            self.fetch_dependents(task_id)?
+                .into_iter()
+                .map(|dependent_id| (dependent_id, task_id)),
+        );
+
+        Ok(edges)
+    }
+
+    /**
+     * Used to check if adding the edge would make a task (indirectly) depend on itself
+     */
+    pub fn would_create_cycle(&self, task_id: i64, depends_on_id: i64) -> Result<bool> {
+        let mut visited = HashSet::new();
+        let mut stack = vec![depends_on_id];
+
+        while let Some(id) = stack.pop() {
+            if id == task_id {
+                return Ok(true);
+            }
+            if visited.insert(id) {
+                stack.extend(self.fetch_dependencies(id)?);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /**
+     * Used to delete a dependency edge
+     */
+    pub fn delete_dependency(&self, task_id: i64, depends_on_id: i64) -> Result<()> {
+        if !self.has_dependency(task_id, depends_on_id)? {
+            return Err(anyhow::anyhow!(
+                "Dependency not found (#{} -> #{})",
+                task_id,
+                depends_on_id
+            ));
+        }
+
+        let (sql, values) = Query::delete()
+            .from_table(TaskDependencyIden::Table)
+            .and_where(Expr::col(TaskDependencyIden::TaskId).eq(task_id))
+            .and_where(Expr::col(TaskDependencyIden::DependsOnId).eq(depends_on_id))
+            .build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        Ok(())
+    }
+
+    /**
+     * Used to delete every dependency edge touching a task
+     */
+    pub fn delete_task_dependencies(&self, task_id: i64) -> Result<()> {
+        let (sql, values) = Query::delete()
+            .from_table(TaskDependencyIden::Table)
+            .cond_where(
+                Cond::any()
+                    .add(Expr::col(TaskDependencyIden::TaskId).eq(task_id))
+                    .add(Expr::col(TaskDependencyIden::DependsOnId).eq(task_id)),
+            )
+            .build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use rusqlite::Connection;
+
+    use crate::{
+        models::{setup_database, AddTask, TaskPriorityEnum, TaskStatusEnum},
+        repositories::{get_now, task_repository::TaskRepository},
+    };
+
+    use super::DependencyRepository;
+
+    #[test]
+    fn test_crud_dependency() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+
+        let repository = DependencyRepository::create(&conn);
+        let task_repository = TaskRepository::create(&conn);
+
+        // prepare
+        for title in ["One", "Two", "Three", "Four"] {
+            task_repository.create_task(AddTask {
+                title: title.into(),
+                info: None,
+                deadline: None,
+                categories: None,
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                created_at: now,
+            })?;
+        }
+
+        // test create: 1 -> 2 -> 3, 4 -> 2
+        repository.create_dependency(1, 2)?;
+        repository.batch_create_dependencies(&[(2, 3), (4, 2)])?;
+        assert!(repository.has_dependency(1, 2)?);
+        assert!(!repository.has_dependency(2, 1)?);
+        assert_eq!(vec![3], repository.fetch_dependencies(2)?);
+        assert_eq!(vec![1, 4], repository.fetch_dependents(2)?);
+        assert_eq!(
+            vec![(2, 3), (1, 2), (4, 2)],
+            repository.fetch_task_edges(2)?
+        );
+
+        // test cycle detection
+        assert!(repository.would_create_cycle(3, 1)?);
+        assert!(repository.would_create_cycle(2, 1)?);
+        assert!(repository.would_create_cycle(3, 3)?);
+        assert!(!repository.would_create_cycle(1, 3)?);
+        assert!(!repository.would_create_cycle(1, 4)?);
+
+        // test delete
+        assert!(repository.delete_dependency(2, 1).is_err());
+        repository.delete_dependency(1, 2)?;
+        assert!(!repository.has_dependency(1, 2)?);
+        assert!(!repository.would_create_cycle(3, 1)?);
+
+        repository.delete_task_dependencies(2)?;
+        assert_eq!(0, repository.fetch_task_edges(2)?.len());
+
+        Ok(())
+    }
+}
@@ -1,13 +1,54 @@
 use anyhow::Result;
 use rusqlite::Connection;
-use sea_query::{Expr, Query, SqliteQueryBuilder};
+use sea_query::{
+    Alias, Cond, Expr, Func, NullOrdering, Query, SelectStatement, SimpleExpr, SqliteQueryBuilder,
+};
+use time::{format_description::well_known::Rfc3339, Date, OffsetDateTime};
 
 use crate::models::{
-    AddTask, OrderByEnum, QueryTaskPayload, Task, TaskCategoryIden, TaskFtsIden, TaskIden,
-    TaskStatusEnum, UpdateTask,
+    AddTask, CompareEnum, OrderByEnum, QueryTaskPayload, SearchTaskPayload, Task, TaskCategoryIden,
+    TaskCursor, TaskDependencyIden, TaskFilter, TaskFilterTerm, TaskFtsIden, TaskIden, TaskPage,
+    TaskSearchResult, TaskSortColumnEnum, TaskSortKey, TaskStatusEnum, UpdateTask,
 };
 
-use super::get_now;
+use crate::{search::fts_query, utils::get_local_now};
+
+use super::{bind, category_repository::categories_text, get_now};
+
+/**
+ * Columns selected when reading a task, in the order expected by `task_from_row`
+ */
+const TASK_COLUMNS: [TaskIden; 10] = [
+    TaskIden::Id,
+    TaskIden::Title,
+    TaskIden::Info,
+    TaskIden::Deadline,
+    TaskIden::Status,
+    TaskIden::Priority,
+    TaskIden::ParentId,
+    TaskIden::Recurrence,
+    TaskIden::UpdatedAt,
+    TaskIden::CreatedAt,
+];
+
+/**
+ * Used to map a row selected with `TASK_COLUMNS` to a Task
+ */
+fn task_from_row(row: &rusqlite::Row) -> rusqlite::Result<Task> {
+    Ok(Task {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        info: row.get(2)?,
+        deadline: row.get(3)?,
+        status: row.get(4)?,
+        priority: row.get(5)?,
+        parent_id: row.get(6)?,
+        recurrence: row.get(7)?,
+        updated_at: row.get(8)?,
+        created_at: row.get(9)?,
+        categories: None,
+    })
+}
 
 /**
  * Task database repository
@@ -28,32 +69,16 @@ impl<'a> TaskRepository<'a> {
      * Used to fetch a single task
      */
     pub fn get_task(&self, id: i64) -> Result<Option<Task>> {
-        let sql = Query::select()
+        let (sql, values) = Query::select()
             .from(TaskIden::Table)
-            .columns([
-                TaskIden::Id,
-                TaskIden::Title,
-                TaskIden::Info,
-                TaskIden::Deadline,
-                TaskIden::Status,
-                TaskIden::UpdatedAt,
-                TaskIden::CreatedAt,
-            ])
+            .columns(TASK_COLUMNS)
             .and_where(Expr::col(TaskIden::Id).eq(id))
-            .to_string(SqliteQueryBuilder);
-
-        let result = self.conn.query_row(&sql, (), |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                info: row.get(2)?,
-                deadline: row.get(3)?,
-                status: row.get(4)?,
-                updated_at: row.get(5)?,
-                created_at: row.get(6)?,
-                categories: None,
-            })
-        });
+            .build(SqliteQueryBuilder);
+
+        let result = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_row(bind(&values), task_from_row);
 
         match result {
             Ok(task) => Ok(Some(task)),
@@ -66,29 +91,35 @@ impl<'a> TaskRepository<'a> {
      * Used to create a single task
      */
     pub fn create_task(&self, task: AddTask) -> Result<Task> {
-        let sql = Query::insert()
+        let (sql, values) = Query::insert()
             .into_table(TaskIden::Table)
             .columns([
                 TaskIden::Title,
                 TaskIden::Info,
                 TaskIden::Deadline,
                 TaskIden::Status,
+                TaskIden::Priority,
+                TaskIden::ParentId,
+                TaskIden::Recurrence,
                 TaskIden::UpdatedAt,
                 TaskIden::CreatedAt,
             ])
             .values([
                 task.title.clone().into(),
                 task.info.clone().into(),
-                task.deadline.into(),
+                task.deadline.map(|v| v.to_string()).into(),
                 task.status.into(),
+                task.priority.into(),
+                task.parent_id.into(),
+                task.recurrence.as_ref().map(|v| v.to_string()).into(),
                 task.created_at.into(),
                 task.created_at.into(),
             ])?
-            .to_string(SqliteQueryBuilder);
-        self.conn.execute(&sql, ())?;
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
         let id = self.conn.last_insert_rowid();
 
-        let sql = Query::insert()
+        let (sql, values) = Query::insert()
             .into_table(TaskFtsIden::Table)
             .columns([TaskFtsIden::Id, TaskFtsIden::Title, TaskFtsIden::Info])
             .values([
@@ -96,8 +127,8 @@ impl<'a> TaskRepository<'a> {
                 task.title.clone().into(),
                 task.info.clone().into(),
             ])?
-            .to_string(SqliteQueryBuilder);
-        self.conn.execute(&sql, ())?;
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
         Ok(Task {
             id,
@@ -105,6 +136,9 @@ impl<'a> TaskRepository<'a> {
             info: task.info,
             deadline: task.deadline,
             status: task.status,
+            priority: task.priority,
+            parent_id: task.parent_id,
+            recurrence: task.recurrence,
             updated_at: task.created_at,
             created_at: task.created_at,
             categories: None,
@@ -117,7 +151,7 @@ impl<'a> TaskRepository<'a> {
     pub fn create_task_with_id(&self, id: i64, task: AddTask) -> Result<Task> {
         let now = get_now();
 
-        let sql = Query::insert()
+        let (sql, values) = Query::insert()
             .into_table(TaskIden::Table)
             .columns([
                 TaskIden::Id,
@@ -125,6 +159,9 @@ impl<'a> TaskRepository<'a> {
                 TaskIden::Info,
                 TaskIden::Deadline,
                 TaskIden::Status,
+                TaskIden::Priority,
+                TaskIden::ParentId,
+                TaskIden::Recurrence,
                 TaskIden::UpdatedAt,
                 TaskIden::CreatedAt,
             ])
@@ -132,16 +169,19 @@ impl<'a> TaskRepository<'a> {
                 id.into(),
                 task.title.clone().into(),
                 task.info.clone().into(),
-                task.deadline.into(),
+                task.deadline.map(|v| v.to_string()).into(),
                 task.status.into(),
+                task.priority.into(),
+                task.parent_id.into(),
+                task.recurrence.as_ref().map(|v| v.to_string()).into(),
                 now.to_string().into(),
                 task.created_at.into(),
             ])?
-            .to_string(SqliteQueryBuilder);
-        self.conn.execute(&sql, ())?;
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
         let id = self.conn.last_insert_rowid();
 
-        let sql = Query::insert()
+        let (sql, values) = Query::insert()
             .into_table(TaskFtsIden::Table)
             .columns([TaskFtsIden::Id, TaskFtsIden::Title, TaskFtsIden::Info])
             .values([
@@ -149,8 +189,8 @@ impl<'a> TaskRepository<'a> {
                 task.title.clone().into(),
                 task.info.clone().into(),
             ])?
-            .to_string(SqliteQueryBuilder);
-        self.conn.execute(&sql, ())?;
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
         Ok(Task {
             id,
@@ -158,6 +198,9 @@ impl<'a> TaskRepository<'a> {
             info: task.info,
             deadline: task.deadline,
             status: task.status,
+            priority: task.priority,
+            parent_id: task.parent_id,
+            recurrence: task.recurrence,
             updated_at: now,
             created_at: task.created_at,
             categories: None,
@@ -193,7 +236,7 @@ impl<'a> TaskRepository<'a> {
         }
 
         if let Some(deadline) = new_task.deadline {
-            sql.value(TaskIden::Deadline, deadline);
+            sql.value(TaskIden::Deadline, deadline.map(|v| v.to_string()));
             changes += 1;
         }
 
@@ -202,6 +245,16 @@ impl<'a> TaskRepository<'a> {
             changes += 1;
         }
 
+        if let Some(priority) = new_task.priority {
+            sql.value(TaskIden::Priority, priority);
+            changes += 1;
+        }
+
+        if let Some(recurrence) = new_task.recurrence {
+            sql.value(TaskIden::Recurrence, recurrence.map(|v| v.to_string()));
+            changes += 1;
+        }
+
         if let Some(created_at) = new_task.created_at {
             sql.value(TaskIden::CreatedAt, created_at);
             changes += 1;
@@ -211,14 +264,14 @@ impl<'a> TaskRepository<'a> {
         if changes > 0 {
             sql.value(TaskIden::UpdatedAt, now);
             sql.and_where(Expr::col(TaskIden::Id).eq(id));
-            let sql = sql.to_string(SqliteQueryBuilder);
-            self.conn.execute(&sql, ())?;
+            let (sql, values) = sql.build(SqliteQueryBuilder);
+            self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
             if changes_fts > 0 {
                 sql_fts.and_where(Expr::col(TaskFtsIden::Id).eq(id));
-                let sql = sql_fts.to_string(SqliteQueryBuilder);
+                let (sql, values) = sql_fts.build(SqliteQueryBuilder);
 
-                self.conn.execute(&sql, ())?;
+                self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
             }
 
             Ok(())
@@ -231,178 +284,642 @@ impl<'a> TaskRepository<'a> {
      * Used to update a single task
      */
     pub fn delete_task(&self, task: &Task) -> Result<()> {
-        let sql = Query::delete()
+        let (sql, values) = Query::delete()
             .from_table(TaskIden::Table)
             .and_where(Expr::col(TaskIden::Id).eq(task.id))
-            .to_string(SqliteQueryBuilder);
-        self.conn.execute(&sql, ())?;
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
-        let sql = Query::delete()
+        let (sql, values) = Query::delete()
             .from_table(TaskFtsIden::Table)
             .and_where(Expr::col(TaskFtsIden::Id).eq(task.id))
-            .to_string(SqliteQueryBuilder);
-        self.conn.execute(&sql, ())?;
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
         Ok(())
     }
 
     /**
-     * Used to query tasks
+     * Used to fetch the direct subtasks of a task
      */
-    pub fn query_tasks(&self, payload: QueryTaskPayload) -> Result<Vec<Task>> {
-        let mut sql = Query::select();
-        sql.from(TaskIden::Table);
-        sql.columns([
-            TaskIden::Id,
-            TaskIden::Title,
-            TaskIden::Info,
-            TaskIden::Deadline,
-            TaskIden::Status,
-            TaskIden::UpdatedAt,
-            TaskIden::CreatedAt,
-        ]);
-
-        if let Some(text) = payload.text {
-            let sub_query = Query::select()
-                .from(TaskFtsIden::Table)
-                .column(TaskFtsIden::Id)
-                .and_where(Expr::col(TaskFtsIden::Table).eq(text))
-                .clone();
-            sql.and_where(Expr::col(TaskIden::Id).in_subquery(sub_query));
-        }
+    pub fn fetch_subtasks(&self, id: i64) -> Result<Vec<Task>> {
+        let (sql, values) = Query::select()
+            .from(TaskIden::Table)
+            .columns(TASK_COLUMNS)
+            .and_where(Expr::col(TaskIden::ParentId).eq(id))
+            .order_by(TaskIden::Id, sea_query::Order::Asc)
+            .build(SqliteQueryBuilder);
 
-        if let Some(status) = payload.status {
-            sql.and_where(Expr::col(TaskIden::Status).eq(status));
-        }
+        let data = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), task_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
 
-        if let Some(categories) = payload.categories {
-            let sub_query = Query::select()
-                .from(TaskCategoryIden::Table)
-                .column(TaskCategoryIden::TaskId)
-                .and_where(Expr::col(TaskCategoryIden::Category).is_in(categories))
-                .clone();
-            sql.and_where(Expr::col(TaskIden::Id).in_subquery(sub_query));
+        Ok(data)
+    }
+
+    /**
+     * Used to fetch all the subtasks of a task, parents always before their children
+     */
+    pub fn fetch_descendants(&self, id: i64) -> Result<Vec<Task>> {
+        let mut descendants = self.fetch_subtasks(id)?;
+        let mut index = 0;
+
+        while index < descendants.len() {
+            let subtasks = self.fetch_subtasks(descendants[index].id)?;
+            descendants.extend(subtasks);
+            index += 1;
         }
 
-        sql.limit(payload.limit);
+        Ok(descendants)
+    }
 
-        if let Some(sort_created_at) = payload.sort_created_at {
-            match sort_created_at {
-                OrderByEnum::Asc => {
-                    sql.order_by(TaskIden::CreatedAt, sea_query::Order::Asc);
-                }
-                OrderByEnum::Desc => {
-                    sql.order_by(TaskIden::CreatedAt, sea_query::Order::Desc);
-                }
-            }
+    /**
+     * Used to move tasks under a new parent without touching their updated at date
+     */
+    pub fn set_parent(&self, ids: &[i64], parent_id: Option<i64>) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
         }
-        if let Some(sort_updated_at) = payload.sort_updated_at {
-            match sort_updated_at {
-                OrderByEnum::Asc => {
-                    sql.order_by(TaskIden::CreatedAt, sea_query::Order::Asc);
-                }
-                OrderByEnum::Desc => {
-                    sql.order_by(TaskIden::CreatedAt, sea_query::Order::Desc);
-                }
+
+        let (sql, values) = Query::update()
+            .table(TaskIden::Table)
+            .value(TaskIden::ParentId, parent_id)
+            .and_where(Expr::col(TaskIden::Id).is_in(ids.iter().copied()))
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        Ok(())
+    }
+
+    /**
+     * Used to query a page of tasks, ordered by the requested sort keys and then by id
+     */
+    pub fn query_tasks(&self, payload: QueryTaskPayload) -> Result<TaskPage> {
+        let keys = &payload.sort;
+
+        let mut filtered = Query::select();
+        filtered.from(TaskIden::Table);
+        filter_tasks(&mut filtered, &payload);
+
+        let total = self.count_tasks(filtered.clone())?;
+
+        let mut sql = filtered.clone();
+        sql.columns(TASK_COLUMNS);
+
+        let start = match &payload.after {
+            Some(cursor) => {
+                let condition = after_cursor(keys, cursor)?;
+                let remaining = self.count_tasks(filtered.cond_where(condition.clone()).clone())?;
+                sql.cond_where(condition);
+                total - remaining
             }
-        }
-        if let Some(sort_deadline) = payload.sort_deadline {
-            match sort_deadline {
-                OrderByEnum::Asc => {
-                    sql.order_by(TaskIden::CreatedAt, sea_query::Order::Asc);
-                }
-                OrderByEnum::Desc => {
-                    sql.order_by(TaskIden::CreatedAt, sea_query::Order::Desc);
-                }
+            None => {
+                sql.offset(payload.offset);
+                payload.offset.min(total)
             }
+        };
+
+        for key in keys {
+            let nulls = match key.nulls_first {
+                true => NullOrdering::First,
+                false => NullOrdering::Last,
+            };
+            sql.order_by_expr_with_nulls(sort_expr(key.column), key.order.into(), nulls);
         }
-        if let Some(sort_title) = payload.sort_title {
-            match sort_title {
-                OrderByEnum::Asc => {
-                    sql.order_by(TaskIden::CreatedAt, sea_query::Order::Asc);
-                }
-                OrderByEnum::Desc => {
-                    sql.order_by(TaskIden::CreatedAt, sea_query::Order::Desc);
-                }
-            }
+        sql.order_by(TaskIden::Id, sea_query::Order::Asc);
+        sql.limit(payload.limit);
+
+        let (sql, values) = sql.build(SqliteQueryBuilder);
+
+        let tasks = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), task_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let next_cursor = match tasks.last() {
+            Some(task) if start + (tasks.len() as u64) < total => Some(TaskCursor {
+                sort: keys.iter().map(|key| key.to_string()).collect(),
+                values: keys
+                    .iter()
+                    .map(|key| sort_value(task, key.column))
+                    .collect(),
+                id: task.id,
+            }),
+            _ => None,
+        };
+
+        Ok(TaskPage {
+            tasks,
+            total,
+            start,
+            next_cursor,
+        })
+    }
+
+    /**
+     * Used to search tasks by title, info and categories, the best matches first.
+     * Title matches weigh the most, then categories, then info.
+     */
+    pub fn search_tasks(&self, payload: SearchTaskPayload) -> Result<Vec<TaskSearchResult>> {
+        let (start, end) = payload.marks;
+
+        let mut sql = Query::select();
+        sql.columns(TASK_COLUMNS.map(|column| (TaskIden::Table, column)))
+            .expr_as(
+                Expr::cust("-bm25(tasks_fts, 0.0, 4.0, 1.0, 2.0)"),
+                Alias::new("score"),
+            )
+            .expr(Expr::cust_with_values(
+                "highlight(tasks_fts, 1, ?, ?)",
+                [start.clone(), end.clone()],
+            ))
+            .expr(Expr::cust_with_values(
+                "snippet(tasks_fts, 2, ?, ?, '…', 12)",
+                [start, end],
+            ))
+            .from(TaskFtsIden::Table)
+            .inner_join(
+                TaskIden::Table,
+                Expr::col((TaskIden::Table, TaskIden::Id))
+                    .equals((TaskFtsIden::Table, TaskFtsIden::Id)),
+            )
+            .and_where(Expr::cust_with_values("tasks_fts MATCH ?", [payload.query]))
+            .order_by(Alias::new("score"), sea_query::Order::Desc)
+            .order_by((TaskIden::Table, TaskIden::Id), sea_query::Order::Asc)
+            .limit(payload.limit);
+
+        if let Some(status) = payload.status {
+            sql.and_where(Expr::col((TaskIden::Table, TaskIden::Status)).eq(status));
         }
 
-        let sql = sql.to_string(SqliteQueryBuilder);
+        let (sql, values) = sql.build(SqliteQueryBuilder);
 
-        let data = self
+        let results = self
             .conn
-            .prepare(&sql)?
-            .query_map((), |row| {
-                Ok(Task {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    info: row.get(2)?,
-                    deadline: row.get(3)?,
-                    categories: None,
-                    status: row.get(4)?,
-                    updated_at: row.get(5)?,
-                    created_at: row.get(6)?,
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), |row| {
+                let task = task_from_row(row)?;
+                let snippet: Option<String> = row.get(12)?;
+
+                Ok(TaskSearchResult {
+                    score: row.get(10)?,
+                    title: row.get(11)?,
+                    snippet: snippet.filter(|_| task.info.is_some()),
+                    task,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(data)
+        Ok(results)
     }
 
     /**
-     * Used to delete all archived tasks
+     * Used to rebuild the full text index of all tasks from scratch, returns the number of tasks indexed
      */
-    pub fn delete_archived(&self) -> Result<i64> {
-        let sql = Query::select()
+    pub fn reindex(&self) -> Result<i64> {
+        let (sql, values) = Query::delete()
+            .from_table(TaskFtsIden::Table)
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        let tasks = Query::select()
+            .columns([TaskIden::Id, TaskIden::Title, TaskIden::Info])
+            .expr(categories_text(
+                Expr::col((TaskIden::Table, TaskIden::Id)).into(),
+            ))
             .from(TaskIden::Table)
-            .expr(Expr::col(TaskIden::Id).count())
-            .and_where(Expr::col(TaskIden::Status).eq(TaskStatusEnum::Archived))
-            .to_string(SqliteQueryBuilder);
+            .to_owned();
+        let (sql, values) = Query::insert()
+            .into_table(TaskFtsIden::Table)
+            .columns([
+                TaskFtsIden::Id,
+                TaskFtsIden::Title,
+                TaskFtsIden::Info,
+                TaskFtsIden::Categories,
+            ])
+            .select_from(tasks)?
+            .build(SqliteQueryBuilder);
+        let indexed = self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
 
-        let count = self.conn.query_row(&sql, (), |row| Ok(row.get(0)?))?;
+        // merge the index segments left by the rebuild
+        self.conn
+            .execute("INSERT INTO tasks_fts (tasks_fts) VALUES ('optimize')", ())?;
 
-        let sql = Query::delete()
-            .from_table(TaskIden::Table)
-            .and_where(Expr::col(TaskIden::Status).eq(TaskStatusEnum::Archived))
-            .to_string(SqliteQueryBuilder);
+        Ok(indexed as i64)
+    }
 
-        self.conn.execute(&sql, ())?;
+    /**
+     * Used to count the tasks selected by a query
+     */
+    fn count_tasks(&self, mut sql: SelectStatement) -> Result<u64> {
+        let (sql, values) = sql
+            .expr(Expr::col((TaskIden::Table, TaskIden::Id)).count())
+            .build(SqliteQueryBuilder);
 
-        Ok(count)
+        let count: i64 = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_row(bind(&values), |row| row.get(0))?;
+
+        Ok(count as u64)
     }
 
     /**
-     * Used to archive all completed tasks
+     * Used to fetch the tasks with a status last updated on or before a day, by id
      */
-    pub fn archive_tasks(&self) -> Result<i64> {
-        let sql = Query::select()
+    pub fn fetch_stale_tasks(&self, status: TaskStatusEnum, until: Date) -> Result<Vec<Task>> {
+        let (sql, values) = Query::select()
             .from(TaskIden::Table)
-            .expr(Expr::col(TaskIden::Id).count())
-            .and_where(Expr::col(TaskIden::Status).eq(TaskStatusEnum::Done))
-            .to_string(SqliteQueryBuilder);
+            .columns(TASK_COLUMNS)
+            .and_where(Expr::col(TaskIden::Status).eq(status))
+            .and_where(Expr::col(TaskIden::UpdatedAt).lte(until))
+            .order_by(TaskIden::Id, sea_query::Order::Asc)
+            .build(SqliteQueryBuilder);
+
+        let data = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), task_from_row)?
+            .collect::<Result<Vec<Task>, _>>()?;
 
-        let count = self.conn.query_row(&sql, (), |row| Ok(row.get(0)?))?;
+        Ok(data)
+    }
 
-        let sql = Query::update()
+    /**
+     * Used to archive tasks, their age as archived tasks starts at `now`
+     */
+    pub fn archive_tasks(&self, ids: &[i64], now: &str) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let (sql, values) = Query::update()
             .table(TaskIden::Table)
-            .and_where(Expr::col(TaskIden::Status).eq(TaskStatusEnum::Done))
-            .value(TaskIden::Status, TaskStatusEnum::Archived)
-            .to_string(SqliteQueryBuilder);
+            .values([
+                (TaskIden::Status, TaskStatusEnum::Archived.into()),
+                (TaskIden::UpdatedAt, now.into()),
+            ])
+            .and_where(Expr::col(TaskIden::Id).is_in(ids.iter().copied()))
+            .build(SqliteQueryBuilder);
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        Ok(())
+    }
+}
+
+/**
+ * Used to apply the filters of a query
+ */
+fn filter_tasks(sql: &mut SelectStatement, payload: &QueryTaskPayload) {
+    if let Some(text) = &payload.text {
+        sql.and_where(matches_text(text));
+    }
+
+    if let Some(status) = payload.status {
+        sql.and_where(Expr::col(TaskIden::Status).eq(status));
+    }
+
+    if let Some(priority) = payload.priority {
+        sql.and_where(Expr::col(TaskIden::Priority).eq(priority));
+    }
+
+    if let Some(categories) = &payload.any_categories {
+        sql.and_where(Expr::col(TaskIden::Id).in_subquery(tasks_with_categories(categories)));
+    }
+
+    if let Some(categories) = &payload.all_categories {
+        let mut categories = categories.clone();
+        categories.sort();
+        categories.dedup();
+
+        // tasks matching as many distinct categories as requested have all of them
+        let sub_query = tasks_with_categories(&categories)
+            .group_by_col(TaskCategoryIden::TaskId)
+            .and_having(
+                Expr::col(TaskCategoryIden::Category)
+                    .count_distinct()
+                    .eq(categories.len() as i64),
+            )
+            .clone();
+        sql.and_where(Expr::col(TaskIden::Id).in_subquery(sub_query));
+    }
+
+    if let Some(categories) = &payload.exclude_categories {
+        sql.and_where(Expr::col(TaskIden::Id).not_in_subquery(tasks_with_categories(categories)));
+    }
+
+    if let Some(date) = payload.due_before {
+        sql.cond_where(compare_date(TaskIden::Deadline, CompareEnum::Lt, date));
+    }
+
+    if let Some(date) = payload.due_after {
+        sql.cond_where(compare_date(TaskIden::Deadline, CompareEnum::Gt, date));
+    }
+
+    if payload.overdue {
+        sql.cond_where(overdue(get_local_now()));
+    }
+
+    if payload.no_deadline {
+        sql.and_where(Expr::col(TaskIden::Deadline).is_null());
+    }
+
+    if let Some(date) = payload.created_since {
+        sql.cond_where(compare_date(TaskIden::CreatedAt, CompareEnum::Ge, date));
+    }
+
+    if let Some(date) = payload.updated_since {
+        sql.cond_where(compare_date(TaskIden::UpdatedAt, CompareEnum::Ge, date));
+    }
+
+    if payload.blocked {
+        sql.cond_where(blocked());
+    }
+
+    if payload.ready {
+        sql.cond_where(ready());
+    }
+
+    if let Some(filter) = &payload.filter {
+        sql.cond_where(filter_condition(filter, get_local_now()));
+    }
+}
+
+/**
+ * Used to translate a `--where` filter into a condition
+ */
+fn filter_condition(filter: &TaskFilter, now: OffsetDateTime) -> Cond {
+    match filter {
+        TaskFilter::And(left, right) => Cond::all()
+            .add(filter_condition(left, now))
+            .add(filter_condition(right, now)),
+        TaskFilter::Or(left, right) => Cond::any()
+            .add(filter_condition(left, now))
+            .add(filter_condition(right, now)),
+        TaskFilter::Not(filter) => filter_condition(filter, now).not(),
+        TaskFilter::Term(term) => match term {
+            TaskFilterTerm::Status(status) => {
+                Cond::all().add(Expr::col(TaskIden::Status).eq(*status))
+            }
+            TaskFilterTerm::Priority(compare, priority) => {
+                let column = Expr::col(TaskIden::Priority);
+                Cond::all().add(match compare {
+                    CompareEnum::Eq => column.eq(*priority),
+                    CompareEnum::Lt => column.lt(*priority),
+                    CompareEnum::Le => column.lte(*priority),
+                    CompareEnum::Gt => column.gt(*priority),
+                    CompareEnum::Ge => column.gte(*priority),
+                })
+            }
+            TaskFilterTerm::Category(category) => Cond::all().add(
+                Expr::col(TaskIden::Id)
+                    .in_subquery(tasks_with_categories(std::slice::from_ref(category))),
+            ),
+            TaskFilterTerm::Text(text) => Cond::all().add(matches_text(text)),
+            TaskFilterTerm::Deadline(compare, Some(date)) => {
+                compare_date(TaskIden::Deadline, *compare, *date)
+            }
+            TaskFilterTerm::Deadline(_, None) => {
+                Cond::all().add(Expr::col(TaskIden::Deadline).is_null())
+            }
+            TaskFilterTerm::CreatedAt(compare, date) => {
+                compare_date(TaskIden::CreatedAt, *compare, *date)
+            }
+            TaskFilterTerm::UpdatedAt(compare, date) => {
+                compare_date(TaskIden::UpdatedAt, *compare, *date)
+            }
+            TaskFilterTerm::Overdue => overdue(now),
+            TaskFilterTerm::Blocked => blocked(),
+            TaskFilterTerm::Ready => ready(),
+        },
+    }
+}
+
+/**
+ * Used to select the tasks whose title, info or categories match a search, see `search::fts_query`
+ */
+fn matches_text(text: &str) -> SimpleExpr {
+    // a text without any word to search for matches no task
+    let Ok(query) = fts_query(text) else {
+        return Expr::val(false).into();
+    };
+
+    let sub_query = Query::select()
+        .from(TaskFtsIden::Table)
+        .column(TaskFtsIden::Id)
+        .and_where(Expr::col(TaskFtsIden::Table).eq(query))
+        .clone();
+
+    Expr::col(TaskIden::Id).in_subquery(sub_query)
+}
+
+/**
+ * Used to compare a date column by whole days. Values are compared as text,
+ * deadlines by the day they were set for, so the column indexes are used.
+ * Missing values never match, so that `not` keeps them.
+ */
+fn compare_date(column: TaskIden, compare: CompareEnum, date: Date) -> Cond {
+    let day = date.to_string();
+    let next_day = date.next_day().unwrap_or(date).to_string();
+
+    let condition = match compare {
+        CompareEnum::Eq => Cond::all()
+            .add(Expr::col(column).gte(day))
+            .add(Expr::col(column).lt(next_day)),
+        CompareEnum::Lt => Cond::all().add(Expr::col(column).lt(day)),
+        CompareEnum::Le => Cond::all().add(Expr::col(column).lt(next_day)),
+        CompareEnum::Gt => Cond::all().add(Expr::col(column).gte(next_day)),
+        CompareEnum::Ge => Cond::all().add(Expr::col(column).gte(day)),
+    };
+
+    condition.add(Expr::col(column).is_not_null())
+}
+
+/**
+ * Used to select the undone tasks whose deadline has passed, day deadlines are
+ * overdue once their day is over, time deadlines once their moment passed
+ */
+fn overdue(now: OffsetDateTime) -> Cond {
+    let deadline = || Expr::col(TaskIden::Deadline);
+
+    Cond::all()
+        .add(Expr::col(TaskIden::Status).eq(TaskStatusEnum::Undone))
+        .add(deadline().is_not_null())
+        .add(
+            Cond::any()
+                .add(
+                    Cond::all()
+                        .add(Expr::expr(Func::char_length(deadline())).eq(10))
+                        .add(deadline().lt(now.date().to_string())),
+                )
+                .add(
+                    Cond::all()
+                        .add(Expr::expr(Func::char_length(deadline())).gt(10))
+                        .add(
+                            Expr::expr(julianday(deadline()))
+                                .lt(julianday(now.format(&Rfc3339).unwrap_or_default())),
+                        ),
+                ),
+        )
+}
+
+/**
+ * Used to convert a date or RFC 3339 text to a moment comparable across UTC offsets
+ */
+fn julianday(value: impl Into<SimpleExpr>) -> SimpleExpr {
+    Func::cust(Alias::new("julianday")).arg(value).into()
+}
+
+/**
+ * Used to select the ids of the tasks that depend on at least one task which is not done yet
+ */
+fn waiting_tasks() -> SelectStatement {
+    Query::select()
+        .from(TaskDependencyIden::Table)
+        .column((TaskDependencyIden::Table, TaskDependencyIden::TaskId))
+        .inner_join(
+            TaskIden::Table,
+            Expr::col((TaskIden::Table, TaskIden::Id))
+                .equals((TaskDependencyIden::Table, TaskDependencyIden::DependsOnId)),
+        )
+        .and_where(Expr::col((TaskIden::Table, TaskIden::Status)).eq(TaskStatusEnum::Undone))
+        .clone()
+}
+
+/**
+ * Used to select the tasks waiting on a task that is not done
+ */
+fn blocked() -> Cond {
+    Cond::all().add(Expr::col(TaskIden::Id).in_subquery(waiting_tasks()))
+}
+
+/**
+ * Used to select the undone tasks that are not waiting on any task
+ */
+fn ready() -> Cond {
+    Cond::all()
+        .add(Expr::col(TaskIden::Status).eq(TaskStatusEnum::Undone))
+        .add(Expr::col(TaskIden::Id).not_in_subquery(waiting_tasks()))
+}
+
+/**
+ * Used to select the ids of the tasks having any of the categories
+ */
+fn tasks_with_categories(categories: &[String]) -> SelectStatement {
+    Query::select()
+        .from(TaskCategoryIden::Table)
+        .column(TaskCategoryIden::TaskId)
+        .and_where(
+            Expr::col(TaskCategoryIden::Category).is_in(categories.iter().map(String::as_str)),
+        )
+        .clone()
+}
+
+/**
+ * Used to get what a sort column is ordered by, deadlines are ordered as moments since
+ * they keep the UTC offset they were set with and day deadlines start at midnight UTC
+ */
+fn sort_expr(column: TaskSortColumnEnum) -> SimpleExpr {
+    match column {
+        TaskSortColumnEnum::Deadline => julianday(Expr::col(TaskIden::Deadline)),
+        column => Expr::col(column.iden()).into(),
+    }
+}
+
+/**
+ * Used to read the value of a sort column from a task, as stored in the database
+ */
+fn sort_value(task: &Task, column: TaskSortColumnEnum) -> serde_json::Value {
+    match column {
+        TaskSortColumnEnum::Id => task.id.into(),
+        TaskSortColumnEnum::Title => task.title.clone().into(),
+        TaskSortColumnEnum::Info => task.info.clone().into(),
+        TaskSortColumnEnum::Deadline => task.deadline.map(|v| v.to_string()).into(),
+        TaskSortColumnEnum::Status => Into::<&str>::into(task.status).into(),
+        TaskSortColumnEnum::Priority => task.priority.as_i64().into(),
+        TaskSortColumnEnum::Parent => task.parent_id.into(),
+        TaskSortColumnEnum::Repeat => task.recurrence.as_ref().map(|v| v.to_string()).into(),
+        TaskSortColumnEnum::Created => task.created_at.to_string().into(),
+        TaskSortColumnEnum::Updated => task.updated_at.to_string().into(),
+    }
+}
+
+/**
+ * Used to build the condition selecting the tasks after a cursor:
+ * `k1 after v1 OR (k1 = v1 AND k2 after v2) OR ... OR (all equal AND id > cursor id)`,
+ * where NULL comes after every value of a `nulls-last` key and before every value otherwise.
+ */
+fn after_cursor(keys: &[TaskSortKey], cursor: &TaskCursor) -> Result<Cond> {
+    let sort: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+    if cursor.sort != sort || cursor.values.len() != keys.len() {
+        return Err(anyhow::anyhow!(
+            "Cursor was created with a different sort [{}]",
+            cursor.sort.join(", ")
+        ));
+    }
 
-        self.conn.execute(&sql, ())?;
+    let mut any = Cond::any();
+    let mut equal = Cond::all();
+
+    for (key, value) in keys.iter().zip(&cursor.values) {
+        let column = || Expr::expr(sort_expr(key.column));
+        let value: Option<SimpleExpr> = match value {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(value) => Some(Expr::val(value.as_str()).into()),
+            serde_json::Value::Number(value) => value.as_i64().map(|value| Expr::val(value).into()),
+            _ => None,
+        };
+        let value = match key.column {
+            TaskSortColumnEnum::Deadline => value.map(julianday),
+            _ => value,
+        };
+
+        let after = match &value {
+            Some(value) => {
+                let beyond = match key.order {
+                    OrderByEnum::Asc => column().gt(value.clone()),
+                    OrderByEnum::Desc => column().lt(value.clone()),
+                };
+                match key.nulls_first {
+                    true => Cond::all().add(beyond),
+                    false => Cond::any().add(beyond).add(column().is_null()),
+                }
+            }
+            None => match key.nulls_first {
+                true => Cond::all().add(column().is_not_null()),
+                false => Cond::all().add(Expr::value(false)),
+            },
+        };
+        any = any.add(equal.clone().add(after));
 
-        Ok(count)
+        equal = match value {
+            Some(value) => equal.add(column().eq(value)),
+            None => equal.add(column().is_null()),
+        };
     }
+
+    Ok(any.add(equal.add(Expr::col(TaskIden::Id).gt(cursor.id))))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use anyhow::Result;
     use rusqlite::Connection;
+    use time::macros::{date, datetime};
 
     use crate::{
-        models::{setup_database, AddTask, TaskStatusEnum, UpdateTask},
+        models::{
+            setup_database, AddTask, DeadlineEnum, OrderByEnum, QueryTaskPayload,
+            SearchTaskPayload, TaskCursor, TaskPage, TaskPriorityEnum, TaskSortColumnEnum,
+            TaskSortKey, TaskStatusEnum, UpdateTask,
+        },
         repositories::get_now,
+        search::fts_query,
     };
 
     use super::TaskRepository;
@@ -426,6 +943,9 @@ mod tests {
             deadline: None,
             categories: None,
             status: TaskStatusEnum::Undone,
+            priority: TaskPriorityEnum::Normal,
+            parent_id: None,
+            recurrence: None,
             created_at: now,
         })?;
         assert_eq!(1, task.id);
@@ -442,24 +962,24 @@ mod tests {
                 info: None,
                 deadline: None,
                 status: None,
+                priority: None,
+                recurrence: None,
                 created_at: None,
             },
             &now.to_string(),
         );
 
-        if let Err(_) = res {
-            assert!(true);
-        } else {
-            assert!(false);
-        }
+        assert!(res.is_err());
 
         repository.update_task(
             1,
             UpdateTask {
                 title: Some("New Title".into()),
                 info: Some(Some("Test".into())),
-                deadline: Some(Some(now)),
+                deadline: Some(Some(now.into())),
                 status: Some(TaskStatusEnum::Done),
+                priority: Some(TaskPriorityEnum::High),
+                recurrence: None,
                 created_at: Some(now),
             },
             &now.to_string(),
@@ -482,6 +1002,9 @@ mod tests {
                 deadline: None,
                 categories: None,
                 status: crate::models::TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
                 created_at: now,
             },
         )?;
@@ -492,4 +1015,529 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_query_tasks_priority() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+
+        let repository = TaskRepository::create(&conn);
+
+        let priorities = [
+            TaskPriorityEnum::High,
+            TaskPriorityEnum::Low,
+            TaskPriorityEnum::Critical,
+            TaskPriorityEnum::Normal,
+            TaskPriorityEnum::High,
+        ];
+        for (index, priority) in priorities.iter().enumerate() {
+            repository.create_task(AddTask {
+                title: format!("Task {}", index),
+                info: None,
+                deadline: None,
+                categories: None,
+                status: TaskStatusEnum::Undone,
+                priority: *priority,
+                parent_id: None,
+                recurrence: None,
+                created_at: now,
+            })?;
+        }
+
+        let payload = QueryTaskPayload {
+            status: None,
+            priority: None,
+            any_categories: None,
+            all_categories: None,
+            exclude_categories: None,
+            text: None,
+            due_before: None,
+            due_after: None,
+            overdue: false,
+            no_deadline: false,
+            created_since: None,
+            updated_since: None,
+            blocked: false,
+            ready: false,
+            filter: None,
+            limit: 10,
+            offset: 0,
+            after: None,
+            sort: vec![],
+        };
+
+        // test filter
+        let tasks = repository.query_tasks(QueryTaskPayload {
+            priority: Some(TaskPriorityEnum::High),
+            ..payload.clone()
+        })?;
+        let ids: Vec<i64> = tasks.tasks.iter().map(|task| task.id).collect();
+        assert_eq!(vec![1, 5], ids);
+        assert_eq!(2, tasks.total);
+
+        // test sort
+        let tasks = repository.query_tasks(QueryTaskPayload {
+            sort: vec![TaskSortKey::create(
+                TaskSortColumnEnum::Priority,
+                OrderByEnum::Desc,
+            )],
+            ..payload
+        })?;
+        let sorted: Vec<TaskPriorityEnum> = tasks.tasks.iter().map(|task| task.priority).collect();
+        assert_eq!(
+            vec![
+                TaskPriorityEnum::Critical,
+                TaskPriorityEnum::High,
+                TaskPriorityEnum::High,
+                TaskPriorityEnum::Normal,
+                TaskPriorityEnum::Low,
+            ],
+            sorted
+        );
+
+        // test update
+        repository.update_task(
+            2,
+            UpdateTask {
+                title: None,
+                info: None,
+                deadline: None,
+                status: None,
+                priority: Some(TaskPriorityEnum::Critical),
+                recurrence: None,
+                created_at: None,
+            },
+            &now.to_string(),
+        )?;
+        let task = repository.get_task(2)?.unwrap();
+        assert_eq!(TaskPriorityEnum::Critical, task.priority);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_tasks_pagination() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let now = get_now();
+
+        let repository = TaskRepository::create(&conn);
+        let new_task = |priority: TaskPriorityEnum| AddTask {
+            title: "Task".into(),
+            info: None,
+            deadline: None,
+            categories: None,
+            status: TaskStatusEnum::Undone,
+            priority,
+            parent_id: None,
+            recurrence: None,
+            created_at: now,
+        };
+
+        let priorities = [
+            TaskPriorityEnum::Low,
+            TaskPriorityEnum::High,
+            TaskPriorityEnum::Normal,
+            TaskPriorityEnum::High,
+            TaskPriorityEnum::Low,
+        ];
+        for priority in priorities {
+            repository.create_task(new_task(priority))?;
+        }
+
+        let payload = QueryTaskPayload {
+            status: None,
+            priority: None,
+            any_categories: None,
+            all_categories: None,
+            exclude_categories: None,
+            text: None,
+            due_before: None,
+            due_after: None,
+            overdue: false,
+            no_deadline: false,
+            created_since: None,
+            updated_since: None,
+            blocked: false,
+            ready: false,
+            filter: None,
+            limit: 2,
+            offset: 0,
+            after: None,
+            sort: vec![TaskSortKey::create(
+                TaskSortColumnEnum::Priority,
+                OrderByEnum::Desc,
+            )],
+        };
+        let ids = |page: &TaskPage| -> Vec<i64> { page.tasks.iter().map(|task| task.id).collect() };
+
+        // test offset, ties are ordered by id
+        let page = repository.query_tasks(QueryTaskPayload {
+            offset: 2,
+            ..payload.clone()
+        })?;
+        assert_eq!(vec![3, 1], ids(&page));
+        assert_eq!((5, 2), (page.total, page.start));
+
+        let page = repository.query_tasks(QueryTaskPayload {
+            offset: 8,
+            ..payload.clone()
+        })?;
+        assert!(page.tasks.is_empty());
+        assert_eq!(None, page.next_cursor);
+
+        // test cursors walk all the tasks
+        let first = repository.query_tasks(payload.clone())?;
+        assert_eq!(vec![2, 4], ids(&first));
+        assert_eq!(0, first.start);
+
+        // test a task inserted before the cursor does not shift the next page
+        repository.create_task(new_task(TaskPriorityEnum::Critical))?;
+
+        let cursor = TaskCursor::decode(&first.next_cursor.unwrap().encode())?;
+        let second = repository.query_tasks(QueryTaskPayload {
+            after: Some(cursor),
+            ..payload.clone()
+        })?;
+        assert_eq!(vec![3, 1], ids(&second));
+        assert_eq!((6, 3), (second.total, second.start));
+
+        let third = repository.query_tasks(QueryTaskPayload {
+            after: second.next_cursor,
+            ..payload.clone()
+        })?;
+        assert_eq!(vec![5], ids(&third));
+        assert_eq!(5, third.start);
+        assert_eq!(None, third.next_cursor);
+
+        // test a cursor only works with the sort it was created with
+        let cursor = repository.query_tasks(payload.clone())?.next_cursor;
+        assert!(repository
+            .query_tasks(QueryTaskPayload {
+                after: cursor,
+                sort: vec![TaskSortKey::create(
+                    TaskSortColumnEnum::Priority,
+                    OrderByEnum::Asc
+                )],
+                ..payload
+            })
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_tasks_sort() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let repository = TaskRepository::create(&conn);
+
+        let tasks = [
+            (
+                "Buy milk",
+                None,
+                TaskPriorityEnum::Low,
+                date!(2024 - 01 - 03),
+            ),
+            (
+                "Write report",
+                Some(date!(2024 - 02 - 01)),
+                TaskPriorityEnum::High,
+                date!(2024 - 01 - 01),
+            ),
+            (
+                "Call bank",
+                Some(date!(2024 - 01 - 15)),
+                TaskPriorityEnum::Normal,
+                date!(2024 - 01 - 05),
+            ),
+            (
+                "Answer mail",
+                Some(date!(2024 - 02 - 01)),
+                TaskPriorityEnum::High,
+                date!(2024 - 01 - 02),
+            ),
+            (
+                "Clean desk",
+                None,
+                TaskPriorityEnum::High,
+                date!(2024 - 01 - 04),
+            ),
+            (
+                "Pay rent",
+                Some(date!(2024 - 02 - 01)),
+                TaskPriorityEnum::Critical,
+                date!(2024 - 01 - 02),
+            ),
+        ];
+        for (title, deadline, priority, created_at) in tasks {
+            repository.create_task(AddTask {
+                title: title.into(),
+                info: None,
+                deadline: deadline.map(DeadlineEnum::Date),
+                categories: None,
+                status: TaskStatusEnum::Undone,
+                priority,
+                parent_id: None,
+                recurrence: None,
+                created_at,
+            })?;
+        }
+
+        let payload = QueryTaskPayload {
+            status: None,
+            priority: None,
+            any_categories: None,
+            all_categories: None,
+            exclude_categories: None,
+            text: None,
+            due_before: None,
+            due_after: None,
+            overdue: false,
+            no_deadline: false,
+            created_since: None,
+            updated_since: None,
+            blocked: false,
+            ready: false,
+            filter: None,
+            limit: 10,
+            offset: 0,
+            after: None,
+            sort: vec![],
+        };
+        let query_ids = |sort: &str, limit: u64| -> Result<Vec<i64>> {
+            let sort = sort
+                .split(',')
+                .map(TaskSortKey::from_str)
+                .collect::<Result<Vec<_>>>()?;
+            Ok(repository
+                .query_tasks(QueryTaskPayload {
+                    sort,
+                    limit,
+                    ..payload.clone()
+                })?
+                .tasks
+                .iter()
+                .map(|task| task.id)
+                .collect())
+        };
+
+        // test every key is applied in order, missing deadlines last
+        assert_eq!(
+            vec![3, 6, 4, 2, 5, 1],
+            query_ids("deadline:asc,priority:desc,title", 10)?
+        );
+        assert_eq!(
+            vec![5, 1, 2, 6, 4, 3],
+            query_ids("deadline:desc:nulls-first,title:desc", 10)?
+        );
+        assert_eq!(vec![2, 4, 6, 3], query_ids("deadline:desc", 4)?);
+
+        // test single columns
+        assert_eq!(vec![4, 1, 3, 5, 6, 2], query_ids("title", 10)?);
+        assert_eq!(vec![2, 6, 5, 3, 1, 4], query_ids("title:desc", 10)?);
+        assert_eq!(vec![6, 2, 4, 5, 3, 1], query_ids("priority:desc", 10)?);
+        assert_eq!(vec![3, 5, 1, 4, 6, 2], query_ids("created:desc", 10)?);
+
+        // test ties are ordered by id
+        assert_eq!(vec![2, 4, 6, 1, 5, 3], query_ids("status,updated", 10)?);
+        assert_eq!(vec![6, 5, 4, 3, 2, 1], query_ids("id:desc", 10)?);
+
+        // test walking pages with cursors gives the same order as a single query
+        for sort in [
+            "deadline:asc,priority:desc,title",
+            "deadline:desc:nulls-first,title:desc",
+            "deadline:asc:nulls-first,created",
+            "priority,id:desc",
+        ] {
+            let keys = sort
+                .split(',')
+                .map(TaskSortKey::from_str)
+                .collect::<Result<Vec<_>>>()?;
+            let mut ids = vec![];
+            let mut after = None;
+            loop {
+                let page = repository.query_tasks(QueryTaskPayload {
+                    sort: keys.clone(),
+                    limit: 1,
+                    after,
+                    ..payload.clone()
+                })?;
+                ids.extend(page.tasks.iter().map(|task| task.id));
+                match page.next_cursor {
+                    Some(cursor) => after = Some(cursor),
+                    None => break,
+                }
+            }
+            assert_eq!(query_ids(sort, 10)?, ids, "{}", sort);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_tasks_sort_deadline_offsets() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let repository = TaskRepository::create(&conn);
+
+        let deadlines = [
+            DeadlineEnum::DateTime(datetime!(2024 - 02 - 10 08:00 +05:00)),
+            DeadlineEnum::DateTime(datetime!(2024 - 02 - 10 04:00 UTC)),
+            DeadlineEnum::DateTime(datetime!(2024 - 02 - 10 01:00 -03:00)),
+            DeadlineEnum::DateTime(datetime!(2024 - 02 - 09 23:30 -05:00)),
+            DeadlineEnum::Date(date!(2024 - 02 - 10)),
+        ];
+        for deadline in deadlines {
+            repository.create_task(AddTask {
+                title: "Task".into(),
+                info: None,
+                deadline: Some(deadline),
+                categories: None,
+                status: TaskStatusEnum::Undone,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                created_at: get_now(),
+            })?;
+        }
+
+        let payload = QueryTaskPayload {
+            status: None,
+            priority: None,
+            any_categories: None,
+            all_categories: None,
+            exclude_categories: None,
+            text: None,
+            due_before: None,
+            due_after: None,
+            overdue: false,
+            no_deadline: false,
+            created_since: None,
+            updated_since: None,
+            blocked: false,
+            ready: false,
+            filter: None,
+            limit: 10,
+            offset: 0,
+            after: None,
+            sort: vec![],
+        };
+
+        // test deadlines are ordered by moment whatever their offset, on every page
+        for (order, expected) in [
+            (OrderByEnum::Asc, vec![5, 1, 2, 3, 4]),
+            (OrderByEnum::Desc, vec![4, 2, 3, 1, 5]),
+        ] {
+            let sort = vec![TaskSortKey::create(TaskSortColumnEnum::Deadline, order)];
+            let page = repository.query_tasks(QueryTaskPayload {
+                sort: sort.clone(),
+                ..payload.clone()
+            })?;
+            let ids: Vec<i64> = page.tasks.iter().map(|task| task.id).collect();
+            assert_eq!(expected, ids);
+
+            let mut ids = vec![];
+            let mut after = None;
+            loop {
+                let page = repository.query_tasks(QueryTaskPayload {
+                    sort: sort.clone(),
+                    limit: 2,
+                    after,
+                    ..payload.clone()
+                })?;
+                ids.extend(page.tasks.iter().map(|task| task.id));
+                match page.next_cursor {
+                    Some(cursor) => after = Some(cursor),
+                    None => break,
+                }
+            }
+            assert_eq!(expected, ids);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_tasks() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let repository = TaskRepository::create(&conn);
+        let add = |title: &str, info: Option<&str>, status: TaskStatusEnum| {
+            repository.create_task(AddTask {
+                title: title.into(),
+                info: info.map(String::from),
+                deadline: None,
+                categories: None,
+                status,
+                priority: TaskPriorityEnum::Normal,
+                parent_id: None,
+                recurrence: None,
+                created_at: get_now(),
+            })
+        };
+
+        add(
+            "Write release notes",
+            Some("Mention the deploy changes"),
+            TaskStatusEnum::Undone,
+        )?;
+        add("Deploy api", None, TaskStatusEnum::Undone)?;
+        add(
+            "Fix login bug",
+            Some("The login page breaks after a deployment"),
+            TaskStatusEnum::Done,
+        )?;
+        add(
+            "Plan the offsite",
+            Some("Book a venue"),
+            TaskStatusEnum::Undone,
+        )?;
+
+        let search = |input: &str, status: Option<TaskStatusEnum>| {
+            repository.search_tasks(SearchTaskPayload {
+                query: fts_query(input)?,
+                status,
+                limit: 10,
+                marks: ("[".into(), "]".into()),
+            })
+        };
+        let ids = |input: &str, status: Option<TaskStatusEnum>| -> Result<Vec<i64>> {
+            Ok(search(input, status)?
+                .iter()
+                .map(|result| result.task.id)
+                .collect())
+        };
+
+        // test title matches rank before info matches
+        assert_eq!(vec![2, 1], ids("deploy", None)?);
+        assert_eq!(vec![2, 1, 3], ids("deplo*", None)?);
+        assert_eq!(vec![2, 1], ids("deplo*", Some(TaskStatusEnum::Undone))?);
+        assert_eq!(vec![3], ids("\"login page\"", None)?);
+        assert_eq!(Vec::<i64>::new(), ids("\"page login\"", None)?);
+        assert_eq!(vec![2], ids("title:deplo*", None)?);
+        assert_eq!(vec![2, 1], ids("deplo* -login", None)?);
+        assert_eq!(vec![3, 4], ids("bug OR venue", None)?);
+
+        // test stray quotes and operators are searched as text
+        assert_eq!(vec![3], ids("login\" (bug", None)?);
+
+        // test the matched words are marked
+        let results = search("deploy*", None)?;
+        assert_eq!("[Deploy] api", results[0].title);
+        assert_eq!(None, results[0].snippet);
+        assert_eq!(
+            Some("Mention the [deploy] changes".to_string()),
+            results[1].snippet
+        );
+        assert!(results[0].score > results[1].score);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,178 @@
+use anyhow::Result;
+use rusqlite::Connection;
+use sea_query::{Expr, OnConflict, Query, SqliteQueryBuilder};
+use time::Date;
+
+use crate::models::{View, ViewIden, ViewQuery};
+
+use super::bind;
+
+const VIEW_COLUMNS: [ViewIden; 4] = [
+    ViewIden::Name,
+    ViewIden::Query,
+    ViewIden::UpdatedAt,
+    ViewIden::CreatedAt,
+];
+
+fn view_from_row(row: &rusqlite::Row) -> rusqlite::Result<View> {
+    let query: String = row.get(1)?;
+
+    Ok(View {
+        name: row.get(0)?,
+        query: serde_json::from_str(&query).map_err(|err| {
+            rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, err.into())
+        })?,
+        updated_at: row.get(2)?,
+        created_at: row.get(3)?,
+    })
+}
+
+/**
+ * Saved view database repository
+ */
+pub struct ViewRepository<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> ViewRepository<'a> {
+    /**
+     * Used to initialize the repository
+     */
+    pub fn create(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /**
+     * Used to save a view, replacing the query of an existing view with the same name
+     */
+    pub fn save_view(&self, name: &str, query: &ViewQuery, now: Date) -> Result<View> {
+        let (sql, values) = Query::insert()
+            .into_table(ViewIden::Table)
+            .columns(VIEW_COLUMNS)
+            .values([
+                name.into(),
+                serde_json::to_string(query)?.into(),
+                now.to_string().into(),
+                now.to_string().into(),
+            ])?
+            .on_conflict(
+                OnConflict::column(ViewIden::Name)
+                    .update_columns([ViewIden::Query, ViewIden::UpdatedAt])
+                    .to_owned(),
+            )
+            .build(SqliteQueryBuilder);
+
+        self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        self.get_view(name)?
+            .ok_or_else(|| anyhow::anyhow!("View [{}] was not saved", name))
+    }
+
+    /**
+     * Used to get a view by name
+     */
+    pub fn get_view(&self, name: &str) -> Result<Option<View>> {
+        let (sql, values) = Query::select()
+            .from(ViewIden::Table)
+            .columns(VIEW_COLUMNS)
+            .and_where(Expr::col(ViewIden::Name).eq(name))
+            .build(SqliteQueryBuilder);
+
+        match self
+            .conn
+            .prepare_cached(&sql)?
+            .query_row(bind(&values), view_from_row)
+        {
+            Ok(view) => Ok(Some(view)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /**
+     * Used to fetch all views ordered by name
+     */
+    pub fn fetch_views(&self) -> Result<Vec<View>> {
+        let (sql, values) = Query::select()
+            .from(ViewIden::Table)
+            .columns(VIEW_COLUMNS)
+            .order_by(ViewIden::Name, sea_query::Order::Asc)
+            .build(SqliteQueryBuilder);
+
+        let views = self
+            .conn
+            .prepare_cached(&sql)?
+            .query_map(bind(&values), view_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(views)
+    }
+
+    /**
+     * Used to delete a view, returns false when it does not exist
+     */
+    pub fn delete_view(&self, name: &str) -> Result<bool> {
+        let (sql, values) = Query::delete()
+            .from_table(ViewIden::Table)
+            .and_where(Expr::col(ViewIden::Name).eq(name))
+            .build(SqliteQueryBuilder);
+
+        let deleted = self.conn.prepare_cached(&sql)?.execute(bind(&values))?;
+
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use rusqlite::Connection;
+    use time::macros::date;
+
+    use crate::models::{setup_database, ViewQuery};
+
+    use super::ViewRepository;
+
+    #[test]
+    fn test_crud_views() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        setup_database(&conn)?;
+
+        let repository = ViewRepository::create(&conn);
+        let query = |filter: Option<&str>, due_within: Option<&str>| ViewQuery {
+            due_within: due_within.map(String::from),
+            filter: filter.map(String::from),
+            limit: 10,
+            sort: vec!["deadline:asc:nulls-last".to_string()],
+            ..Default::default()
+        };
+
+        // test save and fetch, the filter keeps its quoting
+        let week = query(Some("due<+7d and text:\"login bug\""), None);
+        let view = repository.save_view("week", &week, date!(2024 - 01 - 01))?;
+        assert_eq!(week, view.query);
+        assert_eq!(Some(view), repository.get_view("week")?);
+        assert_eq!(None, repository.get_view("missing")?);
+
+        // test saving again replaces the query and keeps the creation date
+        repository.save_view("backlog", &query(None, None), date!(2024 - 01 - 02))?;
+        let view = repository.save_view("week", &query(None, Some("7d")), date!(2024 - 01 - 03))?;
+        assert_eq!(query(None, Some("7d")), view.query);
+        assert_eq!(date!(2024 - 01 - 01), view.created_at);
+        assert_eq!(date!(2024 - 01 - 03), view.updated_at);
+
+        let names: Vec<String> = repository
+            .fetch_views()?
+            .into_iter()
+            .map(|view| view.name)
+            .collect();
+        assert_eq!(vec!["backlog", "week"], names);
+
+        // test delete
+        assert!(repository.delete_view("week")?);
+        assert!(!repository.delete_view("week")?);
+        assert_eq!(1, repository.fetch_views()?.len());
+
+        Ok(())
+    }
+}
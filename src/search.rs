@@ -0,0 +1,239 @@
+use anyhow::Result;
+
+/// names a term can be scoped with in `name:term` and the column of `tasks_fts` they search
+const COLUMNS: [(&str, &str); 4] = [
+    ("title", "title"),
+    ("info", "info"),
+    ("cat", "categories"),
+    ("categories", "categories"),
+];
+
+/**
+ * A search term quoted for FTS5
+ */
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    column: Option<&'static str>,
+    text: String,
+    prefix: bool,
+    exclude: bool,
+}
+
+impl Term {
+    /**
+     * Used to write the term as an FTS5 string, so that no user input is read as syntax
+     */
+    fn to_fts(&self) -> String {
+        let mut result = String::new();
+
+        if let Some(column) = self.column {
+            result.push_str(column);
+            result.push_str(" : ");
+        }
+
+        // SQLite reads the query up to a NUL, control characters separate words anyway
+        let text: String = self
+            .text
+            .chars()
+            .map(|char| if char.is_control() { ' ' } else { char })
+            .collect();
+
+        result.push('"');
+        result.push_str(&text.replace('"', "\"\""));
+        result.push('"');
+
+        if self.prefix {
+            result.push_str(" *");
+        }
+
+        result
+    }
+}
+
+/**
+ * Used to build an FTS5 MATCH expression from a search typed by a user, e.g.
+ * `deplo* "login bug" title:api OR -draft`.
+ *
+ * Words and `"phrases"` must all match, `OR` between terms matches either side, a trailing `*`
+ * matches words by prefix, `title:`, `info:` and `cat:` search a single column, a leading `-` or `NOT`
+ * excludes a term. Everything else is quoted, so stray quotes or operators never reach SQLite
+ * as syntax: an unclosed quote ends at the end of the input and terms without any letter or
+ * digit are ignored.
+ */
+pub fn fts_query(input: &str) -> Result<String> {
+    // groups of terms joined with AND, the groups are joined with OR
+    let mut groups: Vec<Vec<Term>> = vec![vec![]];
+    let mut excluded: Vec<Term> = vec![];
+    let mut exclude_next = false;
+
+    for word in split_words(input) {
+        match word.as_str() {
+            "OR" => {
+                if groups.last().is_some_and(|group| !group.is_empty()) {
+                    groups.push(vec![]);
+                }
+                continue;
+            }
+            "AND" => continue,
+            "NOT" => {
+                exclude_next = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some(mut term) = parse_term(&word) else {
+            continue;
+        };
+        term.exclude |= exclude_next;
+        exclude_next = false;
+
+        match term.exclude {
+            true => excluded.push(term),
+            false => groups.last_mut().expect("At least one group").push(term),
+        }
+    }
+
+    groups.retain(|group| !group.is_empty());
+    if groups.is_empty() {
+        anyhow::bail!(
+            "[Invalid search] - [input: {}] - [expected: at least one word that is not excluded]",
+            input
+        );
+    }
+
+    let groups: Vec<String> = groups
+        .iter()
+        .map(|group| {
+            let terms: Vec<String> = group.iter().map(Term::to_fts).collect();
+            format!("({})", terms.join(" AND "))
+        })
+        .collect();
+
+    let mut query = format!("({})", groups.join(" OR "));
+    for term in excluded {
+        query.push_str(" NOT ");
+        query.push_str(&term.to_fts());
+    }
+
+    Ok(query)
+}
+
+/**
+ * Used to split the input on whitespace, keeping quoted text together with its quotes
+ */
+fn split_words(input: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut quoted = false;
+
+    for char in input.chars() {
+        match char {
+            '"' => {
+                quoted = !quoted;
+                word.push(char);
+            }
+            char if char.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            char => word.push(char),
+        }
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/**
+ * Used to parse a single word, `None` when it has nothing to search for
+ */
+fn parse_term(word: &str) -> Option<Term> {
+    let mut rest = word;
+
+    let exclude = rest.starts_with('-');
+    if exclude {
+        rest = &rest[1..];
+    }
+
+    let mut column = None;
+    if let Some((name, value)) = rest.split_once(':') {
+        if let Some((_, found)) = COLUMNS
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(name))
+        {
+            column = Some(*found);
+            rest = value;
+        }
+    }
+
+    let prefix = rest.ends_with('*');
+    if prefix {
+        rest = &rest[..rest.len() - 1];
+    }
+
+    let text = match rest.strip_prefix('"') {
+        Some(quoted) => quoted.strip_suffix('"').unwrap_or(quoted),
+        None => rest,
+    };
+
+    if !text.chars().any(char::is_alphanumeric) {
+        return None;
+    }
+
+    Some(Term {
+        column,
+        text: text.to_string(),
+        prefix,
+        exclude,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fts_query;
+
+    #[test]
+    fn test_fts_query() {
+        let query = |input: &str| fts_query(input).ok();
+
+        assert_eq!(Some("((\"login\"))".to_string()), query("login"));
+        assert_eq!(
+            Some("((\"deplo\" * AND \"login bug\"))".to_string()),
+            query("deplo* \"login bug\"")
+        );
+        assert_eq!(
+            Some("((title : \"api\") OR (info : \"deploy\" *))".to_string()),
+            query("Title:api OR info:deploy*")
+        );
+        assert_eq!(
+            Some("((categories : \"ops\"))".to_string()),
+            query("cat:ops")
+        );
+        assert_eq!(
+            Some("((\"api\")) NOT \"draft\" NOT title : \"old\"".to_string()),
+            query("api -draft NOT title:old")
+        );
+
+        // test user input is never read as syntax
+        assert_eq!(
+            Some("((\"a\" AND \"it\"\"s AND or(\"))".to_string()),
+            query("a AND it\"s AND or(")
+        );
+        assert_eq!(
+            Some("((\"unclosed phrase\"))".to_string()),
+            query("\"unclosed phrase")
+        );
+        assert_eq!(Some("((\"x:y\"))".to_string()), query("x:y ( \"\" OR"));
+
+        assert_eq!(Some("((\"nul byte\"))".to_string()), query("nul\0byte"));
+
+        assert_eq!(None, query(""));
+        assert_eq!(None, query("-draft"));
+        assert_eq!(None, query("\"\" * OR"));
+    }
+}
@@ -0,0 +1,398 @@
+use std::io::IsTerminal;
+
+use anyhow::Result;
+use rusqlite::Connection;
+use time::OffsetDateTime;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::{
+    models::{Task, TaskColumnEnum, TaskPriorityEnum, TaskStatusEnum},
+    repositories::category_repository::CategoryRepository,
+    utils::tree_prefix,
+};
+
+/// space between two columns
+const COLUMN_GAP: &str = "  ";
+/// columns are never truncated below this width
+const MIN_COLUMN_WIDTH: usize = 6;
+
+/**
+ * Terminal colors used by the table renderer
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorEnum {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Gray,
+}
+
+impl ColorEnum {
+    fn code(&self) -> &'static str {
+        match self {
+            ColorEnum::Red => "\x1b[31m",
+            ColorEnum::Green => "\x1b[32m",
+            ColorEnum::Yellow => "\x1b[33m",
+            ColorEnum::Blue => "\x1b[34m",
+            ColorEnum::Gray => "\x1b[90m",
+        }
+    }
+}
+
+/**
+ * A table cell, the color is only applied when colors are enabled
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    pub text: String,
+    pub color: Option<ColorEnum>,
+}
+
+impl Cell {
+    pub fn create(text: impl Into<String>, color: Option<ColorEnum>) -> Self {
+        Self {
+            text: text.into(),
+            color,
+        }
+    }
+}
+
+/**
+ * A table column, flexible columns are truncated first when the table does not fit
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub header: String,
+    pub flexible: bool,
+}
+
+impl Column {
+    pub fn create(header: impl Into<String>, flexible: bool) -> Self {
+        Self {
+            header: header.into(),
+            flexible,
+        }
+    }
+}
+
+/**
+ * Column aligned table, shrinking its widest columns to fit the terminal
+ */
+#[derive(Debug)]
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    /**
+     * Used to initialize an empty table
+     */
+    pub fn create(columns: Vec<Column>) -> Self {
+        Self {
+            columns,
+            rows: vec![],
+        }
+    }
+
+    /**
+     * Used to append a row, it must have a cell per header
+     */
+    pub fn push_row(&mut self, row: Vec<Cell>) {
+        debug_assert_eq!(self.columns.len(), row.len());
+        self.rows.push(row);
+    }
+
+    /**
+     * Used to render the table, truncating cells when it is wider than `max_width`
+     */
+    pub fn render(&self, max_width: Option<usize>, color: bool) -> String {
+        let mut widths: Vec<usize> = self
+            .columns
+            .iter()
+            .map(|column| column.header.width())
+            .collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.text.width());
+            }
+        }
+
+        if let Some(max_width) = max_width {
+            let gaps = COLUMN_GAP.len() * widths.len().saturating_sub(1);
+            // shrink the widest flexible column first, then the widest of any column
+            for only_flexible in [true, false] {
+                while widths.iter().sum::<usize>() + gaps > max_width {
+                    match widths
+                        .iter_mut()
+                        .zip(&self.columns)
+                        .filter(|(width, column)| {
+                            **width > MIN_COLUMN_WIDTH && (column.flexible || !only_flexible)
+                        })
+                        .map(|(width, _)| width)
+                        .max()
+                    {
+                        Some(width) => *width -= 1,
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        let mut output = String::new();
+
+        let headers: Vec<Cell> = self
+            .columns
+            .iter()
+            .map(|column| Cell::create(column.header.as_str(), None))
+            .collect();
+        render_row(&mut output, &headers, &widths, color);
+
+        let rule: Vec<Cell> = widths
+            .iter()
+            .map(|width| Cell::create("-".repeat(*width), None))
+            .collect();
+        render_row(&mut output, &rule, &widths, false);
+
+        for row in &self.rows {
+            render_row(&mut output, row, &widths, color);
+        }
+
+        output
+    }
+}
+
+fn render_row(output: &mut String, row: &[Cell], widths: &[usize], color: bool) {
+    let mut line = String::new();
+
+    for (index, (cell, width)) in row.iter().zip(widths).enumerate() {
+        if index > 0 {
+            line.push_str(COLUMN_GAP);
+        }
+
+        let text = truncate(&cell.text, *width);
+        let padding = " ".repeat(width - text.width());
+        match cell.color {
+            Some(cell_color) if color && !text.is_empty() => {
+                line.push_str(cell_color.code());
+                line.push_str(&text);
+                line.push_str("\x1b[0m");
+            }
+            _ => line.push_str(&text),
+        }
+        line.push_str(&padding);
+    }
+
+    output.push_str(line.trim_end());
+    output.push('\n');
+}
+
+/**
+ * Used to cut a text to a display width, marking the cut with an ellipsis
+ */
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut result = String::new();
+    let mut used = 0;
+    for char in text.chars() {
+        let char_width = char.width().unwrap_or(0);
+        if used + char_width + 1 > width {
+            break;
+        }
+        result.push(char);
+        used += char_width;
+    }
+    if width > 0 {
+        result.push('…');
+    }
+
+    result
+}
+
+/**
+ * Used to check if colors should be used, honoring `NO_COLOR` and disabling them when piped
+ */
+pub fn use_colors() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+
+    !no_color && std::io::stdout().is_terminal()
+}
+
+/**
+ * Used to get the width of the terminal, `None` when stdout is not a terminal
+ */
+pub fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    terminal_size::terminal_size().map(|(width, _)| width.0 as usize)
+}
+
+/**
+ * Used to build the table of a task tree with the selected columns
+ */
+pub fn task_table(
+    conn: &Connection,
+    tasks: Vec<(usize, Task)>,
+    columns: &[TaskColumnEnum],
+    now: OffsetDateTime,
+) -> Result<Table> {
+    let category_repository = CategoryRepository::create(conn);
+
+    let mut table = Table::create(
+        columns
+            .iter()
+            .map(|column| Column::create(column.header(), column.is_flexible()))
+            .collect(),
+    );
+
+    for (depth, task) in tasks {
+        let mut row = Vec::with_capacity(columns.len());
+
+        for column in columns {
+            let cell = match column {
+                TaskColumnEnum::Id => Cell::create(format!("#{}", task.id), None),
+                TaskColumnEnum::Title => {
+                    Cell::create(format!("{}{}", tree_prefix(depth), task.title), None)
+                }
+                TaskColumnEnum::Status => Cell::create(
+                    task.status.to_string(),
+                    Some(match task.status {
+                        TaskStatusEnum::Done => ColorEnum::Green,
+                        TaskStatusEnum::Undone => ColorEnum::Yellow,
+                        TaskStatusEnum::Archived => ColorEnum::Gray,
+                    }),
+                ),
+                TaskColumnEnum::Priority => Cell::create(
+                    task.priority.to_string(),
+                    match task.priority {
+                        TaskPriorityEnum::Critical => Some(ColorEnum::Red),
+                        TaskPriorityEnum::High => Some(ColorEnum::Yellow),
+                        TaskPriorityEnum::Normal => None,
+                        TaskPriorityEnum::Low => Some(ColorEnum::Gray),
+                    },
+                ),
+                TaskColumnEnum::Deadline => match task.deadline {
+                    Some(deadline) => Cell::create(
+                        deadline.to_string(),
+                        match deadline.is_overdue(now) && task.status == TaskStatusEnum::Undone {
+                            true => Some(ColorEnum::Red),
+                            false => None,
+                        },
+                    ),
+                    None => Cell::create("", None),
+                },
+                TaskColumnEnum::Categories => Cell::create(
+                    category_repository
+                        .fetch_task_categories(task.id)?
+                        .join(", "),
+                    Some(ColorEnum::Blue),
+                ),
+                TaskColumnEnum::Repeat => Cell::create(
+                    task.recurrence
+                        .as_ref()
+                        .map(|v| v.to_string())
+                        .unwrap_or_default(),
+                    None,
+                ),
+                TaskColumnEnum::Created => Cell::create(task.created_at.to_string(), None),
+                TaskColumnEnum::Updated => Cell::create(task.updated_at.to_string(), None),
+            };
+            row.push(cell);
+        }
+
+        table.push_row(row);
+    }
+
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{truncate, Cell, ColorEnum, Column, Table};
+
+    fn table() -> Table {
+        let mut table = Table::create(vec![
+            Column::create("ID", false),
+            Column::create("TITLE", true),
+            Column::create("STATUS", false),
+        ]);
+        table.push_row(vec![
+            Cell::create("#1", None),
+            Cell::create("Write the quarterly report", None),
+            Cell::create("undone", Some(ColorEnum::Yellow)),
+        ]);
+        table.push_row(vec![
+            Cell::create("#12", None),
+            Cell::create("└─ Draft", None),
+            Cell::create("done", Some(ColorEnum::Green)),
+        ]);
+        table
+    }
+
+    #[test]
+    fn test_render_table() {
+        assert_eq!(
+            "ID   TITLE                       STATUS\n\
+             ---  --------------------------  ------\n\
+             #1   Write the quarterly report  undone\n\
+             #12  └─ Draft                    done\n",
+            table().render(None, false)
+        );
+
+        // test truncation shrinks the widest column
+        let lines: Vec<String> = table()
+            .render(Some(29), false)
+            .lines()
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            vec![
+                format!("ID   {:16}  STATUS", "TITLE"),
+                format!("---  {}  ------", "-".repeat(16)),
+                format!("#1   {:16}  undone", "Write the quart…"),
+                format!("#12  {:16}  done", "└─ Draft"),
+            ],
+            lines
+        );
+        assert!(lines.iter().all(|line| line.chars().count() <= 29));
+
+        // test columns are never shrunk below the minimum width
+        let lines: Vec<String> = table()
+            .render(Some(17), false)
+            .lines()
+            .map(String::from)
+            .collect();
+        assert_eq!(
+            vec![
+                "ID   TITLE   STATUS".to_string(),
+                "---  ------  ------".to_string(),
+                "#1   Write…  undone".to_string(),
+                "#12  └─ Dr…  done".to_string(),
+            ],
+            lines
+        );
+
+        // test colors
+        let colored = table().render(None, true);
+        assert!(colored.contains("\x1b[33mundone\x1b[0m"));
+        assert!(colored.contains("\x1b[32mdone\x1b[0m"));
+        assert!(!table().render(None, false).contains('\x1b'));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!("hello", truncate("hello", 5));
+        assert_eq!("hel…", truncate("hello", 4));
+        assert_eq!("…", truncate("hello", 1));
+        assert_eq!("", truncate("hello", 0));
+        // wide characters take two columns
+        assert_eq!("日本…", truncate("日本語テキスト", 6));
+        assert_eq!("日…", truncate("日本語テキスト", 4));
+    }
+}
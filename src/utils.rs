@@ -1,5 +1,20 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::OnceLock,
+};
+
 use anyhow::Result;
-use time::{macros::format_description, Date};
+use time::{
+    macros::format_description, Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time,
+    UtcOffset, Weekday,
+};
+
+use crate::{
+    filter::parse_filter,
+    models::{DeadlineEnum, RecurrenceEnum, Task, TaskCursor, TaskFilter, TaskSortKey},
+    search::fts_query,
+};
 
 /**
  * Used to ask user for confirmation of action
@@ -9,7 +24,7 @@ pub fn ask_permission(message: &str, force: bool) -> Result<bool> {
         return Ok(true);
     }
 
-    println!("{}", message);
+    eprintln!("{}", message);
 
     let mut input = String::new();
 
@@ -20,11 +35,9 @@ pub fn ask_permission(message: &str, force: bool) -> Result<bool> {
     match trimmed_input.as_str() {
         "y" | "yes" => Ok(true),
         "n" | "no" => Ok(false),
-        _ => {
-            return Err(
-                anyhow::anyhow!("Invalid input. Please enter 'y', 'n', 'Y', or 'N'.").into(),
-            )
-        }
+        _ => Err(anyhow::anyhow!(
+            "Invalid input. Please enter 'y', 'n', 'Y', or 'N'."
+        )),
     }
 }
 
@@ -32,40 +45,323 @@ pub fn get_date_format() -> &'static [time::format_description::BorrowedFormatIt
     format_description!("[year]-[month]-[day]")
 }
 
+/**
+ * Used to parse a date expression relative to the current day
+ */
 pub fn date_parser(value: &str) -> Result<Date> {
+    relative_date_parser(value, get_today())
+}
+
+/**
+ * Used to parse the last day of a period starting today
+ */
+pub fn period_end_parser(value: &str) -> Result<Date> {
+    relative_period_end_parser(value, get_today())
+}
+
+/**
+ * Used to parse the last day of a period starting at `today`,
+ * either an amount like `7d`, `2w`, `1m` or `1y`, or any date expression
+ */
+pub fn relative_period_end_parser(value: &str, today: Date) -> Result<Date> {
+    let value = value.trim();
+
+    match value.starts_with(|char: char| char.is_ascii_digit()) {
+        true => relative_date_parser(&format!("+{}", value), today),
+        false => relative_date_parser(value, today),
+    }
+}
+
+/**
+ * Used to parse an age as the last day old enough, counted back from the current day
+ */
+pub fn age_parser(value: &str) -> Result<Date> {
+    relative_age_parser(value, get_today())
+}
+
+/**
+ * Used to parse an age like `14d`, `2w`, `3m` or `1y` as the last day old enough,
+ * counted back from `today`
+ */
+pub fn relative_age_parser(value: &str, today: Date) -> Result<Date> {
+    let value = value.trim();
+
+    let amount = value.starts_with(|char: char| char.is_ascii_digit())
+        && value.chars().all(|char| char.is_ascii_alphanumeric());
+
+    match amount {
+        true => relative_date_parser(&format!("-{}", value), today).ok(),
+        false => None,
+    }
+    .ok_or_else(|| {
+        anyhow::anyhow!(
+            "[Invalid age] - [input: {}] - [expected: 14d, 2w, 3m, 1y]",
+            value
+        )
+    })
+}
+
+/**
+ * Used to parse a date expression relative to `today`, accepts:
+ * `YYYY-MM-DD`, `now`, `today`, `tomorrow`, `yesterday`,
+ * `+3d`, `-2w`, `+1m`, `+1y`, `in 3 days`, `2 weeks ago`,
+ * `monday`, `next monday`, `last friday`, `next week`, `next month`, `next year`,
+ * `start of week`, `end of week`, `start of month`, `end of month`, `start of year`, `end of year`
+ */
+pub fn relative_date_parser(value: &str, today: Date) -> Result<Date> {
     let format = get_date_format();
 
-    match Date::parse(&value, &format) {
-        Ok(date) => Ok(date),
-        Err(_) => Err(anyhow::anyhow!(
-            "[Invalid date format ] - [input: {}] - [expected: YYYY-MM-DD]",
+    if let Ok(date) = Date::parse(value.trim(), &format) {
+        return Ok(date);
+    }
+
+    let expression = value
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase();
+    let words: Vec<&str> = expression.split(' ').collect();
+
+    let date = match words.as_slice() {
+        ["now"] | ["today"] => Some(today),
+        ["tomorrow"] => Some(today + Duration::days(1)),
+        ["yesterday"] => Some(today - Duration::days(1)),
+        ["next", "week"] => Some(today + Duration::weeks(1)),
+        ["next", "month"] => add_months(today, 1),
+        ["next", "year"] => add_months(today, 12),
+        ["start", "of", "week"] => {
+            Some(today - Duration::days(today.weekday().number_days_from_monday() as i64))
+        }
+        ["end", "of", "week"] => {
+            Some(today + Duration::days(6 - today.weekday().number_days_from_monday() as i64))
+        }
+        ["start", "of", "month"] => today.replace_day(1).ok(),
+        ["end", "of", "month"] => today.replace_day(today.month().length(today.year())).ok(),
+        ["start", "of", "year"] => Date::from_calendar_date(today.year(), Month::January, 1).ok(),
+        ["end", "of", "year"] => Date::from_calendar_date(today.year(), Month::December, 31).ok(),
+        [offset] if offset.starts_with('+') || offset.starts_with('-') => {
+            let split = offset.len() - 1;
+            if offset.is_char_boundary(split) {
+                let (amount, unit) = offset.split_at(split);
+                offset_date(today, amount, unit)
+            } else {
+                None
+            }
+        }
+        [weekday] | ["next", weekday] => {
+            weekday_parser(weekday).map(|weekday| today.next_occurrence(weekday))
+        }
+        ["last", weekday] => weekday_parser(weekday).map(|weekday| today.prev_occurrence(weekday)),
+        ["in", amount, unit] => offset_date(today, amount, unit),
+        [amount, unit, "ago"] => amount
+            .parse::<i64>()
+            .ok()
+            .and_then(i64::checked_neg)
+            .and_then(|amount| offset_date(today, &amount.to_string(), unit)),
+        _ => None,
+    };
+
+    match date {
+        Some(date) => Ok(date),
+        None => Err(anyhow::anyhow!(
+            "[Invalid date format ] - [input: {}] - [expected: YYYY-MM-DD, today, tomorrow, yesterday, +3d, +2w, next monday, end of month]",
             value
         )),
     }
 }
 
-pub fn optional_date_parser(value: &str) -> Result<Option<Date>> {
-    if value == "" {
+/**
+ * Used to parse a deadline relative to the current moment, see `relative_deadline_parser`
+ */
+pub fn deadline_parser(value: &str) -> Result<DeadlineEnum> {
+    relative_deadline_parser(value, get_local_now())
+}
+
+pub fn optional_deadline_parser(value: &str) -> Result<Option<DeadlineEnum>> {
+    if value.is_empty() {
         return Ok(None);
     }
-    Ok(Some(date_parser(value)?))
+    Ok(Some(deadline_parser(value)?))
+}
+
+/**
+ * Used to parse a deadline relative to `now`, accepts RFC 3339 or any date expression
+ * optionally followed by a time of day, e.g. `tomorrow 14:00`, `friday at 9:30`, `17:00`.
+ * Times of day are set in the offset of `now`.
+ */
+pub fn relative_deadline_parser(value: &str, now: OffsetDateTime) -> Result<DeadlineEnum> {
+    let value = value.trim();
+
+    if let Ok(deadline) = DeadlineEnum::from_str(value) {
+        return Ok(deadline);
+    }
+
+    // allow `YYYY-MM-DDTHH:MM` without an offset
+    let value = match (value.get(..10), value.get(10..11), value.get(11..)) {
+        (Some(date), Some("T" | "t"), Some(time))
+            if Date::parse(date, get_date_format()).is_ok() =>
+        {
+            format!("{} {}", date, time)
+        }
+        _ => value.to_string(),
+    };
+
+    let mut words: Vec<&str> = value.split_whitespace().collect();
+    let time = words.last().and_then(|word| time_parser(word));
+
+    match time {
+        Some(time) => {
+            words.pop();
+            if words.last().map(|word| word.to_lowercase()) == Some("at".to_string()) {
+                words.pop();
+            }
+
+            let date = if words.is_empty() {
+                now.date()
+            } else {
+                relative_date_parser(&words.join(" "), now.date())?
+            };
+
+            Ok(DeadlineEnum::DateTime(
+                PrimitiveDateTime::new(date, time).assume_offset(now.offset()),
+            ))
+        }
+        None => Ok(DeadlineEnum::Date(relative_date_parser(
+            &value,
+            now.date(),
+        )?)),
+    }
+}
+
+fn time_parser(value: &str) -> Option<Time> {
+    Time::parse(
+        value,
+        format_description!("[hour padding:none]:[minute]:[second]"),
+    )
+    .or_else(|_| Time::parse(value, format_description!("[hour padding:none]:[minute]")))
+    .ok()
+}
+
+/**
+ * Used to get the UTC offset that defines the current day.
+ * Set `TODO_CLI_UTC_OFFSET` (e.g. `+02:00`) to override the local offset of the system.
+ */
+pub fn get_utc_offset() -> UtcOffset {
+    static UTC_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+
+    *UTC_OFFSET.get_or_init(|| {
+        std::env::var(UTC_OFFSET_VARIABLE)
+            .ok()
+            .and_then(|value| utc_offset_parser(&value))
+            .or_else(|| UtcOffset::current_local_offset().ok())
+            .unwrap_or(UtcOffset::UTC)
+    })
+}
+
+pub const UTC_OFFSET_VARIABLE: &str = "TODO_CLI_UTC_OFFSET";
+
+fn utc_offset_parser(value: &str) -> Option<UtcOffset> {
+    let value = value.trim();
+
+    if value.eq_ignore_ascii_case("utc") || value.eq_ignore_ascii_case("z") {
+        return Some(UtcOffset::UTC);
+    }
+
+    UtcOffset::parse(
+        value,
+        format_description!("[offset_hour sign:mandatory]:[offset_minute]"),
+    )
+    .ok()
+}
+
+/**
+ * Used to get the current moment in the configured offset
+ */
+pub fn get_local_now() -> OffsetDateTime {
+    OffsetDateTime::now_utc().to_offset(get_utc_offset())
+}
+
+/**
+ * Used to get the current day in the configured offset
+ */
+pub fn get_today() -> Date {
+    get_local_now().date()
+}
+
+/**
+ * Used to move a date by an amount of days, weeks, months or years
+ */
+fn offset_date(date: Date, amount: &str, unit: &str) -> Option<Date> {
+    let amount: i64 = amount.parse().ok()?;
+
+    match unit {
+        "d" | "day" | "days" => date.checked_add(Duration::days(amount)),
+        "w" | "week" | "weeks" => date.checked_add(Duration::weeks(amount)),
+        "m" | "month" | "months" => add_months(date, amount),
+        "y" | "year" | "years" => add_months(date, amount.checked_mul(12)?),
+        _ => None,
+    }
 }
 
 /**
- * Used to convert string "NOW" to Date struct
+ * Used to move a date by months, clamping the day to the length of the target month
+ */
+fn add_months(date: Date, months: i64) -> Option<Date> {
+    let index = date.year() as i64 * 12 + date.month() as i64 - 1 + months;
+    let year = i32::try_from(index.div_euclid(12)).ok()?;
+    let month = Month::try_from(index.rem_euclid(12) as u8 + 1).ok()?;
+    let day = date.day().min(month.length(year));
+
+    Date::from_calendar_date(year, month, day).ok()
+}
+
+fn weekday_parser(value: &str) -> Option<Weekday> {
+    match value {
+        "mon" | "monday" => Some(Weekday::Monday),
+        "tue" | "tuesday" => Some(Weekday::Tuesday),
+        "wed" | "wednesday" => Some(Weekday::Wednesday),
+        "thu" | "thursday" => Some(Weekday::Thursday),
+        "fri" | "friday" => Some(Weekday::Friday),
+        "sat" | "saturday" => Some(Weekday::Saturday),
+        "sun" | "sunday" => Some(Weekday::Sunday),
+        _ => None,
+    }
+}
+
+/**
+ * Used to convert string "NOW" or a date expression to Date struct
  */
 pub fn created_at_parser(value: &str) -> Result<Date> {
-    use std::time::SystemTime;
-    use time::OffsetDateTime;
-
-    if value.eq("NOW") {
-        // Get current time
-        let now = SystemTime::now();
-        let now = OffsetDateTime::from(now).date();
-        Ok(now)
-    } else {
-        date_parser(value)
+    date_parser(value)
+}
+
+pub fn cursor_parser(value: &str) -> Result<TaskCursor> {
+    TaskCursor::decode(value)
+}
+
+pub fn text_parser(value: &str) -> Result<String> {
+    fts_query(value)?;
+
+    Ok(value.to_string())
+}
+
+pub fn filter_parser(value: &str) -> Result<TaskFilter> {
+    parse_filter(value, get_today())
+}
+
+pub fn sort_key_parser(value: &str) -> Result<TaskSortKey> {
+    TaskSortKey::from_str(value)
+}
+
+pub fn recurrence_parser(value: &str) -> Result<RecurrenceEnum> {
+    RecurrenceEnum::from_str(value)
+}
+
+pub fn optional_recurrence_parser(value: &str) -> Result<Option<RecurrenceEnum>> {
+    if value.is_empty() {
+        return Ok(None);
     }
+    Ok(Some(recurrence_parser(value)?))
 }
 
 pub fn title_parser(value: &str) -> Result<String> {
@@ -78,6 +374,11 @@ pub fn category_parser(value: &str) -> Result<String> {
     function(value)
 }
 
+pub fn view_name_parser(value: &str) -> Result<String> {
+    let function = string_len_parser(1, 100);
+    function(value)
+}
+
 pub fn info_parser(value: &str) -> Result<String> {
     let function = string_len_parser(0, 10000);
     function(value)
@@ -87,7 +388,7 @@ pub fn string_len_parser(
     low: usize,
     high: usize,
 ) -> impl Fn(&str) -> Result<std::string::String, anyhow::Error> {
-    return move |value: &str| -> Result<String> {
+    move |value: &str| -> Result<String> {
         let len = value.len();
 
         if len < low || len > high {
@@ -99,5 +400,249 @@ pub fn string_len_parser(
         } else {
             Ok(value.to_string())
         }
+    }
+}
+
+/**
+ * Used to order tasks depth first as a tree, each task is paired with its depth.
+ * Tasks whose parent is not part of the list are treated as roots, siblings keep their order.
+ */
+pub fn task_tree(tasks: Vec<Task>) -> Vec<(usize, Task)> {
+    let ids: HashSet<i64> = tasks.iter().map(|task| task.id).collect();
+
+    let mut roots = vec![];
+    let mut children: HashMap<i64, Vec<Task>> = HashMap::new();
+    for task in tasks {
+        match task.parent_id {
+            Some(parent_id) if ids.contains(&parent_id) => {
+                children.entry(parent_id).or_default().push(task)
+            }
+            _ => roots.push(task),
+        }
+    }
+
+    let mut result = vec![];
+    let mut stack: Vec<(usize, Task)> = roots.into_iter().rev().map(|task| (0, task)).collect();
+    while let Some((depth, task)) = stack.pop() {
+        if let Some(subtasks) = children.remove(&task.id) {
+            stack.extend(subtasks.into_iter().rev().map(|task| (depth + 1, task)));
+        }
+        result.push((depth, task));
+    }
+
+    result
+}
+
+/**
+ * Used to indent a task line according to its depth in the tree
+ */
+pub fn tree_prefix(depth: usize) -> String {
+    if depth == 0 {
+        String::new()
+    } else {
+        format!("{}└─ ", "   ".repeat(depth - 1))
+    }
+}
+
+/**
+ * Used to print a list of task ids as `#1, #2`
+ */
+pub fn format_ids(ids: &[i64]) -> String {
+    ids.iter()
+        .map(|id| format!("#{}", id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use time::macros::{date, datetime};
+
+    use crate::models::DeadlineEnum;
+
+    use super::{
+        relative_age_parser, relative_date_parser, relative_deadline_parser,
+        relative_period_end_parser,
     };
+
+    #[test]
+    fn test_relative_date_parser() -> Result<()> {
+        // 2024-01-31 is a wednesday
+        let today = date!(2024 - 01 - 31);
+
+        let cases = [
+            ("2023-05-06", date!(2023 - 05 - 06)),
+            (" 2023-05-06 ", date!(2023 - 05 - 06)),
+            ("NOW", today),
+            ("today", today),
+            ("Tomorrow", date!(2024 - 02 - 01)),
+            ("yesterday", date!(2024 - 01 - 30)),
+            ("+0d", today),
+            ("+3d", date!(2024 - 02 - 03)),
+            ("-3d", date!(2024 - 01 - 28)),
+            ("+2w", date!(2024 - 02 - 14)),
+            ("+1m", date!(2024 - 02 - 29)),
+            ("+13m", date!(2025 - 02 - 28)),
+            ("-2m", date!(2023 - 11 - 30)),
+            ("+1y", date!(2025 - 01 - 31)),
+            ("in 10 days", date!(2024 - 02 - 10)),
+            ("in 1 week", date!(2024 - 02 - 07)),
+            ("in 2 months", date!(2024 - 03 - 31)),
+            ("3 days ago", date!(2024 - 01 - 28)),
+            ("1 year ago", date!(2023 - 01 - 31)),
+            ("monday", date!(2024 - 02 - 05)),
+            ("next  Monday", date!(2024 - 02 - 05)),
+            ("next wed", date!(2024 - 02 - 07)),
+            ("next thursday", date!(2024 - 02 - 01)),
+            ("last wednesday", date!(2024 - 01 - 24)),
+            ("last sun", date!(2024 - 01 - 28)),
+            ("next week", date!(2024 - 02 - 07)),
+            ("next month", date!(2024 - 02 - 29)),
+            ("next year", date!(2025 - 01 - 31)),
+            ("start of week", date!(2024 - 01 - 29)),
+            ("end of week", date!(2024 - 02 - 04)),
+            ("start of month", date!(2024 - 01 - 01)),
+            ("end of month", date!(2024 - 01 - 31)),
+            ("start of year", date!(2024 - 01 - 01)),
+            ("end of year", date!(2024 - 12 - 31)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(expected, relative_date_parser(input, today)?, "{}", input);
+        }
+
+        // test end of month in a leap february
+        assert_eq!(
+            date!(2024 - 02 - 29),
+            relative_date_parser("end of month", date!(2024 - 02 - 10))?
+        );
+
+        for invalid in [
+            "",
+            "2024-13-01",
+            "2024/01/01",
+            "someday",
+            "+3",
+            "+d",
+            "+3x",
+            "next",
+            "next moon",
+            "in days",
+            "in 3 fortnights",
+            "3 days",
+            "end of",
+            "+99999999999y",
+            "-9223372036854775808 days ago",
+        ] {
+            assert!(relative_date_parser(invalid, today).is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_period_end_parser() -> Result<()> {
+        let today = date!(2024 - 01 - 31);
+
+        assert_eq!(
+            date!(2024 - 02 - 07),
+            relative_period_end_parser("7d", today)?
+        );
+        assert_eq!(
+            date!(2024 - 02 - 14),
+            relative_period_end_parser("2w", today)?
+        );
+        assert_eq!(
+            date!(2024 - 02 - 29),
+            relative_period_end_parser("1m", today)?
+        );
+        assert_eq!(
+            date!(2024 - 02 - 07),
+            relative_period_end_parser("+7d", today)?
+        );
+        assert_eq!(
+            date!(2024 - 02 - 04),
+            relative_period_end_parser("end of week", today)?
+        );
+        assert_eq!(
+            date!(2024 - 03 - 01),
+            relative_period_end_parser("2024-03-01", today)?
+        );
+        assert!(relative_period_end_parser("7x", today).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_age_parser() -> Result<()> {
+        let today = date!(2024 - 03 - 31);
+
+        assert_eq!(today, relative_age_parser("0d", today)?);
+        assert_eq!(date!(2024 - 03 - 17), relative_age_parser("14d", today)?);
+        assert_eq!(date!(2024 - 03 - 17), relative_age_parser("2w", today)?);
+        assert_eq!(date!(2024 - 02 - 29), relative_age_parser("1m", today)?);
+        assert_eq!(date!(2023 - 03 - 31), relative_age_parser(" 1y ", today)?);
+        for invalid in ["", "14", "-14d", "+14d", "14x", "yesterday", "2024-01-01"] {
+            assert!(relative_age_parser(invalid, today).is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_deadline_parser() -> Result<()> {
+        // early morning east of UTC, where the UTC day is still the 30th
+        let now = datetime!(2024 - 01 - 31 01:30 +05:00);
+
+        let cases = [
+            ("2024-02-10", DeadlineEnum::Date(date!(2024 - 02 - 10))),
+            ("today", DeadlineEnum::Date(date!(2024 - 01 - 31))),
+            ("+1d", DeadlineEnum::Date(date!(2024 - 02 - 01))),
+            (
+                "2024-02-10T08:15:00Z",
+                DeadlineEnum::DateTime(datetime!(2024 - 02 - 10 08:15 UTC)),
+            ),
+            (
+                "2024-02-10T08:15",
+                DeadlineEnum::DateTime(datetime!(2024 - 02 - 10 08:15 +05:00)),
+            ),
+            (
+                "2024-02-10 8:15:30",
+                DeadlineEnum::DateTime(datetime!(2024 - 02 - 10 08:15:30 +05:00)),
+            ),
+            (
+                "at 17:00",
+                DeadlineEnum::DateTime(datetime!(2024 - 01 - 31 17:00 +05:00)),
+            ),
+            (
+                "17:00",
+                DeadlineEnum::DateTime(datetime!(2024 - 01 - 31 17:00 +05:00)),
+            ),
+            (
+                "tomorrow 14:00",
+                DeadlineEnum::DateTime(datetime!(2024 - 02 - 01 14:00 +05:00)),
+            ),
+            (
+                "next friday at 9:30",
+                DeadlineEnum::DateTime(datetime!(2024 - 02 - 02 09:30 +05:00)),
+            ),
+            (
+                "end of month 23:59",
+                DeadlineEnum::DateTime(datetime!(2024 - 01 - 31 23:59 +05:00)),
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(expected, relative_deadline_parser(input, now)?, "{}", input);
+        }
+
+        for invalid in ["", "tomorrow 25:00", "someday 14:00", "14"] {
+            assert!(
+                relative_deadline_parser(invalid, now).is_err(),
+                "{}",
+                invalid
+            );
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,38 @@
+-- A data file written before schema versioning, user_version 0.
+-- Made with the todo-cli of the commit before migrations were introduced, the full text
+-- index is rebuilt from its content instead of copying the FTS5 shadow tables.
+CREATE TABLE IF NOT EXISTS "tasks" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "title" text NOT NULL, "info" text, "deadline" text, "status" text NOT NULL, "updated_at" text NOT NULL, "created_at" text NOT NULL );
+INSERT INTO tasks VALUES(1,'Deploy api','Roll out the new release','2026-11-02','undone','2026-10-18','2026-10-18');
+INSERT INTO tasks VALUES(2,'Fix login redirect',NULL,NULL,'undone','2026-10-18','2026-10-18');
+INSERT INTO tasks VALUES(3,'Write docs',NULL,NULL,'done','2026-10-18','2026-10-18');
+CREATE TABLE IF NOT EXISTS "actions" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "action" blob NOT NULL, "restored" boolean NOT NULL, "created_at" text NOT NULL );
+INSERT INTO actions VALUES(1,X'0000020a4465706c6f79206170690118526f6c6c206f757420746865206e65772072656c65617365010a323032362d31312d30320102076261636b656e6406757267656e74010a323032362d31302d31380a323032362d31302d3138',0,'2026-10-18');
+INSERT INTO actions VALUES(2,X'0000040d466978206c6f67696e20627567000001020866726f6e74656e6403627567010a323032362d31302d31380a323032362d31302d3138',0,'2026-10-18');
+INSERT INTO actions VALUES(3,X'0000060a577269746520646f63730000010104646f6373010a323032362d31302d31380a323032362d31302d3138',0,'2026-10-18');
+INSERT INTO actions VALUES(4,X'000008094f6c64207370696b65000000020a323032362d31302d31380a323032362d31302d3138',0,'2026-10-18');
+INSERT INTO actions VALUES(5,X'0001060a577269746520646f6373000000010a323032362d31302d31380a323032362d31302d3138',0,'2026-10-18');
+INSERT INTO actions VALUES(6,X'0001040d466978206c6f67696e20627567000000010a323032362d31302d31380a323032362d31302d3138',0,'2026-10-18');
+INSERT INTO actions VALUES(7,X'0100076261636b656e6406',0,'2026-10-18');
+INSERT INTO actions VALUES(8,X'02036275670662756766697804',0,'2026-10-18');
+INSERT INTO actions VALUES(9,X'010206757267656e7402',0,'2026-10-18');
+INSERT INTO actions VALUES(10,X'04076261636b656e6406736572766572',0,'2026-10-18');
+INSERT INTO actions VALUES(11,X'03010604646f6373',0,'2026-10-18');
+INSERT INTO actions VALUES(12,X'000208094f6c64207370696b6500000100020a323032362d31302d31380a323032362d31302d3138',0,'2026-10-18');
+INSERT INTO actions VALUES(13,X'00020a0753637261746368000000010a323032362d31302d31380a323032362d31302d3138',1,'2026-10-18');
+CREATE TABLE IF NOT EXISTS "task_categories" ( "task_id" integer NOT NULL, "category" text NOT NULL, PRIMARY KEY ("task_id", "category"), FOREIGN KEY ("task_id") REFERENCES "tasks" ("id") );
+INSERT INTO task_categories VALUES(1,'server');
+INSERT INTO task_categories VALUES(2,'frontend');
+INSERT INTO task_categories VALUES(2,'bugfix');
+INSERT INTO task_categories VALUES(3,'server');
+DELETE FROM sqlite_sequence;
+INSERT INTO sqlite_sequence VALUES('tasks',5);
+INSERT INTO sqlite_sequence VALUES('actions',13);
+CREATE INDEX "TASK_DEADLINE_IDX" ON "tasks" ("deadline");
+CREATE INDEX "TASK_STATUS_IDX" ON "tasks" ("status");
+CREATE INDEX "TASK_CREATED_AT_IDX" ON "tasks" ("created_at");
+CREATE INDEX "ACTION_RESTORED_IDX" ON "actions" ("restored");
+CREATE INDEX "TASK_CATEGORY_CATEGORY_IDX" ON "task_categories" ("category");
+CREATE VIRTUAL TABLE tasks_fts USING fts5 (id UNINDEXED, title, info);
+INSERT INTO tasks_fts VALUES(1,'Deploy api','Roll out the new release');
+INSERT INTO tasks_fts VALUES(2,'Fix login redirect',NULL);
+INSERT INTO tasks_fts VALUES(3,'Write docs',NULL);